@@ -0,0 +1,92 @@
+use anyhow::Result;
+use std::path::PathBuf;
+#[cfg(feature = "zip")]
+use std::path::Path;
+
+/// Separates the path of an archive from the name of an entry inside of it,
+/// for instance `logs.zip!server.log`.
+#[cfg(feature = "zip")]
+pub const ENTRY_SEPARATOR: &str = "!";
+
+#[cfg(feature = "zip")]
+const ARCHIVE_EXTENSION: &str = ".zip";
+
+#[cfg(feature = "zip")]
+pub fn is_archive(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Synthetic path of an entry inside an archive.
+#[cfg(feature = "zip")]
+pub fn entry_path(archive: &Path, entry: &str) -> PathBuf {
+    let mut path = archive.as_os_str().to_owned();
+    path.push(ENTRY_SEPARATOR);
+    path.push(entry);
+    PathBuf::from(path)
+}
+
+/// Split a path built by `entry_path` in the path of the archive and the name of the entry.
+#[cfg(feature = "zip")]
+pub fn split_entry_path(path: &str) -> Option<(&str, &str)> {
+    let pattern = format!("{}{}", ARCHIVE_EXTENSION, ENTRY_SEPARATOR);
+    // Lowercasing only changes ascii letters, so the indices are the same in both strings.
+    let idx = path.to_ascii_lowercase().find(&pattern)? + ARCHIVE_EXTENSION.len();
+    Some((&path[..idx], &path[idx + ENTRY_SEPARATOR.len()..]))
+}
+
+/// Returns a path that can be opened by other programs. Entries of archives are
+/// extracted to a temporary directory.
+pub fn local_path(path: &str) -> Result<PathBuf> {
+    #[cfg(feature = "zip")]
+    if let Some((archive, entry)) = split_entry_path(path) {
+        return extract_entry(archive, entry);
+    }
+
+    return Ok(PathBuf::from(path));
+}
+
+#[cfg(feature = "zip")]
+fn extract_entry(archive: &str, entry: &str) -> Result<PathBuf> {
+    use anyhow::anyhow;
+    use std::{
+        collections::hash_map::DefaultHasher,
+        fs,
+        hash::{Hash, Hasher},
+        io,
+    };
+
+    let file_name = Path::new(entry)
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid entry '{}' in archive '{}'", entry, archive))?;
+
+    // Entries with the same name can come from different archives, so each
+    // archive is extracted in its own directory.
+    let mut hasher = DefaultHasher::new();
+    archive.hash(&mut hasher);
+    let mut path = std::env::temp_dir();
+    path.push("search-archives");
+    path.push(format!("{:016x}", hasher.finish()));
+    fs::create_dir_all(&path)?;
+    path.push(file_name);
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(archive)?)?;
+    let mut content = zip.by_name(entry)?;
+    let mut output = fs::File::create(&path)?;
+    io::copy(&mut content, &mut output)?;
+
+    log!("Extracted '{}' from '{}' to '{}'", entry, archive, path.to_string_lossy());
+    return Ok(path);
+}
+
+#[cfg(all(test, feature = "zip"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_entry_paths() {
+        let path = entry_path(Path::new("logs/Old.ZIP"), "dir/server.log");
+        assert_eq!(split_entry_path(&path.to_string_lossy()), Some(("logs/Old.ZIP", "dir/server.log")));
+        assert_eq!(split_entry_path("logs/server.log"), None);
+        assert_eq!(split_entry_path("logs/server.zip"), None);
+    }
+}
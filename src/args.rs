@@ -54,6 +54,42 @@ pub fn parse_args(cmdline: &str) -> Result<Vec<String>> {
     return Ok(results);
 }
 
+/// Split a list on `separator`, except between double quotes, which are removed. Unlike
+/// `parse_args`, backslashes are kept as is, as they separate the directories on Windows.
+/// An unclosed quote goes until the end of the list and empty values are skipped.
+pub fn split_quoted(list: &str, separator: char) -> Vec<String> {
+    let mut results = Vec::new();
+    let mut value = String::new();
+    let mut in_quote = false;
+    for c in list.chars() {
+        if c == '"' {
+            in_quote = !in_quote;
+        } else if c == separator && !in_quote {
+            if !value.is_empty() {
+                results.push(std::mem::take(&mut value));
+            }
+        } else {
+            value.push(c);
+        }
+    }
+
+    if !value.is_empty() {
+        results.push(value);
+    }
+
+    return results;
+}
+
+/// Join the values with `separator`, quoting the ones containing it such that
+/// `split_quoted` gives them back.
+pub fn join_quoted<'a>(values: impl IntoIterator<Item = &'a str>, separator: char) -> String {
+    let values: Vec<String> = values
+        .into_iter()
+        .map(|value| if value.contains(separator) { format!("\"{}\"", value) } else { value.to_owned() })
+        .collect();
+    return values.join(&separator.to_string());
+}
+
 #[cfg(test)]
 mod tests {
     fn chk(cmdline: &str, expected: &[&'static str]) {
@@ -80,6 +116,18 @@ mod tests {
         chk(r#"EXE a\\\\"b c" d e"#, &["EXE", r"a\\b c", "d", "e"]);
     }
 
+    #[test]
+    fn quoted_lists() {
+        assert_eq!(super::split_quoted(r#"src;"C:\my;dir";;C:\Program Files"#, ';'), vec!["src", r"C:\my;dir", r"C:\Program Files"]);
+        assert_eq!(super::split_quoted(r#""a;b"#, ';'), vec!["a;b"]);
+        assert!(super::split_quoted(r#";"";"#, ';').is_empty());
+
+        let values = ["src", r"C:\my;dir", "a b"];
+        let joined = super::join_quoted(values, ';');
+        assert_eq!(joined, r#"src;"C:\my;dir";a b"#);
+        assert_eq!(super::split_quoted(&joined, ';'), values);
+    }
+
     #[test]
     fn invalid_examples() {
         super::parse_args(r#"EXE \"#).unwrap_err();
@@ -0,0 +1,254 @@
+use anyhow::{bail, Result};
+use std::io::{IsTerminal, Read, Write};
+
+use crate::{
+    search::{self, EntryKind, SearchConfig, SearchQuery, SearchResult},
+    settings::Settings,
+};
+
+/// Options given on the command line, used to fill the first tab or to search without
+/// a window. This allows shell integrations, such as a "Search with Search" entry in a
+/// context menu, and scripting.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    /// List of paths separated by a semicolon ';', same as `SearchConfig::paths`.
+    pub paths: Option<String>,
+    /// List of globs separated by a space, same as `SearchConfig::globs`.
+    pub patterns: Option<String>,
+    /// Query searched as soon as the application starts.
+    pub query: Option<String>,
+    /// Print the results to stdout, without opening the window.
+    pub print: bool,
+    /// Set by `--ignore-case` or `--case-sensitive`, the query keeps its option otherwise.
+    pub ignore_case: Option<bool>,
+    /// Whether the query uses the regex syntax, rather than being a literal. Set by
+    /// `--regex` or `--fixed-strings`, the query keeps its option otherwise.
+    pub regex: Option<bool>,
+    /// Whether hidden files and directories are searched.
+    pub hidden: bool,
+}
+
+impl Args {
+    /// Apply the switches given on the command line to the configuration.
+    pub fn apply_switches(&self, config: &mut SearchConfig) {
+        config.hidden |= self.hidden;
+        if let Some(query) = config.queries.first_mut() {
+            query.ignore_case = self.ignore_case.unwrap_or(query.ignore_case);
+            query.regex_syntax = self.regex.unwrap_or(query.regex_syntax);
+        }
+    }
+}
+
+pub const USAGE: &str = "Usage: search [--paths <paths>] [--patterns <globs>] [--query <query>] [--print] [--ignore-case | --case-sensitive] [--regex | --fixed-strings] [--hidden]";
+
+/// Whether the arguments ask to print the results, checked when they can't be parsed
+/// to report the error on stderr rather than opening the window.
+pub fn wants_print(args: &[String]) -> bool {
+    return args.iter().any(|arg| arg == "--print" || arg == "--no-gui");
+}
+
+/// Parse the arguments, without the name of the program. Values are given either
+/// as `--query value` or as `--query=value`.
+pub fn parse_command_line(args: impl IntoIterator<Item = String>) -> Result<Args> {
+    let mut result = Args::default();
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_owned(), Some(value.to_owned())),
+            _ => (arg.clone(), None),
+        };
+
+        let switch = match arg.as_str() {
+            "--print" | "--no-gui" => Some(&mut result.print),
+            "--hidden" => Some(&mut result.hidden),
+            _ => None,
+        };
+
+        if let Some(switch) = switch {
+            *switch = true;
+            continue;
+        }
+
+        let option = match arg.as_str() {
+            "-i" | "--ignore-case" => Some((&mut result.ignore_case, true)),
+            "-s" | "--case-sensitive" => Some((&mut result.ignore_case, false)),
+            "--regex" => Some((&mut result.regex, true)),
+            "-F" | "--fixed-strings" => Some((&mut result.regex, false)),
+            _ => None,
+        };
+
+        if let Some((option, value)) = option {
+            *option = Some(value);
+            continue;
+        }
+
+        let slot = match flag.as_str() {
+            "--paths" => &mut result.paths,
+            "--patterns" => &mut result.patterns,
+            "--query" => &mut result.query,
+            _ => bail!("Unknown argument '{}'\n{}", arg, USAGE),
+        };
+
+        match inline.or_else(|| it.next()) {
+            Some(value) => *slot = Some(value),
+            None => bail!("Expected a value after '{}'\n{}", flag, USAGE),
+        }
+    }
+
+    return Ok(result);
+}
+
+/// Read the input piped to the application, as in `cat file | search --print --query foo`.
+/// Returns `None` if stdin is a terminal or if nothing was piped.
+pub fn read_piped_stdin() -> Option<Vec<u8>> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    if let Err(err) = stdin.lock().read_to_end(&mut bytes) {
+        log!("Failed to read stdin, error: {}", err);
+        return None;
+    }
+
+    return (!bytes.is_empty()).then_some(bytes);
+}
+
+/// Print the result like grep, "path:line:text" for the matched lines and "path-line-text"
+/// for the context lines.
+fn print_result(output: &mut impl Write, result: &SearchResult) -> std::io::Result<()> {
+    let path = result.path.to_string_lossy();
+    for entry in result.entries.iter() {
+        if entry.context_break {
+            writeln!(output, "--")?;
+        }
+
+        let text = String::from_utf8_lossy(search::trim_line_terminator(&entry.bytes));
+        match entry.kind {
+            EntryKind::Match => writeln!(output, "{}:{}:{}", path, entry.line_number, text)?,
+            EntryKind::Context => writeln!(output, "{}-{}-{}", path, entry.line_number, text)?,
+            EntryKind::FileName => writeln!(output, "{}", path)?,
+        }
+    }
+
+    return Ok(());
+}
+
+/// Search and print the results without opening the window. Returns the exit code,
+/// which is 0 if something matched, 1 if nothing matched and 2 on errors, even if
+/// something matched, like grep.
+/// The piped input, if any, is searched rather than the paths. The query starts from the
+/// default options rather than the ones saved for the window, such that scripts get the
+/// same output whatever was set in the window.
+pub fn run_headless(args: Args, settings: &Settings, stdin: Option<Vec<u8>>) -> i32 {
+    let query = match &args.query {
+        Some(query) => query.clone(),
+        None => {
+            eprintln!("Expected a query to print the results\n{}", USAGE);
+            return 2;
+        }
+    };
+
+    let mut config = SearchConfig::with_paths(String::from("."));
+    config.queries = vec![SearchQuery::new()];
+    args.apply_switches(&mut config);
+    config.paths = args.paths.unwrap_or(config.paths);
+    config.globs = args.patterns.unwrap_or_default();
+    config.queries[0].query = query;
+
+    let stdout = std::io::stdout();
+    let mut output = std::io::BufWriter::new(stdout.lock());
+    let mut matched = false;
+    let mut failed = false;
+    let mut on_result = |result: SearchResult| {
+        if let Some(error) = &result.error {
+            eprintln!("{}: {}", result.path.to_string_lossy(), error);
+            failed = true;
+        }
+
+        matched |= !result.entries.is_empty();
+        if let Err(err) = print_result(&mut output, &result) {
+            eprintln!("Failed to print the results, error: {}", err);
+            failed = true;
+        }
+    };
+
+    let searched = match stdin {
+        Some(bytes) => {
+            let mut workers = config.workers();
+            on_result(search::search_stdin(&mut workers, config.combination, &bytes, settings.binary_mode));
+            Ok(())
+        }
+        None => search::search_blocking(
+            &config,
+            settings.binary_mode,
+            settings.symlink_mode,
+            settings.search_archives,
+            settings.number_of_threads,
+            &mut on_result,
+        ),
+    };
+
+    let _ = output.flush();
+    if let Err(err) = searched {
+        eprintln!("{}", err);
+        return 2;
+    }
+
+    return if failed { 2 } else if matched { 0 } else { 1 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        parse_command_line(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_valid_command_lines() {
+        assert_eq!(parse(&[]).unwrap(), Args::default());
+
+        let args = parse(&["--paths", "src;docs", "--patterns=*.rs *.md", "--query", "a=b"]).unwrap();
+        assert_eq!(args.paths.as_deref(), Some("src;docs"));
+        assert_eq!(args.patterns.as_deref(), Some("*.rs *.md"));
+        assert_eq!(args.query.as_deref(), Some("a=b"));
+        assert!(!args.print);
+
+        let args = parse(&["--no-gui", "-i", "--regex", "--query", "a+"]).unwrap();
+        assert!(args.print && !args.hidden);
+        assert_eq!((args.ignore_case, args.regex), (Some(true), Some(true)));
+
+        let args = parse(&["--case-sensitive", "-F", "--query", "a+"]).unwrap();
+        assert_eq!((args.ignore_case, args.regex), (Some(false), Some(false)));
+    }
+
+    #[test]
+    fn switches_applied_to_the_config() {
+        let mut config = SearchConfig::with_paths(String::from("."));
+        parse(&["-i", "--hidden"]).unwrap().apply_switches(&mut config);
+        assert!(config.hidden && config.queries[0].ignore_case && !config.queries[0].regex_syntax);
+
+        let mut config = SearchConfig::with_paths(String::from("."));
+        config.queries[0].ignore_case = false;
+        parse(&["--regex"]).unwrap().apply_switches(&mut config);
+        assert!(!config.hidden && !config.queries[0].ignore_case && config.queries[0].regex_syntax);
+
+        parse(&["-i", "--fixed-strings"]).unwrap().apply_switches(&mut config);
+        assert!(config.queries[0].ignore_case && !config.queries[0].regex_syntax);
+        parse(&["--case-sensitive"]).unwrap().apply_switches(&mut config);
+        assert!(!config.queries[0].ignore_case && !config.queries[0].regex_syntax);
+    }
+
+    #[test]
+    fn parse_invalid_command_lines() {
+        assert!(parse(&["--query"]).is_err());
+        assert!(parse(&["--unknown", "value"]).is_err());
+        assert!(parse(&["needle"]).is_err());
+
+        assert!(wants_print(&["--query".to_owned(), "a".to_owned(), "--no-gui".to_owned()]));
+        assert!(!wants_print(&["--query".to_owned(), "--bad".to_owned()]));
+    }
+}
@@ -1,6 +1,10 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use crate::args;
-use std::{collections::HashMap, process::Command};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 fn replace(argument: &str, replacements: &HashMap<String, String>) -> Result<String> {
     let mut result = String::with_capacity(argument.len());
@@ -51,24 +55,171 @@ fn replace(argument: &str, replacements: &HashMap<String, String>) -> Result<Str
     Ok(result)
 }
 
-pub fn build_command(editor: &str, file_path: String, line_number: usize) -> Result<Command> {
+/// Command starting the editor, with the files it opens such that they can be opened with
+/// the default application if the editor can't be started.
+pub struct EditorCommand {
+    pub command: Command,
+    pub files: Vec<PathBuf>,
+}
+
+/// Returns the directory containing `file_path`, if it's not empty.
+fn parent_dir(file_path: &str) -> Option<&Path> {
+    Path::new(file_path)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+}
+
+pub fn build_command(editor: &str, file_path: String, line_number: usize, run_in_file_dir: bool) -> Result<Command> {
     let arguments = args::parse_args(editor)?;
     if let Some((editor, arguments)) = arguments.split_first() {
+        let dir = parent_dir(&file_path).map(|dir| dir.to_path_buf());
+
         let mut replacements = HashMap::new();
         replacements.insert(String::from("file"), file_path);
         replacements.insert(String::from("line"), format!("{}", line_number));
+        replacements.insert(
+            String::from("dir"),
+            dir.as_ref().map_or(String::from("."), |dir| dir.to_string_lossy().into_owned()),
+        );
 
         let mut command = Command::new(editor);
         for argument in arguments.iter() {
             command.arg(replace(argument, &replacements)?);
         }
 
+        if run_in_file_dir {
+            if let Some(dir) = dir {
+                command.current_dir(dir);
+            }
+        }
+
+        return Ok(command);
+    }
+
+    bail!("Expected a path to a program");
+}
+
+/// Build the command opening a terminal in the directory of `file_path`, which is also
+/// given as `{dir}` for terminals that don't start in their working directory.
+pub fn build_terminal_command(terminal: &str, file_path: &str) -> Result<Command> {
+    let arguments = args::parse_args(terminal)?;
+    if let Some((terminal, arguments)) = arguments.split_first() {
+        let dir = parent_dir(file_path).unwrap_or(Path::new("."));
+
+        let mut replacements = HashMap::new();
+        replacements.insert(String::from("dir"), dir.to_string_lossy().into_owned());
+
+        let mut command = Command::new(terminal);
+        for argument in arguments.iter() {
+            command.arg(replace(argument, &replacements)?);
+        }
+        command.current_dir(dir);
+
+        return Ok(command);
+    }
+
+    bail!("Expected a path to a program");
+}
+
+/// Start the command without waiting for it to exit.
+fn spawn_detached(mut command: Command, program: &str) -> Result<()> {
+    let mut child = command
+        .spawn()
+        .map_err(|err| anyhow!("Failed to start '{}', error: {}", program, err))?;
+
+    // Must be waited for to not leave a zombie process once closed.
+    std::thread::spawn(move || child.wait());
+    return Ok(());
+}
+
+/// Open a terminal in the directory of `file_path`, without waiting for it to be closed.
+pub fn open_terminal(terminal: &str, file_path: &str) -> Result<()> {
+    return spawn_detached(build_terminal_command(terminal, file_path)?, terminal);
+}
+
+/// Build the command opening `file_path` in a hex editor, with `{file}` and `{offset}`
+/// replaced by the path and the offset, in bytes, of the match.
+pub fn build_hex_editor_command(hex_editor: &str, file_path: &str, offset: u64) -> Result<Command> {
+    let arguments = args::parse_args(hex_editor)?;
+    if let Some((hex_editor, arguments)) = arguments.split_first() {
+        let mut replacements = HashMap::new();
+        replacements.insert(String::from("file"), file_path.to_owned());
+        replacements.insert(String::from("offset"), offset.to_string());
+
+        let mut command = Command::new(hex_editor);
+        for argument in arguments.iter() {
+            command.arg(replace(argument, &replacements)?);
+        }
+
         return Ok(command);
     }
 
     bail!("Expected a path to a program");
 }
 
+/// Open `file_path` in a hex editor at `offset`, without waiting for it to be closed.
+pub fn open_hex_editor(hex_editor: &str, file_path: &str, offset: u64) -> Result<()> {
+    return spawn_detached(build_hex_editor_command(hex_editor, file_path, offset)?, hex_editor);
+}
+
+/// Whether the editor command opens every file in a single invocation, using `{files}`.
+pub fn is_multi_file_editor(editor: &str) -> bool {
+    editor.contains("{files}")
+}
+
+/// Build a single command opening every file. Each argument containing `{files}` is
+/// repeated for every file, with `{files}` replaced by "path:line".
+pub fn build_command_multi(editor: &str, files: Vec<(String, usize)>) -> Result<Command> {
+    let arguments = args::parse_args(editor)?;
+    if let Some((editor, arguments)) = arguments.split_first() {
+        let mut command = Command::new(editor);
+        let mut replacements = HashMap::new();
+        for argument in arguments.iter() {
+            if !is_multi_file_editor(argument) {
+                command.arg(replace(argument, &replacements)?);
+                continue;
+            }
+
+            for (file_path, line_number) in files.iter() {
+                replacements.insert(String::from("files"), format!("{}:{}", file_path, line_number));
+                command.arg(replace(argument, &replacements)?);
+            }
+            replacements.clear();
+        }
+
+        return Ok(command);
+    }
+
+    bail!("Expected a path to a program");
+}
+
+/// Percent-encode the path such that it can be part of an URL, the separators are kept.
+/// The path always starts with a '/', such that `C:\x` becomes `/C:/x` as in `file:///C:/x`.
+fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len() + 1);
+    if !path.starts_with(['/', '\\']) {
+        encoded.push('/');
+    }
+    for value in path.bytes() {
+        match value {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => encoded.push(value as char),
+            b'\\' => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", value)),
+        }
+    }
+    encoded
+}
+
+/// Build an URL to the location from a template, such as `vscode://file{path}:{line}`.
+/// The path is made absolute and percent-encoded.
+pub fn build_location_url(template: &str, file_path: &str, line_number: usize) -> Result<String> {
+    let path = std::path::absolute(file_path)?;
+    let mut replacements = HashMap::new();
+    replacements.insert(String::from("path"), encode_url_path(&path.to_string_lossy()));
+    replacements.insert(String::from("line"), format!("{}", line_number));
+    return replace(template, &replacements);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,9 +270,9 @@ mod tests {
     fn building_command_without_editor() {
         let file = String::from("/home");
         let line = 10;
-        build_command("", file.clone(), line).unwrap_err();
+        build_command("", file.clone(), line, false).unwrap_err();
 
-        let cmd = build_command("{file} {line}", file.clone(), line).unwrap();
+        let cmd = build_command("{file} {line}", file.clone(), line, false).unwrap();
         assert_eq!(cmd.get_program(), std::ffi::OsStr::new("{file}"));
     }
 
@@ -132,17 +283,109 @@ mod tests {
         let file = String::from("/home");
         let line = 10;
 
-        let cmd = build_command("/usr/bin/editor {file} {line}", file.clone(), line).unwrap();
+        let cmd = build_command("/usr/bin/editor {file} {line}", file.clone(), line, false).unwrap();
         assert_eq!(cmd.get_program(), OsStr::new("/usr/bin/editor"));
         let arguments: Vec<&OsStr> = cmd.get_args().collect();
         assert_eq!(arguments.len(), 2);
         assert_eq!(arguments[0], OsStr::new("/home"));
         assert_eq!(arguments[1], OsStr::new("10"));
 
-        let cmd = build_command("subl {file}:{line}", file.clone(), line).unwrap();
+        let cmd = build_command("subl {file}:{line}", file.clone(), line, false).unwrap();
         assert_eq!(cmd.get_program(), OsStr::new("subl"));
         let arguments: Vec<&OsStr> = cmd.get_args().collect();
         assert_eq!(arguments.len(), 1);
         assert_eq!(arguments[0], OsStr::new("/home:10"));
     }
+
+    #[test]
+    fn building_command_with_dir() {
+        use std::ffi::OsStr;
+
+        let cmd = build_command("editor {dir}", String::from("/home/foo/bar.txt"), 1, true).unwrap();
+        let arguments: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(arguments, vec![OsStr::new("/home/foo")]);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/home/foo")));
+
+        let cmd = build_command("editor {dir}", String::from("/home/foo/bar.txt"), 1, false).unwrap();
+        assert_eq!(cmd.get_current_dir(), None);
+    }
+
+    #[test]
+    fn building_hex_editor_command() {
+        use std::ffi::OsStr;
+
+        let cmd = build_hex_editor_command("hexedit --offset {offset} {file}", "/home/foo.bin", 4096).unwrap();
+        assert_eq!(cmd.get_program(), OsStr::new("hexedit"));
+        let arguments: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(arguments, vec![OsStr::new("--offset"), OsStr::new("4096"), OsStr::new("/home/foo.bin")]);
+
+        build_hex_editor_command("", "/home/foo.bin", 0).unwrap_err();
+        build_hex_editor_command("hexedit {line}", "/home/foo.bin", 0).unwrap_err();
+    }
+
+    #[test]
+    fn building_terminal_command() {
+        use std::ffi::OsStr;
+
+        let cmd = build_terminal_command("wt -d {dir}", "/home/foo/bar.txt").unwrap();
+        assert_eq!(cmd.get_program(), OsStr::new("wt"));
+        let arguments: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(arguments, vec![OsStr::new("-d"), OsStr::new("/home/foo")]);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/home/foo")));
+
+        let cmd = build_terminal_command("x-terminal-emulator", "bar.txt").unwrap();
+        assert_eq!(cmd.get_args().count(), 0);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new(".")));
+
+        build_terminal_command("", "bar.txt").unwrap_err();
+    }
+
+    #[test]
+    fn building_command_with_file_without_parent() {
+        use std::ffi::OsStr;
+
+        let cmd = build_command("editor {dir}", String::from("bar.txt"), 1, true).unwrap();
+        let arguments: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(arguments, vec![OsStr::new(".")]);
+        assert_eq!(cmd.get_current_dir(), None);
+
+        let cmd = build_command("editor {file}", String::from("/"), 1, true).unwrap();
+        assert_eq!(cmd.get_current_dir(), None);
+    }
+
+    #[test]
+    fn building_multi_file_command() {
+        use std::ffi::OsStr;
+
+        assert!(is_multi_file_editor("code -g {files}"));
+        assert!(!is_multi_file_editor("code -g {file}:{line}"));
+
+        let files = vec![(String::from("/home/a.rs"), 3), (String::from("b.rs"), 10)];
+        let cmd = build_command_multi("code --reuse-window -g {files}", files.clone()).unwrap();
+        assert_eq!(cmd.get_program(), OsStr::new("code"));
+        let arguments: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(arguments, vec![
+            OsStr::new("--reuse-window"),
+            OsStr::new("-g"),
+            OsStr::new("/home/a.rs:3"),
+            OsStr::new("b.rs:10"),
+        ]);
+
+        build_command_multi("", files.clone()).unwrap_err();
+        build_command_multi("code {file} {files}", files).unwrap_err();
+    }
+
+    #[test]
+    fn building_location_urls() {
+        let url = build_location_url("vscode://file{path}:{line}", "/home/my project/café.rs", 12).unwrap();
+        assert_eq!(url, "vscode://file/home/my%20project/caf%C3%A9.rs:12");
+
+        let url = build_location_url("file://{path}", "/home/a#b", 1).unwrap();
+        assert_eq!(url, "file:///home/a%23b");
+
+        build_location_url("file://{file}", "/home", 1).unwrap_err();
+
+        assert_eq!(encode_url_path("C:\\Users\\my project\\a.rs"), "/C:/Users/my%20project/a.rs");
+        assert_eq!(encode_url_path("/home/a.rs"), "/home/a.rs");
+    }
 }
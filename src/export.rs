@@ -0,0 +1,167 @@
+use crate::search::{self, EntryKind};
+
+/// Result to export, with the path as it's displayed.
+pub struct ExportRow<'a> {
+    pub path: &'a str,
+    pub line_number: u64,
+    pub kind: EntryKind,
+    pub bytes: &'a [u8],
+    /// Byte ranges, in `bytes`, of the capture groups of the line.
+    pub captures: &'a [Option<(usize, usize)>],
+}
+
+impl ExportRow<'_> {
+    fn text(&self) -> String {
+        return String::from_utf8_lossy(search::trim_line_terminator(self.bytes)).into_owned();
+    }
+}
+
+/// Escape the characters that would end a cell or start a code span in a Markdown table.
+fn escape_table_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for value in text.chars() {
+        match value {
+            '|' | '`' | '\\' => {
+                escaped.push('\\');
+                escaped.push(value);
+            }
+            _ => escaped.push(value),
+        }
+    }
+    return escaped;
+}
+
+/// Format the results as a Markdown table with the columns File, Line and Text.
+pub fn markdown_table<'a>(rows: impl IntoIterator<Item = ExportRow<'a>>) -> String {
+    let mut content = String::from("| File | Line | Text |\n| --- | --- | --- |\n");
+    for row in rows.into_iter() {
+        let (line, text) = match row.kind {
+            EntryKind::FileName => (String::new(), String::new()),
+            _ => (row.line_number.to_string(), escape_table_cell(&row.text())),
+        };
+        content.push_str(&format!("| {} | {} | {} |\n", escape_table_cell(row.path), line, text));
+    }
+    return content;
+}
+
+/// Quote the field if it contains a separator, a quote or a line break, as in RFC 4180.
+fn escape_csv_field(text: &str) -> String {
+    if !text.contains([',', '"', '\n', '\r']) {
+        return text.to_owned();
+    }
+    return format!("\"{}\"", text.replace('"', "\"\""));
+}
+
+/// Format the capture groups of the matched lines as CSV, with the columns File, Line and
+/// one per group. Lines without captures are skipped, groups that didn't match are empty.
+pub fn captures_csv<'a>(rows: impl IntoIterator<Item = ExportRow<'a>>, groups: usize) -> String {
+    let mut content = String::from("File,Line");
+    for group in 1..=groups {
+        content.push_str(&format!(",Group {}", group));
+    }
+    content.push('\n');
+
+    for row in rows.into_iter().filter(|row| row.kind == EntryKind::Match && !row.captures.is_empty()) {
+        content.push_str(&format!("{},{}", escape_csv_field(row.path), row.line_number));
+        for group in 0..groups {
+            let text = match row.captures.get(group).copied().flatten() {
+                Some((start, end)) => String::from_utf8_lossy(&row.bytes[start..end]).into_owned(),
+                None => String::new(),
+            };
+            content.push(',');
+            content.push_str(&escape_csv_field(&text));
+        }
+        content.push('\n');
+    }
+    return content;
+}
+
+/// Format the results as a fenced code block per file, preceded by its path. Matched lines
+/// are written "line: text" and context lines "line- text", like grep.
+pub fn markdown_code_blocks<'a>(rows: impl IntoIterator<Item = ExportRow<'a>>) -> String {
+    let mut content = String::new();
+    let mut files: Vec<(&str, Vec<String>)> = Vec::new();
+    for row in rows.into_iter() {
+        if files.last().is_none_or(|(path, _)| *path != row.path) {
+            files.push((row.path, Vec::new()));
+        }
+
+        let lines = &mut files.last_mut().unwrap().1;
+        match row.kind {
+            EntryKind::Match => lines.push(format!("{}: {}", row.line_number, row.text())),
+            EntryKind::Context => lines.push(format!("{}- {}", row.line_number, row.text())),
+            EntryKind::FileName => {}
+        }
+    }
+
+    for (path, lines) in files.iter() {
+        if !content.is_empty() {
+            content.push('\n');
+        }
+
+        content.push_str(&format!("`{}`\n", path.replace('`', "'")));
+        if lines.is_empty() {
+            continue;
+        }
+
+        // The fence must be longer than any run of backticks in the block to not be closed by it.
+        let longest_run = lines
+            .iter()
+            .flat_map(|line| line.split(|value| value != '`'))
+            .map(|run| run.len())
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat(longest_run.max(2) + 1);
+        content.push_str(&format!("{}\n{}\n{}\n", fence, lines.join("\n"), fence));
+    }
+    return content;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row<'a>(path: &'a str, line_number: u64, kind: EntryKind, bytes: &'a [u8]) -> ExportRow<'a> {
+        ExportRow { path, line_number, kind, bytes, captures: &[] }
+    }
+
+    #[test]
+    fn export_markdown_table() {
+        let rows = vec![
+            row("src/main.rs", 3, EntryKind::Match, b"let a = b | `c`;\n"),
+            row("src/README", 0, EntryKind::FileName, b"README"),
+        ];
+
+        assert_eq!(
+            markdown_table(rows),
+            "| File | Line | Text |\n| --- | --- | --- |\n| src/main.rs | 3 | let a = b \\| \\`c\\`; |\n| src/README |  |  |\n"
+        );
+    }
+
+    #[test]
+    fn export_captures_csv() {
+        let captures = [Some((0, 5)), None, Some((7, 13))];
+        let rows = vec![
+            ExportRow { path: "logs/a,b.log", line_number: 4, kind: EntryKind::Match, bytes: b"ERROR: \"disk\"\n", captures: &captures },
+            row("logs/a,b.log", 5, EntryKind::Match, b"no groups\n"),
+            row("logs/a,b.log", 6, EntryKind::Context, b"context\n"),
+        ];
+
+        assert_eq!(captures_csv(rows, 3), "File,Line,Group 1,Group 2,Group 3\n\"logs/a,b.log\",4,ERROR,,\"\"\"disk\"\"\"\n");
+    }
+
+    #[test]
+    fn export_markdown_code_blocks() {
+        let rows = vec![
+            row("a.rs", 1, EntryKind::Context, b"fn main() {\r\n"),
+            row("a.rs", 2, EntryKind::Match, b"    foo();\n"),
+            row("b.md", 7, EntryKind::Match, b"use ```rust``` blocks\n"),
+            row("c.txt", 0, EntryKind::FileName, b"c.txt"),
+        ];
+
+        assert_eq!(
+            markdown_code_blocks(rows),
+            "`a.rs`\n```\n1- fn main() {\n2:     foo();\n```\n\n`b.md`\n````\n7: use ```rust``` blocks\n````\n\n`c.txt`\n"
+        );
+    }
+}
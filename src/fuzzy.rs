@@ -0,0 +1,160 @@
+/// Query matched as a subsequence of the file names, like fzf. Every character of the
+/// pattern must be found in order, but other characters can be in between.
+#[derive(Debug, Clone)]
+pub struct FuzzyPattern {
+    chars: Vec<char>,
+    ignore_case: bool,
+}
+
+/// Match of a pattern in a text, the higher the score the better the match.
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: u32,
+    /// Byte ranges of the matched characters, contiguous characters are in the same range.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 10;
+const PENALTY_GAP: i64 = 1;
+
+fn is_boundary(previous: Option<char>, current: char) -> bool {
+    match previous {
+        None => true,
+        Some(previous) => {
+            matches!(previous, '_' | '-' | '.' | ' ' | '/' | '\\')
+                || (previous.is_lowercase() && current.is_uppercase())
+                || (!previous.is_ascii_digit() && current.is_ascii_digit())
+        }
+    }
+}
+
+impl FuzzyPattern {
+    /// Whitespace is ignored, such that "main rs" matches "main.rs".
+    pub fn new(pattern: &str, ignore_case: bool) -> Self {
+        let chars = pattern.chars().filter(|value| !value.is_whitespace()).collect();
+        Self { chars, ignore_case }
+    }
+
+    fn eq(&self, left: char, right: char) -> bool {
+        if self.ignore_case {
+            left.to_lowercase().eq(right.to_lowercase())
+        } else {
+            left == right
+        }
+    }
+
+    /// Find the pattern in the text. The first occurrence of the subsequence is found, then
+    /// it's shortened from its end, such that the matched characters are as close as possible.
+    pub fn find(&self, text: &str) -> Option<FuzzyMatch> {
+        if self.chars.is_empty() {
+            return None;
+        }
+
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+        // Index, in `chars`, of the last character of the first occurrence.
+        let mut next = 0;
+        let mut end = None;
+        for (idx, (_, value)) in chars.iter().enumerate() {
+            if self.eq(self.chars[next], *value) {
+                next += 1;
+                if next == self.chars.len() {
+                    end = Some(idx);
+                    break;
+                }
+            }
+        }
+        let end = end?;
+
+        // Going backward from the end gives the latest start, so the shortest range.
+        let mut positions = Vec::with_capacity(self.chars.len());
+        let mut remaining = self.chars.len();
+        for idx in (0..=end).rev() {
+            if self.eq(self.chars[remaining - 1], chars[idx].1) {
+                positions.push(idx);
+                remaining -= 1;
+                if remaining == 0 {
+                    break;
+                }
+            }
+        }
+        positions.reverse();
+
+        let mut score = 0;
+        let mut consecutive = 0;
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for (idx, position) in positions.iter().enumerate() {
+            let (offset, value) = chars[*position];
+            let previous = position.checked_sub(1).map(|previous| chars[previous].1);
+
+            score += SCORE_MATCH;
+            if is_boundary(previous, value) {
+                score += BONUS_BOUNDARY;
+            }
+
+            if idx > 0 && positions[idx - 1] + 1 == *position {
+                consecutive += 1;
+                score += BONUS_CONSECUTIVE * consecutive;
+            } else {
+                consecutive = 0;
+                if idx > 0 {
+                    score -= PENALTY_GAP * (position - positions[idx - 1] - 1) as i64;
+                }
+            }
+
+            let range_end = offset + value.len_utf8();
+            match ranges.last_mut() {
+                Some(last) if last.1 == offset => last.1 = range_end,
+                _ => ranges.push((offset, range_end)),
+            }
+        }
+
+        return Some(FuzzyMatch { score: score.max(1) as u32, ranges });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> u32 {
+        FuzzyPattern::new(pattern, true).find(text).unwrap().score
+    }
+
+    #[test]
+    fn subsequence_of_the_text() {
+        let pattern = FuzzyPattern::new("srch", true);
+        assert_eq!(pattern.find("search.rs").unwrap().ranges, vec![(0, 1), (3, 6)]);
+        assert!(pattern.find("settings.rs").is_none());
+        assert!(FuzzyPattern::new("", true).find("main.rs").is_none());
+
+        let pattern = FuzzyPattern::new("Main", false);
+        assert!(pattern.find("main.rs").is_none());
+        assert!(pattern.find("Main.rs").is_some());
+    }
+
+    #[test]
+    fn shortest_occurrence_is_matched() {
+        let pattern = FuzzyPattern::new("ab", true);
+        assert_eq!(pattern.find("a_xab").unwrap().ranges, vec![(3, 5)]);
+    }
+
+    #[test]
+    fn better_matches_have_higher_scores() {
+        // Consecutive characters.
+        assert!(score("main", "main.rs") > score("main", "my_animation.rs"));
+        // Start of words.
+        assert!(score("sw", "search_worker.rs") > score("sw", "answer.rs"));
+        assert!(score("sw", "SearchWorker.rs") > score("sw", "answer.rs"));
+        // Shorter gaps.
+        assert!(score("ab", "a_b") > score("ab", "a___b"));
+    }
+
+    #[test]
+    fn whitespace_and_multibyte_characters() {
+        let pattern = FuzzyPattern::new("é r", true);
+        assert_eq!(pattern.find("café.rs").unwrap().ranges, vec![(3, 5), (6, 7)]);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{bail, Result};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn run_git(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(root).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.trim());
+    }
+    return Ok(output.stdout);
+}
+
+/// Parse the output of `git status --porcelain -z`, returning the paths relative to the
+/// top-level of the repository. Deleted files are skipped, as they can't be searched.
+fn parse_status(output: &[u8]) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut it = output.split(|byte| *byte == 0).filter(|entry| !entry.is_empty());
+    while let Some(entry) = it.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+
+        let (status, path) = (&entry[..2], &entry[3..]);
+        // Renames and copies are followed by the original path, which isn't needed.
+        if status.contains(&b'R') || status.contains(&b'C') {
+            it.next();
+        }
+
+        if status.contains(&b'D') {
+            continue;
+        }
+
+        paths.push(String::from_utf8_lossy(path).into_owned());
+    }
+
+    return paths;
+}
+
+/// Parse the output of `git ls-files -z`, the paths relative to the directory git ran in.
+fn parse_ls_files(output: &[u8]) -> Vec<String> {
+    return output
+        .split(|byte| *byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .collect();
+}
+
+/// Path under `root` of a path relative to the top-level of the repository, where `prefix` is
+/// the path of `root` relative to the top-level, as given by `git rev-parse --show-prefix`.
+/// The paths are then shaped like the paths found by walking `root`.
+fn path_under_root(root: &Path, prefix: &str, path: &str) -> Option<PathBuf> {
+    return path.strip_prefix(prefix).map(|path| root.join(path));
+}
+
+/// Files modified, added or untracked under `root`, according to `git status`. Fails if
+/// `root` isn't in a git repository or if git isn't installed.
+pub fn modified_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let prefix = run_git(root, &["rev-parse", "--show-prefix"])?;
+    let prefix = String::from_utf8_lossy(&prefix).trim_end_matches(['\n', '\r']).to_owned();
+
+    let status = run_git(root, &["status", "--porcelain", "-z", "--untracked-files=all", "--", "."])?;
+    let files = parse_status(&status)
+        .into_iter()
+        .filter_map(|path| path_under_root(root, &prefix, &path))
+        .filter(|path| path.is_file())
+        .collect();
+
+    return Ok(files);
+}
+
+/// Files tracked by git under `root`, according to `git ls-files`. Tracked files deleted from
+/// the working tree are skipped. Fails if `root` isn't in a git repository or if git isn't installed.
+pub fn tracked_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let output = run_git(root, &["ls-files", "-z", "--cached", "--", "."])?;
+    let files = parse_ls_files(&output)
+        .into_iter()
+        .map(|path| root.join(path))
+        .filter(|path| path.is_file())
+        .collect();
+
+    return Ok(files);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_porcelain_status() {
+        let output = b" M src/main.rs\0?? notes.txt\0D  removed.rs\0R  new.rs\0old.rs\0A  dir/with space.rs\0";
+        assert_eq!(parse_status(output), vec!["src/main.rs", "notes.txt", "new.rs", "dir/with space.rs"]);
+        assert!(parse_status(b"").is_empty());
+    }
+
+    #[test]
+    fn status_paths_under_the_root() {
+        let root = Path::new("repo/src");
+        assert_eq!(path_under_root(root, "src/", "src/main.rs"), Some(PathBuf::from("repo/src/main.rs")));
+        assert_eq!(path_under_root(root, "src/", "README.md"), None);
+        assert_eq!(path_under_root(Path::new("."), "", "src/main.rs"), Some(PathBuf::from("./src/main.rs")));
+    }
+
+    #[test]
+    fn parse_tracked_files() {
+        let output = b"Cargo.toml\0src/main.rs\0dir/with space.rs\0";
+        assert_eq!(parse_ls_files(output), vec!["Cargo.toml", "src/main.rs", "dir/with space.rs"]);
+        assert!(parse_ls_files(b"").is_empty());
+    }
+}
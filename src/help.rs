@@ -12,7 +12,9 @@ pub fn show_help(ui: &Ui, text: &str) {
 pub const PATHS_USAGE: &str = indoc! { "
     A list of ';' seperated file or directory to search. Directories are searched
     recursively. File paths specified on the command line override glob and ignore
-    rules.
+    rules. Paths containing a ';' must be quoted, for instance \"C:\\my;dir\".
+    With \"Per path patterns\", each path is searched with its own patterns, for
+    instance src with *.rs and docs with *.md.
 "};
 
 pub const GLOBS_USAGE: &str = indoc! { "
@@ -28,23 +30,371 @@ pub const GLOBS_USAGE: &str = indoc! { "
     in gitignore files, even though this syntax isn't supported by git itself.
     ripgrep may disable this syntax extension in gitignore files, but it will
     always remain available via the -g/--glob flag.
+
+    Globs are separated by spaces, a glob containing spaces can be quoted. Arguments
+    copied from a ripgrep command line, like *-g \"*.rs\"*, are also accepted.
+"};
+
+pub const EXCLUDE_DIRS_USAGE: &str = indoc! { "
+    Names of directories to exclude, at any depth, separated by a comma or a
+    semicolon. For instance, \"node_modules, target\" is equivalent to the
+    patterns \"!**/node_modules/**\" and \"!**/target/**\". The excluded directories
+    take precedence over the patterns.
+"};
+
+pub const ONLY_MODIFIED_USAGE: &str = indoc! { "
+    Only search the files reported by \"git status\" in each path, that is the files
+    modified, added or untracked. The deleted files are skipped, and the patterns
+    still apply. The paths which aren't in a git repository are searched entirely.
+"};
+
+pub const ONLY_TRACKED_USAGE: &str = indoc! { "
+    Only search the files listed by \"git ls-files\" in each path, so the untracked
+    and generated files are skipped whatever the ignore files say. The patterns
+    still apply. When \"Only modified\" is also checked, it takes precedence. The
+    paths which aren't in a git repository are searched entirely.
+"};
+
+pub const LINE_RANGE_USAGE: &str = indoc! { "
+    Only keep the matches between these lines, inclusive. An empty bound is
+    unbounded, so only setting the max line searches the start of the files. The
+    files are read up to the max line, which is faster for huge files.
+"};
+
+pub const TAB_OVERRIDES_USAGE: &str = indoc! { "
+    Checked settings only apply to this tab, the others follow the settings. The
+    hidden files and the context lines are already options of each tab.
+"};
+
+pub const PCRE_USAGE: &str = indoc! { "
+    Match the regex with an engine supporting lookaround and backreferences, like
+    PCRE, for instance foo(?=bar) or (\\w+) \\1. It's slower, so only use it for the
+    queries that need it. Only available with the regex syntax.
+"};
+
+pub const FIND_IN_RESULTS_USAGE: &str = indoc! { "
+    Find a term in the results, regardless of case, without searching the files
+    again. The results containing it are highlighted, but the other results stay
+    visible. Press enter to jump to the next one, and shift+enter to the previous one.
+"};
+
+pub const GLOBS_IGNORE_CASE_USAGE: &str = indoc! { "
+    Match the globs regardless of case, so *.txt also matches *NOTES.TXT*. This is
+    useful on case insensitive file systems, like on Windows and macOS.
+
+    This only applies to the patterns and the excluded directories. The queries
+    have their own \"Ignore case\" option, which is independent.
+"};
+
+pub const GLOBS_ANY_DEPTH_USAGE: &str = indoc! { "
+    By default, a glob containing a '/' is matched against the path relative to the
+    search path, so *src/*.rs* only matches in the directory *src* at the root of
+    the search path. A glob without '/', like *.txt, already matches file names at
+    any depth.
+
+    With this option, globs are prefixed by **/ such that *src/*.rs* also matches
+    *a/src/lib.rs*. Globs starting with / or **/ are kept as is.
+"};
+
+pub const SEARCH_MODE_USAGE: &str = indoc! { "
+    What the queries are matched against. With \"File names\", only the names of
+    the files are matched and the files are not opened. With \"Both\", a file is
+    listed if its name matches, shown as a [file name] row, and every line matching
+    in its content is listed as well.
+"};
+
+pub const FUZZY_USAGE: &str = indoc! { "
+    Match the queries against the file names like fzf, every character of the
+    query must be found in order but other characters can be in between. For
+    instance, \"srs\" matches search.rs. Once the search is done, the best matches,
+    with consecutive characters or at the start of words, are shown first. Only
+    available when searching the file names.
+"};
+
+pub const PARAGRAPHS_USAGE: &str = indoc! { "
+    Show the paragraphs containing a match rather than the matched lines with their
+    context. A paragraph is a block of lines delimited by blank lines, such as a
+    record of a log or a section of a config file, so matches spanning several
+    lines can be read at once. The number of lines of a paragraph is limited in
+    the settings.
+"};
+
+pub const CAPTURES_USAGE: &str = indoc! { "
+    Show a column per capture group of the regex queries, with the text captured
+    by the first match of each line. For instance, \"(\\w+)=(\\d+)\" shows the
+    names and the values in two columns, which can be copied as CSV from the
+    context menu of the results. At most 8 groups are shown.
+"};
+
+pub const ANY_WORD_USAGE: &str = indoc! { "
+    Split the query on whitespace and match any of the words, which are searched
+    literally. For instance, \"foo bar\" matches the lines containing foo or bar.
+    Not available with the regex syntax.
+"};
+
+pub const COMBINE_USAGE: &str = indoc! { "
+    How the queries are combined for each file. With \"Match all queries\", a file
+    is only listed if every query matches somewhere in the file, not necessarily on
+    the same line. With \"Match any query\", a file is listed if at least one query
+    matches. In both cases, the matches of every query are highlighted.
+"};
+
+pub const REPLACE_USAGE: &str = indoc! { "
+    Replace every match of the results by the replacement text. The replacement
+    is literal, capture groups are not expanded. With \"Preserve case\", the case
+    of each match is applied to the replacement (foo -> bar, Foo -> Bar and
+    FOO -> BAR), other cases use the replacement as is.
+
+    The lines to replace are shown first, with each match struck through and
+    followed by its replacement. Uncheck the lines that must not be replaced, the
+    files are only written when pressing Apply.
+
+    Files that changed since the search are not modified. Once done, the search
+    is started again to show the new content.
 "};
 
 pub const SETTINGS_SEARCH_BINARY_HELP: &str = indoc! { "
-    Enabling this flag will cause ripgrep to search binary files. By default,
-    ripgrep attempts to automatically skip binary files in order to improve the
-    relevance of results and make the search faster.
+    How binary files are searched. Binary files are heuristically detected based
+    on whether they contain a NUL byte or not.
+    - Skip: stop searching the file once a NUL byte is seen. Usually, NUL bytes
+      occur in the beginning of most binary files. Files given explicitly in the
+      paths are searched like with Convert.
+    - Convert NUL to new lines: search the whole file, replacing the NUL bytes
+      with line terminators, which keeps the matched lines short and readable.
+    - Search as text: search the whole file as it is. Binary files often have very
+      long lines, so the results can be garbled.
+"};
+
+pub const SETTINGS_SYMLINK_MODE_HELP: &str = indoc! { "
+    Which symbolic links are followed when walking the directories.
+    - Don't follow: the links are skipped.
+    - Follow files only: the files the links point to are searched, but linked
+      directories are not walked, which avoids cycles and searching a tree twice.
+    - Follow files and directories: every link is followed, links leading to a
+      directory already being walked are skipped.
+"};
+
+pub const SETTINGS_ALWAYS_ON_TOP_HELP: &str = indoc! { "
+    Keep the window above the other windows. It can also be toggled with F12.
+    This has no effect on the platforms that don't support it.
+"};
 
-    Binary files are heuristically detected based on whether they contain a NUL
-    byte or not. By default (without this flag set), once a NUL byte is seen,
-    ripgrep will stop searching the file. Usually, NUL bytes occur in the beginning
-    of most binary files. If a NUL byte occurs after a match, then ripgrep will
-    still stop searching the rest of the file, but a warning will be printed.
+pub const SETTINGS_GLOBAL_HOTKEY_HELP: &str = indoc! { "
+    Hotkey hiding the window when it's focused, and showing it back from any other
+    application, for instance \"Ctrl+Alt+Space\". While hidden, an icon in the
+    notification area also shows the window when clicked. Modifiers are Ctrl, Alt, Shift and
+    Win, the key is a letter, a digit, F1 to F12 or Space. Leave it empty to disable
+    it. Only supported on Windows and requires a restart to take effect.
+"};
+
+pub const SETTINGS_RESET_TABS_HELP: &str = indoc! { "
+    Close every tab and open a single tab searching the new workspace when opening
+    a workspace. Otherwise, only new tabs search the new workspace.
+"};
+
+pub const SETTINGS_RESTORE_WORKSPACE_SEARCH_HELP: &str = indoc! { "
+    Remember the paths, patterns and query of the last search done in each workspace,
+    and fill the first tab with them when starting in the same directory, or when
+    opening the workspace with \"Reset tabs when opening a workspace\". The paths,
+    patterns and query given on the command line take precedence.
+"};
+
+pub const SETTINGS_THREADS_HELP: &str = indoc! { "
+    Number of threads used to search. Set it to 0 to use as many threads as there
+    are cores. It can't be more than 4 times the number of cores.
+"};
+
+pub const SETTINGS_DEFAULT_QUERY_HELP: &str = indoc! { "
+    Options of the queries in new tabs and of the queries added with the + button.
+    Duplicated tabs keep the options of their queries.
+"};
+
+pub const SETTINGS_CONTEXT_HELP: &str = indoc! { "
+    Number of lines to show before and after each match for new queries. Each query
+    can then show a different number of lines before and after its matches. Groups of
+    lines that are not contiguous are separated by a thin line in the results.
+"};
+
+pub const CONTEXT_USAGE: &str = indoc! { "
+    Number of lines to show before and after each match, like -B and -A with grep.
+    Groups of lines that are not contiguous are separated by a thin line.
+"};
 
+pub const SETTINGS_MATCH_HIGHLIGHT_HELP: &str = indoc! { "
+    How the matches are highlighted, each query having its own color. With the
+    underline or the background, the text keeps its color, which can be easier
+    to read with color vision deficiencies.
+"};
+
+pub const SETTINGS_HIGHLIGHT_MATCH_ROWS_HELP: &str = indoc! { "
+    Tint the background of the whole line for the lines that matched, such that
+    they stand out from the context lines and the file names.
+"};
+
+pub const SETTINGS_CJK_GLYPHS_HELP: &str = indoc! { "
+    Load the Japanese glyphs in the fonts, such that Japanese text is readable in
+    the results. Disabling it makes the startup faster and uses less memory. Only
+    applied when the application is started again.
+"};
+
+pub const SETTINGS_RELATIVE_PATHS_HELP: &str = indoc! { "
+    Show the paths of the results relative to the path they were found from.
+    When a file is searched directly, its name is shown.
+"};
+
+pub const SETTINGS_GROUP_BY_FILE_HELP: &str = indoc! { "
+    Only show the path on the first line of each file, rather than on every line.
+    Every line is still its own row, which can be selected and copied.
+"};
+
+pub const SETTINGS_FOLD_REPEATED_CONTEXT_HELP: &str = indoc! { "
+    Show consecutive context lines with the same text as a single line, followed
+    by the number of times it's repeated. Copying the results still copies every
+    line.
+"};
+
+pub const SETTINGS_SHOW_MODIFIED_TIME_HELP: &str = indoc! { "
+    Show the last modification time of the files, in local time, in a column of
+    the results. Click the header of the column to sort the results by it, the
+    most recently changed files first, and click again to change the order.
+"};
+
+pub const SETTINGS_SHOW_MINIMAP_HELP: &str = indoc! { "
+    When the results all come from a single file, such as a big log, draw a strip
+    beside them where each range of lines is tinted by its number of matches. The
+    visible lines are outlined, and clicking or dragging in the strip scrolls the
+    results to the lines under the mouse. It's updated once the search completes.
+"};
+
+pub const SETTINGS_SCROLL_TO_FIRST_MATCH_HELP: &str = indoc! { "
+    When a search completes, scroll the results back to the top and focus the
+    first result, such that it can be navigated with the keyboard right away.
+    Disable it to keep the scroll position.
+"};
+
+pub const SETTINGS_KEEP_SELECTION_HELP: &str = indoc! { "
+    When a tab is searched again, select the result that was selected, at the same
+    path and line, once the search completes and scroll to it. If it's not found
+    anymore, the search behaves as usual.
+"};
+
+pub const SETTINGS_MIN_QUERY_LEN_HELP: &str = indoc! { "
+    Minimum number of characters of the queries to start a search when pressing
+    enter. This avoids searching a large tree for almost everything by accident.
+    The Search button always searches.
+"};
+
+pub const SETTINGS_SEARCH_ARCHIVES_HELP: &str = indoc! { "
+    Search the files contained in zip archives. Results are reported with a path
+    like \"logs.zip!server.log\" and the file is extracted to a temporary directory
+    when opened. This can be slow for large archives.
+"};
+
+pub const SETTINGS_HEX_BINARY_LINES_HELP: &str = indoc! { "
+    Display the lines that look binary as a hex dump, similar to xxd, instead of
+    text. A line is considered binary if it contains a NUL byte or if too many of
+    its bytes are not printable. The offset is the position of the line in the file.
+"};
+
+pub const SETTINGS_MAX_LINE_LEN_HELP: &str = indoc! { "
+    Maximum number of bytes of a line displayed in the results. Longer lines are
+    truncated around the first match. Copying a result still copies the full line.
+    Set to 0 to never truncate, otherwise it's at least 16.
+"};
+
+pub const SETTINGS_MAX_PARAGRAPH_LINES_HELP: &str = indoc! { "
+    Maximum number of lines of a paragraph shown when searching the paragraphs.
+    Longer paragraphs are cut around their first match.
+"};
+
+pub const SETTINGS_DOUBLE_CLICK_HELP: &str = indoc! { "
+    What double clicking a result does: open the file in the editor at the line
+    of the result, open the folder containing the file, open the file with the
+    application associated to its type or copy the path of the file. F4 always
+    opens the results in the editor.
 "};
 
 pub const SETTINGS_EDITOR_HELP: &str = indoc! { "
     Command line to use when using F4 which can be interpolated with:
     - {file} Path to the file
     - {line} Line of the result
+    - {dir} Directory containing the file
+    - {files} Every selected result, or every result if none is selected, as
+      \"path:line\". The argument is repeated for each file and the editor is
+      started only once, for instance: code -g {files}
+"};
+
+pub const SETTINGS_EXTENSION_EDITORS_HELP: &str = indoc! { "
+    Command lines opening the files with a given extension, such as a markdown
+    application for .md files, instead of the active editor. They are used by F4
+    and by double clicking a result, and are interpolated like the editor. Type an
+    extension and press + to add it, starting from the command of the active editor.
+"};
+
+pub const SETTINGS_LOCATION_URL_HELP: &str = indoc! { "
+    URL copied by \"Copy location URL\" in the context menu of a result, which can be
+    interpolated with:
+    - {path} Absolute path to the file, URL encoded
+    - {line} Line of the result
+    For instance: vscode://file{path}:{line} or idea://open?file={path}&line={line}
+"};
+
+pub const SETTINGS_TERMINAL_HELP: &str = indoc! { "
+    Command opening a terminal from \"Open terminal in file directory\" in the context
+    menu of a result. It's started in the directory of the file, which can also be
+    given with {dir}. For instance: wt -d {dir}, cmd /c start cmd or gnome-terminal
+"};
+
+pub const SETTINGS_HEX_EDITOR_HELP: &str = indoc! { "
+    Command opening a file from \"Open in hex editor\" in the context menu of a
+    result, which can be interpolated with:
+    - {file} Path to the file
+    - {offset} Offset, in bytes, of the first match of the line in the file
+    For instance: hexedit {file} or \"C:\\Program Files\\HxD\\HxD.exe\" {file}
+"};
+
+pub const SETTINGS_MAX_RESULTS_MEMORY_HELP: &str = indoc! { "
+    Memory the results of each tab can hold before some are dropped, which keeps
+    the application usable when a query matches millions of lines by accident.
+    The context lines are dropped first, then the oldest results. The memory is an
+    estimation. Set it to 0 to never drop results.
+"};
+
+pub const SETTINGS_AUTOSAVE_DELAY_HELP: &str = indoc! { "
+    The settings are saved when the settings window is closed and on exit. They are
+    also saved once they haven't changed for this number of seconds, such that they
+    are not lost if the application crashes. Set it to 0 to disable it.
+"};
+
+pub const SETTINGS_VERBOSE_SEARCH_HELP: &str = indoc! { "
+    Write to the log what every search runs with: the paths, the globs as they are
+    given to the walker, the number of threads and the options of each query.
+    Helps to understand why a search doesn't find what is expected, for instance
+    when a glob excludes more files than intended.
+"};
+
+pub const TIMING_USAGE: &str = indoc! { "
+    Time spent in each step of the search, shown in the footer:
+    - Walk: listing the directories and filtering the files with the patterns
+    - Search: reading and searching the files
+    - Collect: receiving the results and preparing them for the display
+    Walk and Search are summed over every thread, so they can be larger than the
+    duration of the search. If Walk dominates, try more specific paths or patterns.
+    If Search dominates, more threads may help.
+"};
+
+pub const SETTINGS_EDITOR_PRESETS_HELP: &str = indoc! { "
+    Editors used to open the results with F4. Add one for each way to open the
+    results, for instance VS Code in a new window and VS Code with --reuse-window,
+    and pick the one to use.
+"};
+
+pub const SETTINGS_EDITOR_DIR_HELP: &str = indoc! { "
+    Start the editor with its current directory set to the directory containing
+    the file, instead of the current directory of Search.
+"};
+
+pub const SETTINGS_RESULTS_FONT_HELP: &str = indoc! { "
+    Font used to draw the results, the rest of the interface uses the monospace
+    font. Only applied when the application is started again.
 "};
@@ -0,0 +1,100 @@
+/// Number of states kept, the oldest are forgotten first.
+const MAX_HISTORY: usize = 50;
+
+/// States to go back and forward through, like the history of a browser. Pushing a state
+/// after going back forgets the states that were ahead.
+pub struct History<T> {
+    states: Vec<T>,
+    /// Index, in `states`, of the current state.
+    current: usize,
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    pub fn new() -> Self {
+        Self { states: Vec::new(), current: 0 }
+    }
+
+    /// Make `state` the current state, unless it's already the current one.
+    pub fn push(&mut self, state: T) {
+        if self.states.get(self.current) == Some(&state) {
+            return;
+        }
+
+        if !self.states.is_empty() {
+            self.states.truncate(self.current + 1);
+        }
+        self.states.push(state);
+        if self.states.len() > MAX_HISTORY {
+            self.states.remove(0);
+        }
+        self.current = self.states.len() - 1;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.current + 1 < self.states.len()
+    }
+
+    /// Returns the previous state, which becomes the current one.
+    pub fn back(&mut self) -> Option<T> {
+        if !self.can_go_back() {
+            return None;
+        }
+
+        self.current -= 1;
+        return self.states.get(self.current).cloned();
+    }
+
+    /// Returns the next state, which becomes the current one.
+    pub fn forward(&mut self) -> Option<T> {
+        if !self.can_go_forward() {
+            return None;
+        }
+
+        self.current += 1;
+        return self.states.get(self.current).cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = History::new();
+        assert_eq!(history.back(), None);
+
+        history.push("a");
+        history.push("b");
+        history.push("b");
+        history.push("c");
+        assert_eq!(history.back(), Some("b"));
+        assert_eq!(history.back(), Some("a"));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some("b"));
+
+        // The states ahead are forgotten.
+        history.push("d");
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back(), Some("b"));
+        assert_eq!(history.forward(), Some("d"));
+    }
+
+    #[test]
+    fn oldest_states_forgotten() {
+        let mut history = History::new();
+        for state in 0..MAX_HISTORY + 10 {
+            history.push(state);
+        }
+
+        let mut oldest = None;
+        while let Some(state) = history.back() {
+            oldest = Some(state);
+        }
+        assert_eq!(oldest, Some(10));
+    }
+}
@@ -1,5 +1,63 @@
+use anyhow::{bail, Result};
 use imgui::*;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HotkeyKey {
+    /// Letter, always uppercase, or digit.
+    Char(char),
+    /// Function key, from F1 to F12.
+    Function(u8),
+    Space,
+}
+
+/// Combination of keys of a global hotkey, such as "Ctrl+Alt+Space".
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HotkeyCombination {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    pub key: HotkeyKey,
+}
+
+pub fn parse_hotkey(text: &str) -> Result<HotkeyCombination> {
+    let mut ctrl = false;
+    let mut alt = false;
+    let mut shift = false;
+    let mut win = false;
+    let mut key = None;
+
+    for part in text.split('+').map(|part| part.trim()) {
+        if key.is_some() {
+            bail!("The key must be last in the hotkey '{}'", text);
+        }
+
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => ctrl = true,
+            "alt" => alt = true,
+            "shift" => shift = true,
+            "win" | "super" | "cmd" => win = true,
+            "space" => key = Some(HotkeyKey::Space),
+            name => {
+                let mut chars = name.chars();
+                key = match (chars.next(), chars.next()) {
+                    (Some(value), None) if value.is_ascii_alphanumeric() => Some(HotkeyKey::Char(value.to_ascii_uppercase())),
+                    (Some('f'), Some(_)) => match name[1..].parse::<u8>() {
+                        Ok(number) if (1..=12).contains(&number) => Some(HotkeyKey::Function(number)),
+                        _ => bail!("Unknown key '{}' in the hotkey '{}'", part, text),
+                    },
+                    _ => bail!("Unknown key '{}' in the hotkey '{}'", part, text),
+                };
+            }
+        }
+    }
+
+    match key {
+        Some(key) => Ok(HotkeyCombination { ctrl, alt, shift, win, key }),
+        None => bail!("Expected a key in the hotkey '{}'", text),
+    }
+}
+
 pub struct HotkeysWindow {
     opened: bool,
 }
@@ -40,10 +98,20 @@ impl HotkeysWindow {
             ("ESC", "Cancel search."),
             ("Ctrl+T", "Creates a new tab."),
             ("Ctrl+Shift+T", "Duplicate current tab."),
+            ("Ctrl+O", "Pick a folder to search in a new tab."),
             ("Ctrl+W", "Close current tab."),
+            ("Ctrl+Q", "Quit, asking first while a replace is running."),
+            ("Ctrl+L", "Cancel the search and clear the results of the current tab."),
+            ("Ctrl+Z", "Go back to the previous search of the current tab."),
+            ("Ctrl+Y", "Go forward to the next search of the current tab."),
             ("Ctrl+PageUp", "Rotate current tab to the left."),
             ("Ctrl+PageDown", "Rotate current tab to the right."),
             ("F4", "Open selected files with your configured editor."),
+            ("F12", "Toggle keeping the window always on top."),
+            ("Ctrl+Shift+F", "Find a term in the results of the current tab."),
+            ("Ctrl+Shift+P", "Open the command palette, to run any action by its name."),
+            ("Double click on a tab", "Rename the tab."),
+            ("Double click on a result", "Open the result, as configured in the settings."),
         ];
 
         window.build(|| {
@@ -63,3 +131,32 @@ impl HotkeysWindow {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_hotkeys() {
+        assert_eq!(
+            parse_hotkey("Ctrl+Alt+Space").unwrap(),
+            HotkeyCombination { ctrl: true, alt: true, shift: false, win: false, key: HotkeyKey::Space },
+        );
+        assert_eq!(
+            parse_hotkey(" win + shift + s ").unwrap(),
+            HotkeyCombination { ctrl: false, alt: false, shift: true, win: true, key: HotkeyKey::Char('S') },
+        );
+        assert_eq!(parse_hotkey("F12").unwrap().key, HotkeyKey::Function(12));
+        assert_eq!(parse_hotkey("Alt+1").unwrap().key, HotkeyKey::Char('1'));
+    }
+
+    #[test]
+    fn parse_invalid_hotkeys() {
+        assert!(parse_hotkey("").is_err());
+        assert!(parse_hotkey("Ctrl+Alt").is_err());
+        assert!(parse_hotkey("Space+Ctrl").is_err());
+        assert!(parse_hotkey("Ctrl+F13").is_err());
+        assert!(parse_hotkey("Ctrl+Enter").is_err());
+        assert!(parse_hotkey("Ctrl+é").is_err());
+    }
+}
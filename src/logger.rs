@@ -0,0 +1,110 @@
+use imgui::*;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// Maximum number of messages kept, the oldest messages are dropped first.
+const MAX_MESSAGES: usize = 1000;
+
+static MESSAGES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Set when stdout is used for the results, without a window.
+static PRINT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Print the message to stdout and keep it, such that it can be displayed in
+/// the log window. This is useful when the application doesn't have a console.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::logger::push(format!($($arg)*))
+    };
+}
+
+/// Print the messages to stderr rather than stdout, such that they are not mixed with the output.
+pub fn print_to_stderr() {
+    PRINT_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+pub fn push(message: String) {
+    if PRINT_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+    if let Ok(mut messages) = MESSAGES.lock() {
+        if messages.len() == MAX_MESSAGES {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+    }
+}
+
+pub struct LogWindow {
+    opened: bool,
+}
+
+impl LogWindow {
+    pub fn new() -> Self {
+        Self { opened: false }
+    }
+
+    pub fn open(&mut self, opened: bool) {
+        self.opened = opened;
+    }
+
+    pub fn toggle_open(&mut self) {
+        self.open(!self.opened);
+    }
+
+    pub fn draw_log(&mut self, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+
+        let display_size = ui.io().display_size;
+        let log_window_size = [750.0, 562.0];
+        let pos_x = (display_size[0] / 2.0) - (log_window_size[0] / 2.0);
+        let pos_y = (display_size[1] / 2.0) - (log_window_size[1] / 2.0);
+
+        let window = ui
+            .window("Log")
+            .size(log_window_size, Condition::Appearing)
+            .position([pos_x, pos_y], Condition::Appearing)
+            .collapsible(false)
+            .opened(&mut self.opened);
+
+        window.build(|| {
+            let mut messages = if let Ok(messages) = MESSAGES.lock() {
+                messages
+            } else {
+                return;
+            };
+
+            if ui.button("Clear") {
+                messages.clear();
+            }
+
+            ui.same_line();
+            if ui.button("Copy") {
+                let content: Vec<&str> = messages.iter().map(|message| message.as_str()).collect();
+                ui.set_clipboard_text(content.join("\n"));
+            }
+
+            ui.separator();
+            ui.child_window("##log-messages").horizontal_scrollbar(true).build(|| {
+                // Follow new messages, unless the user scrolled up to read older ones.
+                let follow = ui.scroll_y() >= ui.scroll_max_y();
+                for message in messages.iter() {
+                    ui.text(message);
+                }
+
+                if follow {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
+        });
+    }
+}
@@ -1,68 +1,294 @@
 // #![windows_subsystem = "windows"]
 
+#[macro_use]
+mod logger;
+
+mod archive;
 mod args;
+mod cli;
 mod clipboard;
 mod editor;
+mod export;
+mod fuzzy;
+mod git;
 mod help;
+mod history;
 mod hotkeys;
+mod matcher;
+mod minimap;
+mod palette;
+mod replace;
+mod ripgrep;
 mod search;
 mod settings;
+mod snippet;
+mod statistics;
 mod support;
+mod sys;
+mod toolbar;
+mod workspace;
 
 use glium::glutin::event::VirtualKeyCode;
 use imgui::*;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
     process::Child,
     rc::Rc,
-    sync::mpsc::TryRecvError,
-    time::Duration,
+    sync::{mpsc::TryRecvError, Arc},
+    time::{Duration, Instant, SystemTime},
 };
 
 use crate::{
     editor::*,
     help::*,
+    history::*,
     hotkeys::*,
+    logger::*,
+    minimap::*,
+    palette::*,
+    replace::*,
+    ripgrep::*,
     search::*,
     settings::*,
+    statistics::*,
+    toolbar::*,
+    workspace::*,
 };
 
 pub struct UiSearchEntry {
     pub selected: bool,
     pub path: Rc<String>,
+    pub root: Rc<String>,
     pub line_number: u64,
+    pub offset: u64,
     pub bytes: Vec<u8>,
-    pub matches: Vec<(usize, usize)>,
+    pub matches: Vec<SearchMatch>,
+    pub kind: EntryKind,
+    pub context_break: bool,
+    /// Whether the line looks like binary data rather than text.
+    pub binary: bool,
+    /// Hidden by the user, until the dismissed results are cleared.
+    pub dismissed: bool,
+    /// Score of the fuzzy match of the file name, used to rank the results.
+    pub score: u32,
+    /// Byte ranges of the capture groups, when they are extracted.
+    pub captures: Vec<Option<(usize, usize)>>,
+    /// Modification time of the file, read once when the results of the file are received.
+    pub modified: Option<SystemTime>,
+    /// Index of the file in the order the results were received, to restore it once sorted.
+    pub arrival: usize,
 }
 
 impl UiSearchEntry {
-    fn new(path: Rc<String>, entry: SearchResultEntry) -> Self {
+    fn new(path: Rc<String>, root: Rc<String>, entry: SearchResultEntry, modified: Option<SystemTime>, arrival: usize) -> Self {
         Self {
             selected: false,
             path,
+            root,
             line_number: entry.line_number,
+            offset: entry.offset,
+            binary: is_binary_line(&entry.bytes),
             bytes: entry.bytes,
             matches: entry.matches,
+            kind: entry.kind,
+            context_break: entry.context_break,
+            dismissed: false,
+            score: entry.score,
+            captures: entry.captures,
+            modified,
+            arrival,
+        }
+    }
+
+    /// Estimation of the memory held by the entry, the paths are shared so they are not counted.
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.bytes.capacity()
+            + self.matches.capacity() * std::mem::size_of::<SearchMatch>()
+            + self.captures.capacity() * std::mem::size_of::<Option<(usize, usize)>>()
+    }
+
+    /// Text captured by the group at `idx`, empty if it didn't match.
+    fn capture_text(&self, idx: usize) -> std::borrow::Cow<'_, str> {
+        match self.captures.get(idx).copied().flatten() {
+            Some((start, end)) => String::from_utf8_lossy(&self.bytes[start..end]),
+            None => std::borrow::Cow::Borrowed(""),
+        }
+    }
+
+    /// Results of the piped input have no file to open.
+    fn has_file(&self) -> bool {
+        self.path.as_str() != STDIN_PATH
+    }
+
+    /// Modification time of the file in local time, empty if it's unknown.
+    fn modified_text(&self) -> String {
+        match self.modified {
+            Some(modified) => chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Find a term in the results, jumping from one to the other without hiding the
+/// results that don't contain it, unlike the filters.
+#[derive(Default)]
+struct FindInResults {
+    opened: bool,
+    focus: bool,
+    term: String,
+    /// Indices, in `results`, of the results containing the term.
+    rows: Vec<usize>,
+    current: usize,
+    /// Number of results already looked at, the new results are looked at when they arrive.
+    searched: usize,
+    /// Result to scroll to, until it's drawn.
+    scroll_to: Option<usize>,
+}
+
+/// Lines around the hovered result, shown in a tooltip once hovered for a moment.
+#[derive(Default)]
+struct HoverPreview {
+    /// Result hovered, in `results`, and since when.
+    hovered: Option<(usize, Instant)>,
+    /// Lines read for each path and line number, `None` if the file couldn't be read.
+    cache: HashMap<(Rc<String>, u64), Option<String>>,
+}
+
+impl HoverPreview {
+    const DELAY: Duration = Duration::from_millis(500);
+    const LINES: u64 = 3;
+
+    fn hover(&mut self, row: Option<usize>) {
+        if row != self.hovered.map(|(row, _)| row) {
+            self.hovered = row.map(|row| (row, Instant::now()));
+        }
+    }
+
+    /// Lines to show for the result, once it has been hovered long enough. The file is only
+    /// read the first time.
+    fn text(&mut self, row: usize, result: &UiSearchEntry) -> Option<&str> {
+        match self.hovered {
+            Some((hovered, since)) if hovered == row && since.elapsed() >= Self::DELAY => {}
+            _ => return None,
+        }
+
+        if result.kind == EntryKind::FileName || result.line_number == 0 {
+            return None;
         }
+
+        let key = (Rc::clone(&result.path), result.line_number);
+        let text = self.cache.entry(key).or_insert_with(|| {
+            let file = std::fs::File::open(result.path.as_str()).ok()?;
+            search::surrounding_lines(std::io::BufReader::new(file), result.line_number, Self::LINES).ok()
+        });
+        return text.as_deref();
+    }
+}
+
+/// Settings of the search overridden by a single tab, `None` inherits the global setting.
+#[derive(Clone, Default)]
+struct TabOverrides {
+    binary_mode: Option<BinaryMode>,
+    number_of_threads: Option<i32>,
+    search_archives: Option<bool>,
+}
+
+impl TabOverrides {
+    fn is_empty(&self) -> bool {
+        self.binary_mode.is_none() && self.number_of_threads.is_none() && self.search_archives.is_none()
     }
 }
 
+/// Time spent in each step of a search. Walking and searching are done by multiple
+/// threads, so their durations are summed over every thread.
+#[derive(Clone, Copy, Default)]
+struct SearchTiming {
+    walk: Duration,
+    search: Duration,
+    /// Time spent receiving the results and preparing them for the UI.
+    collect: Duration,
+}
+
 pub struct SearchTab {
     config: SearchConfig,
     results: Vec<UiSearchEntry>,
     pending_search: Option<PendingSearch>,
     file_searched: usize,
     file_searched_with_results: usize,
+    searched_roots: usize,
     search_duration: Duration,
+    timing: SearchTiming,
     last_focused_row: Option<usize>,
     last_selected_row: Option<usize>,
     error_message: Option<String>,
+    /// Files that couldn't be searched, with the reason.
+    file_errors: Vec<String>,
+    replace: String,
+    preserve_case: bool,
+    pending_replace: Option<PendingReplace>,
+    /// Replacements shown for review, before the files are written.
+    replace_preview: Option<ReplacePreview>,
+    replaced_matches: usize,
+    replaced_files: usize,
+    replace_errors: usize,
+    /// Indices of the results that are shown, if the results are filtered.
+    /// Otherwise, every result is shown.
+    filtered_rows: Option<Vec<usize>>,
+    /// Number of results dismissed, which are not shown.
+    dismissed: usize,
+    /// Number of results per file extension, computed once the search is done.
+    extensions: Vec<(String, usize)>,
+    selected_extensions: HashSet<String>,
+    /// Name given by the user to the tab, the paths are used if it's empty.
+    name: String,
+    /// Scroll to the first result and focus it, set when a search completes.
+    scroll_to_first_match: bool,
+    /// Path and line number of the result selected when the search was started again,
+    /// selected again once the search completes if it's still in the results.
+    reselect: Option<(String, u64)>,
+    /// Input piped to the application, searched instead of the paths.
+    stdin: Option<Arc<Vec<u8>>>,
+    /// Result to give the keyboard focus to once it's drawn.
+    focus_row: Option<usize>,
+    /// Result double clicked to be opened in the editor, which is run by the main loop.
+    open_in_editor: Option<usize>,
+    /// Whether the repeated context lines are hidden, set from the settings.
+    fold_repeats: bool,
+    /// Largest number of capture groups of the results, shown as a column each.
+    capture_columns: usize,
+    /// Direction of the sort by modification time, the results are in the order they were
+    /// received otherwise.
+    sort_modified: Option<TableSortDirection>,
+    /// Number typed in the "Go to result" input of the footer.
+    go_to_result: i32,
+    /// Set when a search is started, such that it's remembered as the last search of the workspace.
+    searched: bool,
+    /// Estimation of the memory held by `results`.
+    results_memory: usize,
+    /// Number of results dropped to limit the memory.
+    trimmed_results: usize,
+    find: FindInResults,
+    preview: HoverPreview,
+    overrides: TabOverrides,
+    /// Configurations of the searches of the tab, to go back and forward through them.
+    history: History<SearchConfig>,
+    /// Bumped whenever the results or the visible rows change, such that what is computed
+    /// from them is known to be outdated.
+    results_generation: usize,
+    /// Matches per range of lines, when a single file was searched.
+    minimap: Option<MatchDensity>,
+    /// Generation of the results `minimap` was computed from, even if no minimap was shown for them.
+    minimap_generation: Option<usize>,
 }
 
 impl SearchTab {
-    pub fn from_context(context: String) -> Self {
+    pub fn from_context(context: String, settings: &Settings) -> Self {
+        let mut config = SearchConfig::with_paths(context);
+        config.queries = vec![settings.default_query()];
         Self {
-            config: SearchConfig::with_paths(context),
+            config,
             ..Self::default()
         }
     }
@@ -74,16 +300,76 @@ impl SearchTab {
             pending_search: None,
             file_searched: 0,
             file_searched_with_results: 0,
+            searched_roots: 0,
             search_duration: Duration::from_secs(0),
+            timing: SearchTiming::default(),
             last_focused_row: None,
             last_selected_row: None,
             error_message: None,
+            file_errors: Vec::new(),
+            replace: String::new(),
+            preserve_case: false,
+            pending_replace: None,
+            replace_preview: None,
+            replaced_matches: 0,
+            replaced_files: 0,
+            replace_errors: 0,
+            filtered_rows: None,
+            dismissed: 0,
+            extensions: Vec::new(),
+            selected_extensions: HashSet::new(),
+            name: String::new(),
+            scroll_to_first_match: false,
+            reselect: None,
+            stdin: None,
+            focus_row: None,
+            open_in_editor: None,
+            go_to_result: 1,
+            capture_columns: 0,
+            sort_modified: None,
+            fold_repeats: false,
+            searched: false,
+            results_memory: 0,
+            trimmed_results: 0,
+            find: FindInResults::default(),
+            preview: HoverPreview::default(),
+            overrides: TabOverrides::default(),
+            history: History::new(),
+            results_generation: 0,
+            minimap: None,
+            minimap_generation: None,
+        }
+    }
+
+    /// Fill the tab with the last search done in the current workspace, if there is one.
+    fn restore_workspace_search(&mut self, summaries: &[WorkspaceSummary]) {
+        let workspace = match current_workspace() {
+            Some(workspace) => workspace,
+            None => return,
+        };
+
+        if let Some(summary) = find_workspace_summary(summaries, &workspace) {
+            self.config.paths = summary.paths.clone();
+            self.config.globs = summary.globs.clone();
+            self.config.queries[0].query = summary.query.clone();
+        }
+    }
+
+    /// Summary of the search of the tab, to restore it in the same workspace.
+    fn workspace_summary(&self, workspace: String) -> WorkspaceSummary {
+        let query = self.config.queries.iter().map(|query| query.query.as_str()).find(|query| !query.is_empty());
+        WorkspaceSummary {
+            workspace,
+            paths: self.config.paths_label(),
+            globs: self.config.globs.clone(),
+            query: query.unwrap_or_default().to_owned(),
         }
     }
 
     pub fn clone_for_tab(&self) -> Self {
         Self {
             config: self.config.clone(),
+            overrides: self.overrides.clone(),
             ..Self::default()
         }
     }
@@ -94,41 +380,216 @@ impl SearchTab {
             self.search_duration = pending.elapsed();
         }
 
-        self.pending_search = None;
+        if self.pending_search.take().is_some() && !clear_results {
+            self.rank_results();
+            self.update_extensions();
+        }
 
         if clear_results {
             self.results.clear();
             self.file_searched = 0;
             self.search_duration = Duration::from_secs(0);
+            self.timing = SearchTiming::default();
             self.file_searched_with_results = 0;
+            self.searched_roots = 0;
             self.last_focused_row = None;
             self.last_selected_row = None;
             self.error_message = None;
+            self.file_errors.clear();
+            self.filtered_rows = None;
+            self.dismissed = 0;
+            self.extensions.clear();
+            self.selected_extensions.clear();
+            self.scroll_to_first_match = false;
+            self.focus_row = None;
+            self.results_memory = 0;
+            self.capture_columns = 0;
+            self.trimmed_results = 0;
+            self.reset_find();
+            self.preview = HoverPreview::default();
+            self.results_generation += 1;
+        }
+    }
+
+    /// Lines of the visible matches, if they all come from the same file.
+    fn single_file_match_lines(&self) -> Option<Vec<u64>> {
+        if self.searched_roots != 1 {
+            return None;
+        }
+
+        let mut path = None;
+        let mut lines = Vec::new();
+        for row in 0..self.visible_rows() {
+            let result = &self.results[self.visible_row(row)];
+            if result.kind == EntryKind::FileName || *path.get_or_insert(result.path.as_str()) != result.path.as_str() {
+                return None;
+            }
+            if result.kind == EntryKind::Match {
+                lines.push(result.line_number);
+            }
+        }
+
+        return (!lines.is_empty()).then_some(lines);
+    }
+
+    /// Count the matches per range of lines again if the results changed. Only done once the
+    /// search completes, as the results of a big file would otherwise be counted every frame.
+    fn update_minimap(&mut self, bucket_count: usize) {
+        if self.pending_search.is_some() {
+            self.clear_minimap();
+            return;
+        }
+
+        let up_to_date = self.minimap_generation == Some(self.results_generation)
+            && self.minimap.as_ref().is_none_or(|minimap| !minimap.is_stale(bucket_count));
+        if up_to_date {
+            return;
+        }
+        self.minimap = self.single_file_match_lines().map(|lines| MatchDensity::new(&lines, bucket_count));
+        self.minimap_generation = Some(self.results_generation);
+    }
+
+    fn clear_minimap(&mut self) {
+        self.minimap = None;
+        self.minimap_generation = None;
+    }
+
+    /// Scroll the results to the first visible row at or after `line`.
+    fn scroll_to_line(&mut self, line: u64) {
+        let rows = self.visible_rows();
+        if rows == 0 {
+            return;
+        }
+
+        let row = (0..rows).find(|row| self.results[self.visible_row(*row)].line_number >= line).unwrap_or(rows - 1);
+        // Scrolled like the results found in the results, with the row centered once it's drawn.
+        self.find.scroll_to = Some(self.visible_row(row));
+    }
+
+    /// Forget the results containing the find term, such that they are looked for again.
+    fn reset_find(&mut self) {
+        self.find.rows.clear();
+        self.find.current = 0;
+        self.find.searched = 0;
+        self.find.scroll_to = None;
+    }
+
+    /// Look for the find term in the results that arrived since the last call.
+    fn update_find(&mut self) {
+        if self.find.term.is_empty() {
+            self.find.searched = self.results.len();
+            return;
+        }
+
+        let pattern = regex::escape(&self.find.term);
+        let finder = match regex::bytes::RegexBuilder::new(&pattern).case_insensitive(true).build() {
+            Ok(finder) => finder,
+            Err(_) => return,
+        };
+
+        // Only the results that are shown are looked at.
+        for (idx, result) in self.results.iter().enumerate().skip(self.find.searched) {
+            if self.visible_position(idx).is_none() {
+                continue;
+            }
+
+            if finder.is_match(&result.bytes) || finder.is_match(result.path.as_bytes()) {
+                self.find.rows.push(idx);
+            }
+        }
+        self.find.searched = self.results.len();
+    }
+
+    /// Jump to the next result containing the find term, or to the previous one.
+    fn find_next(&mut self, forward: bool) {
+        let count = self.find.rows.len();
+        if count == 0 {
+            return;
+        }
+
+        self.find.current = if forward { (self.find.current + 1) % count } else { (self.find.current + count - 1) % count };
+        self.find.scroll_to = Some(self.find.rows[self.find.current]);
+    }
+
+    /// Returns the row showing the result at index `idx` in `results`, if it's visible.
+    fn visible_position(&self, idx: usize) -> Option<usize> {
+        match &self.filtered_rows {
+            Some(rows) => rows.binary_search(&idx).ok(),
+            None => (idx < self.results.len()).then_some(idx),
         }
     }
 
-    fn save_results(results: &mut Vec<UiSearchEntry>, result: SearchResult){
+    /// Returns the estimated memory of the results that were added.
+    fn save_results(results: &mut Vec<UiSearchEntry>, result: SearchResult, arrival: usize) -> usize {
+        let mut memory = 0;
+        let modified = result.modified;
         if let Ok(path) = result.path.into_os_string().into_string() {
             let path = Rc::new(path);
+            let root = Rc::new(result.root.to_string_lossy().into_owned());
             for entry in result.entries.into_iter() {
                 let path = Rc::clone(&path);
-                results.push(UiSearchEntry::new(path, entry));
+                let root = Rc::clone(&root);
+                let entry = UiSearchEntry::new(path, root, entry, modified, arrival);
+                memory += entry.memory_size();
+                results.push(entry);
             }
         } else {
-            println!("Failed to convert the path in a UTF-8 string");
+            log!("Failed to convert the path in a UTF-8 string");
+        }
+        return memory;
+    }
+
+    /// Drop results until their memory is below `max_memory`. The context lines are dropped
+    /// first, then the oldest results.
+    fn trim_results(&mut self, max_memory: usize) {
+        if self.results_memory <= max_memory {
+            return;
+        }
+
+        // Trim a bit more than needed, such that it's not done again for every new result.
+        let target = max_memory - max_memory / 10;
+        let count = self.results.len();
+        self.results.retain(|result| result.kind != EntryKind::Context);
+        self.results_memory = self.results.iter().map(|result| result.memory_size()).sum();
+
+        let mut dropped = 0;
+        while self.results_memory > target && dropped < self.results.len() {
+            self.results_memory -= self.results[dropped].memory_size();
+            dropped += 1;
+        }
+        self.results.drain(..dropped);
+        self.dismissed = self.results.iter().filter(|result| result.dismissed).count();
+        self.results_generation += 1;
+
+        self.trimmed_results += count - self.results.len();
+        log!("Dropped {} result(s) to limit the memory", count - self.results.len());
+
+        // The indices of the rows changed.
+        self.last_focused_row = None;
+        self.last_selected_row = None;
+        self.reset_find();
+        if self.filtered_rows.is_some() {
+            self.update_filter();
         }
     }
 
-    fn update_pending_search(&mut self) {
+    /// Returns true if the search completed.
+    fn update_pending_search(&mut self) -> bool {
         let mut is_done = false;
+        let first_new_row = self.results.len();
         if let Some(pending) = self.pending_search.as_mut() {
+            let started = Instant::now();
             loop {
                 match pending.try_recv() {
                     Ok(result) => {
                         self.file_searched += 1;
-                        if !result.entries.is_empty() {
+                        self.timing.walk += result.walk_duration;
+                        self.timing.search += result.search_duration;
+                        if let Some(error) = result.error {
+                            self.file_errors.push(format!("{}: {}", result.path.to_string_lossy(), error));
+                        } else if !result.entries.is_empty() {
                             self.file_searched_with_results += 1;
-                            Self::save_results(&mut self.results, result);
+                            self.results_memory += Self::save_results(&mut self.results, result, self.file_searched);
                         }
                     },
                     Err(TryRecvError::Empty) => break,
@@ -139,213 +600,1748 @@ impl SearchTab {
                     }
                 }
             }
+            self.timing.collect += started.elapsed();
+        }
+
+        if self.results.len() != first_new_row {
+            self.results_generation += 1;
+        }
+
+        let new_captures = self.results[first_new_row..].iter().map(|result| result.captures.len()).max();
+        self.capture_columns = self.capture_columns.max(new_captures.unwrap_or(0));
+
+        // The filtered rows are only computed for the results that were there. The repeated
+        // lines are always filtered when folded, even if nothing was filtered yet.
+        if self.filtered_rows.is_some() || self.fold_repeats {
+            let new_rows: Vec<usize> = (first_new_row..self.results.len()).filter(|idx| self.is_row_shown(*idx)).collect();
+            self.filtered_rows.get_or_insert_with(Vec::new).extend(new_rows);
         }
 
         if is_done {
             self.pending_search = None;
+            self.rank_results();
+            self.update_extensions();
         }
-    }
 
-    fn is_searching(&self) -> bool {
-        self.pending_search.is_some()
+        return is_done;
     }
 
-    fn search_duration(&self) -> Duration {
-        if let Some(pending) = &self.pending_search {
-            pending.elapsed()
-        } else {
-            self.search_duration
+    /// Order the results by the score of their fuzzy match, best first, or by modification time
+    /// if the results are sorted. This is done once the search is done, such that the results
+    /// don't move while they arrive.
+    fn rank_results(&mut self) {
+        if self.sort_modified.is_none() && self.results.iter().all(|result| result.score == 0) {
+            return;
         }
+
+        self.sort_results();
     }
-}
 
-pub struct SearchTabs {
-    tabs: Vec<SearchTab>,
-    selected_tab: usize,
-    set_selected_tab: Option<usize>,
-}
+    /// Sort the results by modification time in `direction`, or restore the order they were
+    /// received in if there is none.
+    fn sort_by_modified(&mut self, direction: Option<TableSortDirection>) {
+        self.sort_modified = direction;
+        self.sort_results();
+    }
 
-fn search_parallel(tab: &mut SearchTab, settings: &Settings) {
-    tab.cancel_search(true);
+    fn sort_results(&mut self) {
+        // The sorts are stable and the lines of a file share the same keys, so they stay together and in order.
+        match self.sort_modified {
+            Some(direction) => self.results.sort_by(|left, right| {
+                let order = left.modified.cmp(&right.modified);
+                let order = if direction == TableSortDirection::Descending { order.reverse() } else { order };
+                order.then_with(|| left.arrival.cmp(&right.arrival))
+            }),
+            None => self.results.sort_by(|left, right| right.score.cmp(&left.score).then(left.arrival.cmp(&right.arrival))),
+        }
 
-    let non_existing_paths: Vec<String> = tab
-        .config
-        .paths()
-        .into_iter()
-        .filter(|path| !path.exists())
-        .map(|path| path.to_string_lossy().into_owned())
-        .collect();
+        // The indices of the rows changed.
+        self.results_generation += 1;
+        self.last_focused_row = None;
+        self.last_selected_row = self.results.iter().position(|result| result.selected);
+        self.reset_find();
+        if self.filtered_rows.is_some() {
+            self.update_filter();
+        }
+    }
 
-    if !non_existing_paths.is_empty() {
-        let error = format!("Can't open {}", non_existing_paths.join(", "));
-        println!("{}", error);
-        tab.error_message = Some(error);
+    fn extension_of(path: &str) -> String {
+        std::path::Path::new(path)
+            .extension()
+            .map_or(String::from("(none)"), |extension| extension.to_string_lossy().into_owned())
     }
 
-    if let Ok(pending) = search::spawn_search(&tab.config, settings.search_binary, settings.number_of_threads as usize) {
-        tab.pending_search = Some(pending);
+    fn statistics(&self) -> ResultStatistics {
+        ResultStatistics::compute(self.results.iter().map(|result| (result.path.as_str(), result.kind, result.matches.len())))
     }
-}
 
-fn cwd() -> String {
-    std::env::current_dir()
-        .map(|path| {
-            path.into_os_string()
-                .into_string()
-                .unwrap_or(String::from("./"))
-        })
-        .unwrap_or(String::from("./"))
-}
+    fn update_extensions(&mut self) {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for result in self.results.iter() {
+            *counts.entry(Self::extension_of(&result.path)).or_insert(0) += 1;
+        }
 
-fn draw_menu(
-    ui: &Ui,
-    keep_running: &mut bool,
-    state: &mut SearchTabs,
-    settings: &mut SettingsWindow,
-    hotkeys: &mut HotkeysWindow
-) {
-    if let Some(menu) = ui.begin_menu("File") {
-        if ui.menu_item_config("New Tab").shortcut("CTRL+T").build() {
-            state.tabs.push(SearchTab::from_context(cwd()));
+        self.extensions = counts.into_iter().collect();
+        self.extensions.sort();
+    }
+
+    fn toggle_extension(&mut self, extension: &str) {
+        if !self.selected_extensions.remove(extension) {
+            self.selected_extensions.insert(extension.to_owned());
         }
+        self.update_filter();
+    }
 
-        ui.menu_item_config("Open...").shortcut("CTRL+O").build();
-        ui.separator();
-        if ui.menu_item_config("Quit").shortcut("CTRL+Q").build() {
-            *keep_running = false;
+    fn is_shown(&self, result: &UiSearchEntry) -> bool {
+        if result.dismissed {
+            return false;
         }
-        menu.end();
+
+        return self.selected_extensions.is_empty() || self.selected_extensions.contains(&Self::extension_of(&result.path));
     }
 
-    if let Some(menu) = ui.begin_menu("Edit") {
-        ui.menu_item_config("Undo").shortcut("CTRL+Z").build();
-        ui.menu_item_config("Redo").shortcut("CTRL+Y").build();
-        ui.separator();
-        menu.end();
+    /// Whether the result at `idx` is a context line with the same text as the line before it.
+    fn is_repeated_context(&self, idx: usize) -> bool {
+        if idx == 0 {
+            return false;
+        }
+
+        let (previous, result) = (&self.results[idx - 1], &self.results[idx]);
+        return previous.kind == EntryKind::Context
+            && result.kind == EntryKind::Context
+            && Rc::ptr_eq(&previous.path, &result.path)
+            && previous.line_number + 1 == result.line_number
+            && trim_line_terminator(&previous.bytes) == trim_line_terminator(&result.bytes);
     }
 
-    if let Some(menu) = ui.begin_menu("Preferences") {
-        if ui.menu_item_config("Settings").build() {
-            settings.open(true);
+    /// Number of lines folded in the context line at `idx`, including itself.
+    fn repeat_count(&self, idx: usize) -> usize {
+        let mut count = 1;
+        while idx + count < self.results.len() && self.is_repeated_context(idx + count) {
+            count += 1;
         }
-        menu.end();
+        return count;
     }
 
-    if let Some(menu) = ui.begin_menu("Help") {
-        ui.text("Version: 0.1.0\nHomepage: https://github.com/reduf/search");
-        ui.separator();
-        if ui.menu_item_config("Hotkeys").shortcut("F1").build() {
-            hotkeys.toggle_open();
+    fn is_row_shown(&self, idx: usize) -> bool {
+        self.is_shown(&self.results[idx]) && !(self.fold_repeats && self.is_repeated_context(idx))
+    }
+
+    fn update_filter(&mut self) {
+        self.results_generation += 1;
+        self.reset_find();
+        if self.selected_extensions.is_empty() && self.dismissed == 0 && !self.fold_repeats {
+            self.filtered_rows = None;
+            return;
         }
-        menu.end();
+
+        let rows = (0..self.results.len()).filter(|idx| self.is_row_shown(*idx)).collect();
+        self.filtered_rows = Some(rows);
     }
-}
 
-fn draw_text_from_cow(ui: &Ui, color: Option<[f32; 4]>, text: std::borrow::Cow<'_, str>) {
-    use std::borrow::Cow;
-    let _style = color.map(|color| ui.push_style_color(StyleColor::Text, color));
-    match text {
-        Cow::Borrowed(text) => ui.text(text),
-        Cow::Owned(text) => ui.text(text),
+    /// Hide the result, it stays in `results` such that it can be restored.
+    fn dismiss(&mut self, row: usize) {
+        let result = &mut self.results[row];
+        if result.dismissed {
+            return;
+        }
+
+        result.dismissed = true;
+        result.selected = false;
+        self.dismissed += 1;
+
+        // The indices in `results` don't change, only the dismissed row must be forgotten.
+        if self.last_selected_row == Some(row) {
+            self.last_selected_row = None;
+        }
+        if self.last_focused_row == Some(row) {
+            self.last_focused_row = None;
+        }
+        self.update_filter();
     }
-}
 
-fn draw_result(ui: &Ui, result: &UiSearchEntry) {
-    const COLOR_RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-    let mut printed = 0;
-    for (start, end) in result.matches.iter().map(|&val| val) {
-        draw_text_from_cow(ui, None, String::from_utf8_lossy(&result.bytes[printed..start]));
-        ui.same_line_with_spacing(0.0, 0.0);
-        draw_text_from_cow(ui, Some(COLOR_RED), String::from_utf8_lossy(&result.bytes[start..end]));
-        ui.same_line_with_spacing(0.0, 0.0);
-        printed = end;
+    fn clear_dismissed(&mut self) {
+        for result in self.results.iter_mut() {
+            result.dismissed = false;
+        }
+        self.dismissed = 0;
+        self.update_filter();
     }
-    draw_text_from_cow(ui, None, String::from_utf8_lossy(&result.bytes[printed..]));
-}
 
-fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab, settings: &Settings) {
-    tab.update_pending_search();
+    fn visible_rows(&self) -> usize {
+        self.filtered_rows.as_ref().map_or(self.results.len(), |rows| rows.len())
+    }
 
-    let mut flags = TabItemFlags::empty();
-    if state.set_selected_tab == Some(tab_id) {
-        flags |= TabItemFlags::SET_SELECTED;
-        state.set_selected_tab = None;
+    /// Returns the index in `results` of the n-th visible row.
+    fn visible_row(&self, row: usize) -> usize {
+        self.filtered_rows.as_ref().map_or(row, |rows| rows[row])
     }
 
-    flags |= TabItemFlags::TRAILING;
+    /// Path and line number of the selected result, shown in the footer.
+    fn selected_location(&self) -> Option<String> {
+        let result = &self.results[self.last_selected_row?];
+        if !result.selected {
+            return None;
+        }
 
-    let label = format!("{}###{}", tab.config.paths, tab_id);
-    let mut keep_open = true;
-    TabItem::new(label).opened(&mut keep_open).flags(flags).build(ui, || {
-        // If we enter this block, we are in the selected tab.
-        state.selected_tab = tab_id;
+        if result.kind == EntryKind::FileName {
+            return Some(result.path.to_string());
+        }
 
-        let mut search = false;
-        if let Some(_t) = ui.begin_table_with_flags("Basic-Table", 2, TableFlags::SIZING_FIXED_FIT) {
-            // ui.text("Search:");
+        return Some(format!("{}:{}", result.path, result.line_number));
+    }
 
-            ui.table_setup_column_with(TableColumnSetup { name: "##labels", flags: TableColumnFlags::WIDTH_FIXED, init_width_or_weight: 0.0, user_id: Id::default() });
-            ui.table_setup_column_with(TableColumnSetup { name: "##widgets", flags: TableColumnFlags::WIDTH_STRETCH, init_width_or_weight: 0.0, user_id: Id::default() });
-            ui.table_next_row();
+    /// Select the result recorded in `reselect` again, and scroll to it. Returns false if it's
+    /// not in the results anymore.
+    fn reselect(&mut self) -> bool {
+        let (path, line_number) = match self.reselect.take() {
+            Some(location) => location,
+            None => return false,
+        };
 
-            ui.table_next_column();
-            ui.text("Paths:");
-            ui.table_next_column();
-            if ui
-                .input_text("##paths", &mut tab.config.paths)
-                .enter_returns_true(true)
-                .build()
-            {
-                // Keep the focus in the search input making it easier to iterate.
-                ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
-                search = true;
-            }
-            show_help(ui, help::PATHS_USAGE);
+        let found = self
+            .results
+            .iter()
+            .position(|result| result.path.as_str() == path && result.line_number == line_number);
+        let row = match found {
+            Some(row) if self.visible_position(row).is_some() => row,
+            _ => return false,
+        };
 
-            ui.table_next_column();
-            ui.text("Patterns:");
-            ui.table_next_column();
-            if ui
-                .input_text("##globs", &mut tab.config.globs)
-                .enter_returns_true(true)
-                .hint("*.txt *.cpp")
-                .build()
-            {
-                search = true;
-                // Keep the focus in the search input making it easier to iterate.
-                ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
-            }
-            show_help(ui, help::GLOBS_USAGE);
+        self.select_and_scroll_to(row);
+        return true;
+    }
 
-            let queries = std::mem::replace(&mut tab.config.queries, vec![]);
-            for (idx, mut query) in queries.into_iter().enumerate() {
-                // Dropping this value pop the id from IMGUI stack.
-                let _stack = ui.push_id_usize(idx);
+    /// Select only the result at index `row` in `results` and scroll to it.
+    fn select_and_scroll_to(&mut self, row: usize) {
+        for result in self.results.iter_mut() {
+            result.selected = false;
+        }
 
-                ui.table_next_column();
+        self.results[row].selected = true;
+        self.last_selected_row = Some(row);
+        self.last_focused_row = Some(row);
+        self.find.scroll_to = Some(row);
+        self.focus_row = Some(row);
+    }
 
-                // How can we calculate that dynamically such that the button fits in the window?
-                ui.table_next_column();
-                let _w = ui.push_item_width(450.0);
-                if ui
-                    .input_text("##search", &mut query.query)
-                    .hint("(press enter to search)")
-                    .enter_returns_true(true)
-                    .build()
-                {
-                    search = true;
+    /// Fill the paths with their own patterns from the simple fields, the first time they are used,
+    /// such that the patterns don't have to be typed again.
+    fn init_roots(&mut self) {
+        if !self.config.per_root_globs || !self.config.roots.is_empty() {
+            return;
+        }
 
-                    // Keep the focus in the search input making it easier to iterate.
-                    ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
-                }
+        let globs = self.config.globs.clone();
+        self.config.roots = self
+            .config
+            .paths()
+            .iter()
+            .map(|path| SearchRoot { path: path.to_string_lossy().into_owned(), globs: globs.clone() })
+            .collect();
+    }
+
+    /// Select and scroll to the n-th visible result, starting from 1. The number is clamped to
+    /// the visible results.
+    fn go_to_result(&mut self, number: i32) {
+        let count = self.visible_rows();
+        if count == 0 {
+            return;
+        }
 
+        let row = (number.max(1) as usize).min(count) - 1;
+        self.select_and_scroll_to(self.visible_row(row));
+    }
+
+    /// List of the files with results, one per line, without duplicates.
+    fn file_list(&self) -> String {
+        let mut seen = HashSet::new();
+        let files: Vec<&str> = self
+            .results
+            .iter()
+            .map(|result| result.path.as_str())
+            .filter(|path| seen.insert(*path))
+            .collect();
+        files.join("\n")
+    }
+
+    /// Comma separated list of the line numbers matched in the file `path`.
+    fn line_numbers(&self, path: &str) -> String {
+        let lines: Vec<String> = self
+            .results
+            .iter()
+            .filter(|result| result.path.as_str() == path && result.kind != EntryKind::FileName)
+            .map(|result| result.line_number.to_string())
+            .collect();
+        lines.join(", ")
+    }
+
+    /// Range, in `results`, of the contiguous lines of the file around the result, that is
+    /// the matches with their context lines.
+    fn line_group(&self, row: usize) -> std::ops::Range<usize> {
+        // File name results are alone in their group, as they have no line.
+        let follows = |idx: usize| {
+            let (previous, result) = (&self.results[idx - 1], &self.results[idx]);
+            Rc::ptr_eq(&previous.path, &result.path)
+                && result.kind != EntryKind::FileName
+                && previous.kind != EntryKind::FileName
+                && result.line_number == previous.line_number + 1
+        };
+
+        let mut start = row;
+        while start > 0 && follows(start) {
+            start -= 1;
+        }
+
+        let mut end = row + 1;
+        while end < self.results.len() && follows(end) {
+            end += 1;
+        }
+
+        return start..end;
+    }
+
+    /// Lines of the file around the result.
+    fn snippet(&self, row: usize) -> String {
+        let lines: Vec<std::borrow::Cow<'_, str>> = self.results[self.line_group(row)]
+            .iter()
+            .map(|result| String::from_utf8_lossy(trim_line_terminator(&result.bytes)))
+            .collect();
+        return lines.join("\n") + "\n";
+    }
+
+    /// Lines of the file around the result, with the line numbers in a gutter like an editor.
+    fn excerpt(&self, row: usize) -> String {
+        let group = &self.results[self.line_group(row)];
+        let width = group.last().map_or(1, |result| result.line_number.to_string().len());
+        let mut content = String::new();
+        for result in group.iter() {
+            let text = String::from_utf8_lossy(trim_line_terminator(&result.bytes));
+            if result.kind == EntryKind::FileName {
+                content.push_str(&format!("{}\n", text));
+            } else {
+                content.push_str(&format!("{:>width$} | {}\n", result.line_number, text, width = width));
+            }
+        }
+        return content;
+    }
+
+    /// Visible rows to export, with their path shown relative to their root if `relative`.
+    fn export_rows(&self, relative: bool) -> Vec<export::ExportRow<'_>> {
+        // The folded lines are exported, as the folding only changes how they are shown.
+        let rows: Vec<usize> = if self.fold_repeats {
+            (0..self.results.len()).filter(|idx| self.is_shown(&self.results[*idx])).collect()
+        } else {
+            (0..self.visible_rows()).map(|row| self.visible_row(row)).collect()
+        };
+
+        rows.into_iter()
+            .map(|idx| {
+                let result = &self.results[idx];
+                let path = if relative { relative_path(&result.path, &result.root) } else { result.path.as_str() };
+                export::ExportRow { path, line_number: result.line_number, kind: result.kind, bytes: &result.bytes, captures: &result.captures }
+            })
+            .collect()
+    }
+
+    /// Text of every match of the visible rows, one per line. Returns the text and whether it
+    /// was truncated to `MAX_COPIED_MATCHES_LEN`.
+    fn matched_text(&self, unique: bool) -> (String, bool) {
+        let mut seen = HashSet::new();
+        let mut content = String::new();
+        for row in 0..self.visible_rows() {
+            let result = &self.results[self.visible_row(row)];
+            for matche in result.matches.iter() {
+                let text = String::from_utf8_lossy(&result.bytes[matche.start..matche.end]);
+                if unique && !seen.insert(text.clone()) {
+                    continue;
+                }
+
+                if content.len() + text.len() + 1 > MAX_COPIED_MATCHES_LEN {
+                    return (content, true);
+                }
+
+                content.push_str(&text);
+                content.push('\n');
+            }
+        }
+        (content, false)
+    }
+
+    /// Files to open in an editor supporting multiple files, with the line of their first
+    /// result. Only the selected results are used if there are any.
+    fn editor_files(&self) -> anyhow::Result<Vec<(String, usize)>> {
+        let rows: Vec<&UiSearchEntry> = (0..self.visible_rows()).map(|row| &self.results[self.visible_row(row)]).collect();
+        let any_selected = rows.iter().any(|result| result.selected);
+
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for result in rows.into_iter().filter(|result| (result.selected || !any_selected) && result.has_file()) {
+            if seen.insert(result.path.as_str()) {
+                let path = archive::local_path(&result.path)?;
+                // File name results don't have a line, so open the file at the start.
+                files.push((path.to_string_lossy().into_owned(), std::cmp::max(result.line_number, 1) as usize));
+            }
+        }
+        Ok(files)
+    }
+
+    /// Results formatted like the output of grep, one "path:line:text" per line.
+    fn export_text(&self) -> String {
+        let mut content = String::new();
+        for result in self.results.iter() {
+            content.push_str(&result.path);
+            if result.kind != EntryKind::FileName {
+                let separator = if result.kind == EntryKind::Context { '-' } else { ':' };
+                content.push(separator);
+                content.push_str(&result.line_number.to_string());
+                content.push(separator);
+                content.push_str(&String::from_utf8_lossy(trim_line_terminator(&result.bytes)));
+            }
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Highlight the matches of the current queries in the loaded results, without
+    /// searching the files again. Context lines are left untouched.
+    fn rehighlight(&mut self) {
+        // The fuzzy matches of the file names can't be found by the highlighter.
+        if self.config.fuzzy_file_names() {
+            return;
+        }
+
+        let highlighter = match Highlighter::new(&self.config.queries) {
+            Ok(highlighter) => highlighter,
+            Err(_) => return,
+        };
+
+        for result in self.results.iter_mut().filter(|result| result.kind != EntryKind::Context) {
+            result.matches = highlighter.find(&result.bytes);
+        }
+    }
+
+    fn is_searching(&self) -> bool {
+        self.pending_search.is_some()
+    }
+
+    fn is_replacing(&self) -> bool {
+        self.pending_replace.is_some()
+    }
+
+    /// Group the matches of the shown results per file, such that they can be replaced. The
+    /// results dismissed or filtered out by their extension are never replaced.
+    fn file_replacements(&self) -> Vec<FileReplacement> {
+        let mut files: Vec<FileReplacement> = Vec::new();
+        let mut indices: HashMap<&str, usize> = HashMap::new();
+        let replaceable = |result: &&UiSearchEntry| result.kind == EntryKind::Match && !result.matches.is_empty();
+        let shown = (0..self.visible_rows()).map(|row| &self.results[self.visible_row(row)]);
+        for result in shown.filter(replaceable) {
+            let idx = *indices.entry(result.path.as_str()).or_insert_with(|| {
+                files.push(FileReplacement { path: PathBuf::from(result.path.as_str()), lines: Vec::new() });
+                files.len() - 1
+            });
+
+            let lines = &mut files[idx].lines;
+            if lines.last().is_some_and(|line| line.line_number == result.line_number) {
+                // The same line can be found from overlapping search paths.
+                continue;
+            }
+
+            lines.push(LineReplacement {
+                line_number: result.line_number,
+                bytes: result.bytes.clone(),
+                matches: result.matches.iter().map(|matche| (matche.start, matche.end)).collect(),
+            });
+        }
+        files
+    }
+
+    fn start_replace(&mut self, files: Vec<FileReplacement>) {
+        self.replaced_matches = 0;
+        self.replaced_files = 0;
+        self.replace_errors = 0;
+        self.pending_replace = Some(spawn_replace(files, self.replace.clone(), self.preserve_case));
+    }
+
+    /// Returns true once the pending replace is finished.
+    fn update_pending_replace(&mut self) -> bool {
+        let pending = if let Some(pending) = self.pending_replace.as_ref() {
+            pending
+        } else {
+            return false;
+        };
+
+        loop {
+            match pending.try_recv() {
+                Ok(result) => {
+                    if result.error.is_some() {
+                        self.replace_errors += 1;
+                    } else {
+                        self.replaced_files += 1;
+                        self.replaced_matches += result.replaced;
+                    }
+                },
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        self.pending_replace = None;
+        log!("Replaced {} match(es) in {} file(s)", self.replaced_matches, self.replaced_files);
+        return true;
+    }
+
+    fn is_paused(&self) -> bool {
+        self.pending_search.as_ref().is_some_and(|pending| pending.is_paused())
+    }
+
+    fn toggle_pause(&mut self) {
+        if let Some(pending) = self.pending_search.as_mut() {
+            if pending.is_paused() {
+                pending.resume();
+            } else {
+                pending.pause();
+            }
+        }
+    }
+
+    fn search_duration(&self) -> Duration {
+        if let Some(pending) = &self.pending_search {
+            pending.elapsed()
+        } else {
+            self.search_duration
+        }
+    }
+}
+
+pub struct SearchTabs {
+    tabs: Vec<SearchTab>,
+    selected_tab: usize,
+    set_selected_tab: Option<usize>,
+    /// Tabs opened while drawing the tabs, searched and added once every tab is drawn.
+    new_tabs: Vec<SearchTab>,
+    /// Give the keyboard focus to the first query of the selected tab.
+    focus_query: bool,
+    snippets: snippet::SnippetFiles,
+    /// Folder picked in the dialog of "Open...", received once the dialog is closed.
+    picked_folder: Option<std::sync::mpsc::Receiver<anyhow::Result<Option<PathBuf>>>>,
+    /// Quitting was requested while a replace is running, ask for a confirmation.
+    confirm_quit: bool,
+}
+
+/// Ask for a folder to search in a new tab, unless the dialog is already opened.
+fn open_folder(state: &mut SearchTabs) {
+    if state.picked_folder.is_none() {
+        state.picked_folder = Some(sys::spawn_pick_folder());
+    }
+}
+
+/// Add a tab searching the picked folder, once the dialog is closed.
+fn receive_picked_folder(state: &mut SearchTabs, settings: &Settings) {
+    let picked = match state.picked_folder.as_ref().map(|picked| picked.try_recv()) {
+        Some(Ok(picked)) => picked,
+        Some(Err(TryRecvError::Disconnected)) => Ok(None),
+        Some(Err(TryRecvError::Empty)) | None => return,
+    };
+
+    state.picked_folder = None;
+    match picked {
+        Ok(Some(path)) => {
+            state.tabs.push(SearchTab::from_context(path.to_string_lossy().into_owned(), settings));
+            state.focus_query = true;
+        }
+        Ok(None) => (),
+        Err(err) => log!("{}", err),
+    }
+}
+
+fn search_parallel(tab: &mut SearchTab, settings: &Settings) {
+    tab.reselect = if settings.keep_selection_on_search {
+        tab.last_selected_row
+            .map(|row| &tab.results[row])
+            .filter(|result| result.selected)
+            .map(|result| (result.path.to_string(), result.line_number))
+    } else {
+        None
+    };
+    tab.cancel_search(true);
+
+    if let Some(stdin) = tab.stdin.as_ref() {
+        search_stdin_parallel(tab, Arc::clone(stdin), settings);
+        return;
+    }
+
+    let non_existing_paths: Vec<String> = tab
+        .config
+        .paths()
+        .into_iter()
+        .filter(|path| !path.exists())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    if !non_existing_paths.is_empty() {
+        let error = format!("Can't open {}", non_existing_paths.join(", "));
+        log!("{}", error);
+        tab.error_message = Some(error);
+    }
+
+    let invalid_query = tab.config.queries.iter().find(|query| query.check_syntax().is_some());
+    if let Some(query) = invalid_query {
+        let error = format!("Invalid regex '{}'", query.query);
+        log!("{}", error);
+        tab.error_message = Some(error);
+        return;
+    }
+
+    match tab.config.walk_roots() {
+        Ok(roots) => tab.searched_roots = roots.len(),
+        Err(err) => {
+            let error = err.to_string();
+            log!("{}", error);
+            tab.error_message = Some(error);
+            return;
+        }
+    }
+
+    tab.config.max_paragraph_lines = settings.max_paragraph_lines.max(1) as usize;
+    tab.history.push(tab.config.clone());
+    let overrides = &tab.overrides;
+    let binary_mode = overrides.binary_mode.unwrap_or(settings.binary_mode);
+    let search_archives = overrides.search_archives.unwrap_or(settings.search_archives);
+    let number_of_threads = overrides.number_of_threads.unwrap_or(settings.number_of_threads);
+    if settings.verbose_search {
+        log!("Search started:");
+        for line in tab.config.describe_roots().unwrap_or_default().into_iter().chain(tab.config.describe_queries()) {
+            log!("    {}", line);
+        }
+        log!(
+            "    Threads: {}, binary files: {:?}, symlinks: {:?}, archives: {}",
+            search::thread_count(number_of_threads),
+            binary_mode,
+            settings.symlink_mode,
+            search_archives
+        );
+    }
+
+    if let Ok(pending) = search::spawn_search(&tab.config, binary_mode, settings.symlink_mode, search_archives, number_of_threads) {
+        tab.pending_search = Some(pending);
+        tab.searched = true;
+    }
+}
+
+/// Command opening the file of a result at its line with the editor of its extension.
+fn result_editor_command(result: &UiSearchEntry, settings: &Settings) -> anyhow::Result<EditorCommand> {
+    let path = archive::local_path(&result.path)?;
+    let editor = settings.editor_command_for(&path);
+    if editor.is_empty() {
+        return Err(anyhow::anyhow!("Editor not configured"));
+    }
+
+    let file_path = path.to_string_lossy().into_owned();
+    // File name results don't have a line, so open the file at the start.
+    let line_number = std::cmp::max(result.line_number, 1) as usize;
+    let command = if is_multi_file_editor(editor) {
+        build_command_multi(editor, vec![(file_path, line_number)])?
+    } else {
+        build_command(editor, file_path, line_number, settings.editor_in_file_dir)?
+    };
+    return Ok(EditorCommand { command, files: vec![path] });
+}
+
+/// Commands opening the files at their line with the editor of their extension. The files
+/// sharing an editor supporting multiple files are opened by a single command. An editor
+/// opening a single file only opens the `focused` file, like the active editor does, rather
+/// than being started once per file. Returns the commands and the number of files skipped.
+fn files_editor_commands(files: Vec<(String, usize)>, focused: Option<&str>, settings: &Settings) -> anyhow::Result<(Vec<EditorCommand>, usize)> {
+    let mut commands = Vec::new();
+    let mut skipped = 0;
+    let mut multi_file_editors: Vec<(&str, Vec<(String, usize)>)> = Vec::new();
+    for (file_path, line_number) in files.into_iter() {
+        let editor = settings.editor_command_for(std::path::Path::new(&file_path));
+        if editor.is_empty() {
+            return Err(anyhow::anyhow!("Editor not configured for '{}'", file_path));
+        }
+
+        if is_multi_file_editor(editor) {
+            match multi_file_editors.iter_mut().find(|(multi_file_editor, _)| *multi_file_editor == editor) {
+                Some((_, files)) => files.push((file_path, line_number)),
+                None => multi_file_editors.push((editor, vec![(file_path, line_number)])),
+            }
+        } else if focused == Some(file_path.as_str()) {
+            let path = PathBuf::from(&file_path);
+            let command = build_command(editor, file_path, line_number, settings.editor_in_file_dir)
+                .map_err(|err| anyhow::anyhow!("editor '{}': {}", editor, err))?;
+            commands.push(EditorCommand { command, files: vec![path] });
+        } else {
+            skipped += 1;
+        }
+    }
+
+    for (editor, files) in multi_file_editors.into_iter() {
+        let paths = files.iter().map(|(path, _)| PathBuf::from(path)).collect();
+        let command = build_command_multi(editor, files).map_err(|err| anyhow::anyhow!("editor '{}': {}", editor, err))?;
+        commands.push(EditorCommand { command, files: paths });
+    }
+
+    return Ok((commands, skipped));
+}
+
+/// Do the action configured for double clicking a result.
+fn double_click_result(ui: &Ui, tab: &mut SearchTab, row: usize, settings: &Settings) {
+    let path = tab.results[row].path.as_str();
+    let opened = match settings.double_click_action {
+        DoubleClickAction::OpenInEditor => {
+            tab.open_in_editor = Some(row);
+            Ok(())
+        },
+        DoubleClickAction::OpenContainingFolder => archive::local_path(path).and_then(|path| match path.parent() {
+            Some(dir) => sys::open_file(dir),
+            None => Err(anyhow::anyhow!("'{}' has no parent directory", path.to_string_lossy())),
+        }),
+        DoubleClickAction::OpenWithDefaultApp => archive::local_path(path).and_then(|path| sys::open_file(&path)),
+        DoubleClickAction::CopyPath => {
+            ui.set_clipboard_text(path);
+            Ok(())
+        },
+    };
+
+    if let Err(err) = opened {
+        let error = err.to_string();
+        log!("{}", error);
+        tab.error_message = Some(error);
+    }
+}
+
+/// Go back or forward through the searches of the tab, like in a browser, and search again.
+fn navigate_history(tab: &mut SearchTab, settings: &Settings, forward: bool) {
+    let config = if forward { tab.history.forward() } else { tab.history.back() };
+    if let Some(config) = config {
+        tab.config = config;
+        search_parallel(tab, settings);
+    }
+}
+
+/// Search the piped input rather than the paths, the result is received like any other search.
+fn search_stdin_parallel(tab: &mut SearchTab, stdin: Arc<Vec<u8>>, settings: &Settings) {
+    let invalid_query = tab.config.queries.iter().find(|query| query.check_syntax().is_some());
+    if let Some(query) = invalid_query {
+        let error = format!("Invalid regex '{}'", query.query);
+        log!("{}", error);
+        tab.error_message = Some(error);
+        return;
+    }
+
+    tab.searched_roots = 1;
+    tab.config.max_paragraph_lines = settings.max_paragraph_lines.max(1) as usize;
+    tab.history.push(tab.config.clone());
+    if settings.verbose_search {
+        log!("Search of the piped input started:");
+        for line in tab.config.describe_queries() {
+            log!("    {}", line);
+        }
+    }
+    if let Ok(pending) = search::spawn_stdin_search(&tab.config, stdin, tab.overrides.binary_mode.unwrap_or(settings.binary_mode)) {
+        tab.pending_search = Some(pending);
+    }
+}
+
+fn cwd() -> String {
+    std::env::current_dir()
+        .map(|path| {
+            path.into_os_string()
+                .into_string()
+                .unwrap_or(String::from("./"))
+        })
+        .unwrap_or(String::from("./"))
+}
+
+fn open_workspace(path: &str, state: &mut SearchTabs, settings: &mut Settings, workspace: &mut WorkspaceWindow) {
+    match set_workspace(path) {
+        Ok(path) => {
+            push_recent_workspace(&mut settings.recent_workspaces, path.to_string_lossy().into_owned());
+            if settings.reset_tabs_on_workspace {
+                let mut tab = SearchTab::from_context(cwd(), settings);
+                if settings.restore_workspace_search {
+                    tab.restore_workspace_search(&settings.workspace_summaries);
+                }
+                state.tabs.clear();
+                state.tabs.push(tab);
+                state.set_selected_tab = Some(0);
+            }
+        }
+        Err(err) => {
+            log!("{}", err);
+            workspace.open(path.to_owned(), Some(err.to_string()));
+        }
+    }
+}
+
+/// Windows opened from the menu and the command palette, drawn over the tabs.
+struct ToolWindows {
+    hotkeys: HotkeysWindow,
+    log: LogWindow,
+    workspace: WorkspaceWindow,
+    rg_import: RgImportWindow,
+    statistics: StatisticsWindow,
+}
+
+fn draw_menu(ui: &Ui, keep_running: &mut bool, state: &mut SearchTabs, settings: &mut SettingsWindow, windows: &mut ToolWindows) {
+    let ToolWindows { hotkeys, log, workspace, rg_import, statistics } = windows;
+    if let Some(menu) = ui.begin_menu("File") {
+        if ui.menu_item_config("New Tab").shortcut("CTRL+T").build() {
+            state.tabs.push(SearchTab::from_context(cwd(), &settings.settings));
+        }
+
+        if ui.menu_item_config("Open...").shortcut("CTRL+O").build() {
+            open_folder(state);
+        }
+        ui.separator();
+        if ui.menu_item("Import ripgrep command...") {
+            rg_import.open();
+        }
+
+        ui.separator();
+        if ui.menu_item("Open Workspace...") {
+            workspace.open(cwd(), None);
+        }
+
+        let mut recent_workspace = None;
+        let has_recent = !settings.settings.recent_workspaces.is_empty();
+        if let Some(_m) = ui.begin_menu_with_enabled("Recent Workspaces", has_recent) {
+            for path in settings.settings.recent_workspaces.iter() {
+                if ui.menu_item(path) {
+                    recent_workspace = Some(path.clone());
+                }
+            }
+        }
+
+        if let Some(path) = recent_workspace {
+            open_workspace(&path, state, &mut settings.settings, workspace);
+        }
+
+        ui.separator();
+        if ui.menu_item_config("Quit").shortcut("CTRL+Q").build() {
+            request_quit(keep_running, state);
+        }
+        menu.end();
+    }
+
+    if let Some(menu) = ui.begin_menu("Edit") {
+        let can_go_back = state.tabs.get(state.selected_tab).is_some_and(|tab| tab.history.can_go_back());
+        if ui.menu_item_config("Undo").shortcut("CTRL+Z").enabled(can_go_back).build() {
+            if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                navigate_history(tab, &settings.settings, false);
+            }
+        }
+        let can_go_forward = state.tabs.get(state.selected_tab).is_some_and(|tab| tab.history.can_go_forward());
+        if ui.menu_item_config("Redo").shortcut("CTRL+Y").enabled(can_go_forward).build() {
+            if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                navigate_history(tab, &settings.settings, true);
+            }
+        }
+        ui.separator();
+        if ui.menu_item_config("Clear Results").shortcut("CTRL+L").enabled(!state.tabs.is_empty()).build() {
+            if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                tab.cancel_search(true);
+            }
+        }
+        let tab = state.tabs.get(state.selected_tab);
+        if ui.menu_item_config("Statistics...").enabled(tab.is_some()).build() {
+            if let Some(tab) = tab {
+                statistics.open(tab.statistics());
+            }
+        }
+        menu.end();
+    }
+
+    if let Some(menu) = ui.begin_menu("Preferences") {
+        if ui.menu_item_config("Settings").build() {
+            settings.open(true);
+        }
+        ui.menu_item_config("Group Results by File").build_with_ref(&mut settings.settings.group_by_file);
+        ui.menu_item_config("Fold Repeated Context Lines").build_with_ref(&mut settings.settings.fold_repeated_context);
+        ui.menu_item_config("Show Modification Time").build_with_ref(&mut settings.settings.show_modified_time);
+        ui.menu_item_config("Show Match Minimap").build_with_ref(&mut settings.settings.show_minimap);
+        menu.end();
+    }
+
+    if let Some(menu) = ui.begin_menu("Help") {
+        ui.text("Version: 0.1.0\nHomepage: https://github.com/reduf/search");
+        ui.separator();
+        if ui.menu_item_config("Hotkeys").shortcut("F1").build() {
+            hotkeys.toggle_open();
+        }
+        if ui.menu_item("Log") {
+            log.toggle_open();
+        }
+        menu.end();
+    }
+}
+
+fn draw_text_from_cow(ui: &Ui, color: Option<[f32; 4]>, text: std::borrow::Cow<'_, str>) {
+    use std::borrow::Cow;
+    let _style = color.map(|color| ui.push_style_color(StyleColor::Text, color));
+    match text {
+        Cow::Borrowed(text) => ui.text(text),
+        Cow::Owned(text) => ui.text(text),
+    }
+}
+
+/// Limit the size of the matched text copied to the clipboard.
+const MAX_COPIED_MATCHES_LEN: usize = 16 * 1024 * 1024;
+
+/// Each query gets its own color, such that it's easy to see which query matched what.
+const MATCH_COLORS: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 1.0],
+    [0.0, 0.8, 0.0, 1.0],
+    [0.2, 0.5, 1.0, 1.0],
+    [1.0, 0.6, 0.0, 1.0],
+];
+
+/// Heuristic to detect lines that are binary data, which are not worth displaying as text.
+fn is_binary_line(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+
+    let non_printable = bytes
+        .iter()
+        .filter(|&&value| value < 0x20 && !matches!(value, b'\t' | b'\r' | b'\n'))
+        .count();
+    return bytes.len() >= 8 && non_printable * 10 > bytes.len();
+}
+
+/// Color of the context lines, which is the text color faded toward the background,
+/// such that the matched lines stand out.
+fn context_color(ui: &Ui, style_color: crate::settings::StyleColor) -> [f32; 4] {
+    let text = ui.style_color(imgui::StyleColor::Text);
+    let background = ui.style_color(imgui::StyleColor::WindowBg);
+    let dim = style_color.context_dim();
+    let mut color = text;
+    for idx in 0..3 {
+        color[idx] = text[idx] + (background[idx] - text[idx]) * dim;
+    }
+    return color;
+}
+
+fn draw_result(ui: &Ui, result: &UiSearchEntry, settings: &Settings) {
+    let _dim = (result.kind == EntryKind::Context)
+        .then(|| ui.push_style_color(imgui::StyleColor::Text, context_color(ui, settings.style_color)));
+    let first_match = result.matches.first().map_or(0, |matche| matche.start);
+    let range = display_range(&result.bytes, first_match, settings.display_line_len());
+    let bytes = &result.bytes[range.clone()];
+
+    // Matches clipped to the displayed range, with offsets relative to the start of the range.
+    let matches: Vec<SearchMatch> = result
+        .matches
+        .iter()
+        .filter(|matche| matche.end > range.start && matche.start < range.end)
+        .map(|matche| SearchMatch {
+            start: std::cmp::max(matche.start, range.start) - range.start,
+            end: std::cmp::min(matche.end, range.end) - range.start,
+            query: matche.query,
+        })
+        .collect();
+
+    if result.kind == EntryKind::FileName {
+        ui.text_disabled("[file name] ");
+        ui.same_line_with_spacing(0.0, 0.0);
+    }
+
+    if range.start != 0 {
+        ui.text_disabled("...");
+        ui.same_line_with_spacing(0.0, 0.0);
+    }
+
+    if settings.hex_binary_lines && result.binary {
+        draw_line_hex(ui, result.offset + range.start as u64, bytes, &matches, settings.match_highlight);
+    } else {
+        draw_highlighted(ui, bytes, &matches, None, &MATCH_COLORS, settings.match_highlight);
+    }
+
+    if range.end != trim_line_terminator(&result.bytes).len() {
+        ui.same_line_with_spacing(0.0, 0.0);
+        ui.text_disabled("...");
+    }
+}
+
+/// Draw the path of the result, highlighting the matches of file name results.
+fn draw_path(ui: &Ui, result: &UiSearchEntry, relative: bool, style: MatchHighlightStyle) {
+    let path = if relative { relative_path(&result.path, &result.root) } else { result.path.as_str() };
+    let bytes = path.as_bytes();
+    if result.kind != EntryKind::FileName || !bytes.ends_with(&result.bytes) {
+        ui.text(path);
+        return;
+    }
+
+    // The matches are relative to the file name, which is at the end of the path.
+    let offset = bytes.len() - result.bytes.len();
+    let matches: Vec<SearchMatch> = result
+        .matches
+        .iter()
+        .map(|matche| SearchMatch { start: matche.start + offset, end: matche.end + offset, query: matche.query })
+        .collect();
+    draw_highlighted(ui, bytes, &matches, None, &MATCH_COLORS, style);
+}
+
+/// Draw the text of a match in the given style. The underline and the background are added
+/// to the draw list around the text, so the text takes the same space with every style.
+fn draw_match_text(ui: &Ui, text: std::borrow::Cow<'_, str>, color: [f32; 4], base_color: Option<[f32; 4]>, style: MatchHighlightStyle) {
+    if style == MatchHighlightStyle::Color {
+        draw_text_from_cow(ui, Some(color), text);
+        return;
+    }
+
+    let min = ui.cursor_screen_pos();
+    let size = ui.calc_text_size(&text);
+    let max = [min[0] + size[0], min[1] + size[1]];
+    let draw_list = ui.get_window_draw_list();
+    if style == MatchHighlightStyle::Background {
+        // Added before the text, such that it's drawn behind it.
+        draw_list.add_rect(min, max, [color[0], color[1], color[2], 0.35]).filled(true).build();
+    }
+
+    draw_text_from_cow(ui, base_color, text);
+
+    if style == MatchHighlightStyle::Underline {
+        draw_list.add_line([min[0], max[1] - 1.0], [max[0], max[1] - 1.0], color).thickness(1.5).build();
+    }
+}
+
+/// Draw the text with `spans` drawn in the color of their query, and the rest in `base_color`.
+fn draw_highlighted(
+    ui: &Ui,
+    bytes: &[u8],
+    spans: &[SearchMatch],
+    base_color: Option<[f32; 4]>,
+    match_colors: &[[f32; 4]],
+    style: MatchHighlightStyle,
+) {
+    let mut printed = 0;
+    for span in spans.iter() {
+        let color = match_colors[span.query % match_colors.len()];
+        draw_text_from_cow(ui, base_color, String::from_utf8_lossy(&bytes[printed..span.start]));
+        ui.same_line_with_spacing(0.0, 0.0);
+        draw_match_text(ui, String::from_utf8_lossy(&bytes[span.start..span.end]), color, base_color, style);
+        ui.same_line_with_spacing(0.0, 0.0);
+        printed = span.end;
+    }
+    draw_text_from_cow(ui, base_color, String::from_utf8_lossy(&bytes[printed..]));
+}
+
+/// Draw the line as a hex dump, on a single line such that every row keeps the same height.
+fn draw_line_hex(ui: &Ui, offset: u64, bytes: &[u8], matches: &[SearchMatch], style: MatchHighlightStyle) {
+    use std::fmt::Write;
+
+    ui.text(format!("{:08x}: ", offset));
+
+    // Draw contiguous runs of bytes that are in the same match, or not in a match, together.
+    let mut at = 0;
+    let mut matches = matches.iter().peekable();
+    while at < bytes.len() {
+        let (end, color) = match matches.peek() {
+            Some(matche) if matche.start <= at => {
+                let matche = matches.next().unwrap();
+                (matche.end, Some(MATCH_COLORS[matche.query % MATCH_COLORS.len()]))
+            },
+            Some(matche) => (matche.start, None),
+            None => (bytes.len(), None),
+        };
+
+        let mut text = String::with_capacity(3 * (end - at));
+        for value in bytes[at..end].iter() {
+            let _ = write!(text, "{:02x} ", value);
+        }
+
+        ui.same_line_with_spacing(0.0, 0.0);
+        match color {
+            Some(color) => draw_match_text(ui, std::borrow::Cow::Owned(text), color, None, style),
+            None => draw_text_from_cow(ui, None, std::borrow::Cow::Owned(text)),
+        }
+        at = end;
+    }
+
+    let ascii: String = bytes
+        .iter()
+        .map(|&value| if value.is_ascii_graphic() || value == b' ' { value as char } else { '.' })
+        .collect();
+    ui.same_line_with_spacing(0.0, 0.0);
+    ui.text(format!(" |{}|", ascii));
+}
+
+fn run_palette_action(ui: &Ui, action: PaletteAction, keep_running: &mut bool, state: &mut SearchTabs, settings: &mut SettingsWindow, windows: &mut ToolWindows) {
+    let ToolWindows { hotkeys, log, workspace, rg_import, statistics } = windows;
+    let tab = state.tabs.get_mut(state.selected_tab);
+    match action {
+        PaletteAction::NewTab => state.tabs.push(SearchTab::from_context(cwd(), &settings.settings)),
+        PaletteAction::OpenFolder => open_folder(state),
+        PaletteAction::PreviousSearch | PaletteAction::NextSearch => {
+            if let Some(tab) = tab {
+                navigate_history(tab, &settings.settings, action == PaletteAction::NextSearch);
+            }
+        },
+        PaletteAction::DuplicateTab => {
+            let new_tab = match tab {
+                Some(tab) => tab.clone_for_tab(),
+                None => SearchTab::from_context(cwd(), &settings.settings),
+            };
+            state.tabs.push(new_tab);
+        }
+        PaletteAction::CloseTab => {
+            if !state.tabs.is_empty() {
+                state.tabs.remove(state.selected_tab);
+                state.selected_tab %= std::cmp::max(state.tabs.len(), 1);
+            }
+        }
+        PaletteAction::Search => {
+            if let Some(tab) = tab {
+                search_parallel(tab, &settings.settings);
+            }
+        }
+        PaletteAction::CancelSearch => {
+            if let Some(tab) = tab {
+                tab.cancel_search(false);
+            }
+        }
+        PaletteAction::ClearResults => {
+            if let Some(tab) = tab {
+                tab.cancel_search(true);
+            }
+        }
+        PaletteAction::FindInResults => {
+            if let Some(tab) = tab {
+                tab.find.opened = true;
+                tab.find.focus = true;
+            }
+        }
+        PaletteAction::ExportResults => {
+            if let Some(tab) = tab {
+                ui.set_clipboard_text(tab.export_text());
+            }
+        }
+        PaletteAction::Statistics => {
+            if let Some(tab) = tab {
+                statistics.open(tab.statistics());
+            }
+        }
+        PaletteAction::TogglePerRootGlobs => {
+            if let Some(tab) = tab {
+                tab.config.per_root_globs = !tab.config.per_root_globs;
+                tab.init_roots();
+            }
+        }
+        PaletteAction::ToggleGroupByFile => settings.settings.group_by_file = !settings.settings.group_by_file,
+        PaletteAction::ToggleRelativePaths => settings.settings.relative_paths = !settings.settings.relative_paths,
+        PaletteAction::ToggleToolbar => settings.settings.hide_toolbar = !settings.settings.hide_toolbar,
+        PaletteAction::ToggleAlwaysOnTop => settings.settings.always_on_top = !settings.settings.always_on_top,
+        PaletteAction::OpenWorkspace => workspace.open(cwd(), None),
+        PaletteAction::ImportRipgrep => rg_import.open(),
+        PaletteAction::Settings => settings.open(true),
+        PaletteAction::Hotkeys => hotkeys.toggle_open(),
+        PaletteAction::Log => log.toggle_open(),
+        PaletteAction::Quit => request_quit(keep_running, state),
+    }
+}
+
+/// Draw the line with each match struck through, followed by the text replacing it.
+fn draw_replace_line(ui: &Ui, line: &LineReplacement, replacement: &str, keep_case: bool, style: MatchHighlightStyle) {
+    const REMOVED_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+    const ADDED_COLOR: [f32; 4] = [0.0, 0.8, 0.0, 1.0];
+
+    let bytes = trim_line_terminator(&line.bytes);
+    let mut printed = 0;
+    for &(start, end) in line.matches.iter() {
+        let (start, end) = (start.min(bytes.len()), end.min(bytes.len()));
+        draw_text_from_cow(ui, None, String::from_utf8_lossy(&bytes[printed..start]));
+        ui.same_line_with_spacing(0.0, 0.0);
+
+        let removed = String::from_utf8_lossy(&bytes[start..end]);
+        let min = ui.cursor_screen_pos();
+        let size = ui.calc_text_size(&removed);
+        draw_text_from_cow(ui, Some(REMOVED_COLOR), removed);
+        let y = min[1] + size[1] / 2.0;
+        ui.get_window_draw_list().add_line([min[0], y], [min[0] + size[0], y], REMOVED_COLOR).build();
+        ui.same_line_with_spacing(0.0, 0.0);
+
+        let added = replacement_text(&line.bytes[start..end], replacement, keep_case);
+        draw_match_text(ui, std::borrow::Cow::Owned(added), ADDED_COLOR, None, style);
+        ui.same_line_with_spacing(0.0, 0.0);
+        printed = end;
+    }
+    draw_text_from_cow(ui, None, String::from_utf8_lossy(&bytes[printed..]));
+}
+
+/// Quit, unless a replace is running: the files being written would be left half replaced,
+/// so a confirmation is asked first.
+fn request_quit(keep_running: &mut bool, state: &mut SearchTabs) {
+    if state.tabs.iter().any(|tab| tab.is_replacing()) {
+        state.confirm_quit = true;
+    } else {
+        *keep_running = false;
+    }
+}
+
+/// Ask whether to quit while a replace is running. Quits by itself once the replace is done.
+fn draw_quit_confirmation(ui: &Ui, keep_running: &mut bool, state: &mut SearchTabs) {
+    if !state.confirm_quit {
+        return;
+    }
+
+    let replacing = state.tabs.iter().filter(|tab| tab.is_replacing()).count();
+    if replacing == 0 {
+        state.confirm_quit = false;
+        *keep_running = false;
+        return;
+    }
+
+    ui.open_popup("Quit##confirm-quit");
+    let mut quit = false;
+    let mut cancel = false;
+    let mut opened = true;
+    ui.modal_popup_config("Quit##confirm-quit").opened(&mut opened).always_auto_resize(true).build(|| {
+        ui.text(format!("A replace is running in {} tab(s), quitting now would leave files partially replaced.", replacing));
+        ui.text("The application quits by itself once the replace is done.");
+        quit = ui.button("Quit anyway");
+        ui.same_line();
+        cancel = ui.button("Cancel");
+        if quit || cancel {
+            ui.close_current_popup();
+        }
+    });
+
+    if quit {
+        *keep_running = false;
+    }
+    if quit || cancel || !opened {
+        state.confirm_quit = false;
+    }
+}
+
+/// Draw the lines that would be replaced, each of them can be excluded. Returns true once
+/// the included lines must be replaced.
+fn draw_replace_preview(ui: &Ui, tab: &mut SearchTab, settings: &Settings) -> bool {
+    let preview = match tab.replace_preview.as_mut() {
+        Some(preview) => preview,
+        None => return false,
+    };
+
+    let mut apply = false;
+    let mut cancel = false;
+    let mut opened = true;
+    ui.modal_popup_config("Replace preview##replace-preview").opened(&mut opened).build(|| {
+        ui.text(format!("Replace with \"{}\", {} of {} line(s) selected", tab.replace, preview.included_count(), preview.rows.len()));
+        if ui.button("Select all") {
+            preview.included.iter_mut().for_each(|included| *included = true);
+        }
+        ui.same_line();
+        if ui.button("Select none") {
+            preview.included.iter_mut().for_each(|included| *included = false);
+        }
+
+        ui.child_window("##replace-preview-lines").size([900.0, 400.0]).border(true).build(|| {
+            let clip = ListClipper::new(preview.rows.len() as i32);
+            let mut tok = clip.begin(ui);
+            if let Some(_t) = ui.begin_table_with_flags("##replace-preview-table", 3, TableFlags::SIZING_FIXED_FIT) {
+                ui.table_setup_column("##included");
+                ui.table_setup_column("Location");
+                ui.table_setup_column_with(TableColumnSetup { name: "Change", flags: TableColumnFlags::WIDTH_STRETCH, init_width_or_weight: 0.0, user_id: Id::default() });
+                ui.table_headers_row();
+
+                while tok.step() {
+                    for row in tok.display_start()..tok.display_end() {
+                        let row = row as usize;
+                        let _stack = ui.push_id_usize(row);
+                        ui.table_next_column();
+                        ui.checkbox("##included", &mut preview.included[row]);
+
+                        let (file, line) = preview.line(row);
+                        ui.table_next_column();
+                        ui.text(format!("{}:{}", file.path.to_string_lossy(), line.line_number));
+
+                        ui.table_next_column();
+                        draw_replace_line(ui, line, &tab.replace, tab.preserve_case, settings.match_highlight);
+                    }
+                }
+            }
+        });
+
+        ui.disabled(preview.included_count() == 0, || {
+            apply = ui.button("Apply");
+        });
+        ui.same_line();
+        cancel = ui.button("Cancel");
+        if cancel || apply {
+            ui.close_current_popup();
+        }
+    });
+
+    if apply {
+        if let Some(preview) = tab.replace_preview.take() {
+            tab.start_replace(preview.into_included());
+        }
+    } else if cancel || !opened {
+        tab.replace_preview = None;
+    }
+
+    return apply;
+}
+
+/// Draw a thin line at the top of the current row, separating it from the previous group of lines.
+fn draw_context_separator(ui: &Ui) {
+    let [x, y] = ui.cursor_screen_pos();
+    let right = ui.window_pos()[0] + ui.window_content_region_max()[0];
+    let color = ui.style_color(imgui::StyleColor::Separator);
+    ui.get_window_draw_list().add_line([x, y], [right, y], color).build();
+}
+
+/// Draw a rotating arc, showing that a search is running. The number of files to search
+/// isn't known before the end of the walk, so the progress can't be shown.
+fn draw_spinner(ui: &Ui) {
+    const SEGMENTS: usize = 24;
+
+    let size = ui.text_line_height();
+    let radius = size * 0.4;
+    let [x, y] = ui.cursor_screen_pos();
+    let center = [x + size / 2.0, y + size / 2.0];
+
+    let start = ui.time() as f32 * 6.0;
+    let points: Vec<[f32; 2]> = (0..=SEGMENTS)
+        .map(|idx| {
+            let angle = start + (idx as f32 / SEGMENTS as f32) * std::f32::consts::PI * 1.5;
+            [center[0] + angle.cos() * radius, center[1] + angle.sin() * radius]
+        })
+        .collect();
+
+    let color = ui.style_color(imgui::StyleColor::Text);
+    ui.get_window_draw_list().add_polyline(points, color).thickness(2.0).build();
+    ui.dummy([size, size]);
+}
+
+/// Draw the box to find a term in the results.
+fn draw_find_in_results(ui: &Ui, tab: &mut SearchTab) {
+    if !tab.find.opened {
+        return;
+    }
+
+    tab.update_find();
+
+    if std::mem::take(&mut tab.find.focus) {
+        ui.set_keyboard_focus_here();
+    }
+    let entered = {
+        let _w = ui.push_item_width(300.0);
+        ui.input_text("##find-in-results", &mut tab.find.term)
+            .hint("Find in results")
+            .enter_returns_true(true)
+            .build()
+    };
+
+    if ui.is_item_edited() {
+        tab.reset_find();
+        tab.update_find();
+        tab.find.scroll_to = tab.find.rows.first().copied();
+    }
+
+    if entered {
+        tab.find_next(!ui.io().key_shift);
+        // Keep the focus in the input, such that enter can be pressed again.
+        ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+    }
+
+    ui.same_line();
+    if ui.arrow_button("##find-previous", Direction::Up) {
+        tab.find_next(false);
+    }
+    ui.same_line();
+    if ui.arrow_button("##find-next", Direction::Down) {
+        tab.find_next(true);
+    }
+    ui.same_line();
+    if tab.find.rows.is_empty() {
+        ui.text_disabled("No results");
+    } else {
+        ui.text(format!("{}/{}", tab.find.current + 1, tab.find.rows.len()));
+    }
+    ui.same_line();
+    if ui.small_button("x##close-find") {
+        tab.find.opened = false;
+    }
+    show_help(ui, help::FIND_IN_RESULTS_USAGE);
+}
+
+/// Draw a button per file extension found in the results, used to filter the results.
+fn draw_extension_chips(ui: &Ui, tab: &mut SearchTab) {
+    if tab.extensions.len() < 2 {
+        return;
+    }
+
+    let selected_color = ui.style_color(imgui::StyleColor::ButtonActive);
+    let spacing = unsafe { ui.style() }.item_spacing[0];
+    let window_right = ui.window_pos()[0] + ui.window_content_region_max()[0];
+
+    let mut clicked = None;
+    for (idx, (extension, count)) in tab.extensions.iter().enumerate() {
+        let label = format!("{} ({})", extension, count);
+        if idx != 0 {
+            // Only stay on the same line if the button fits.
+            let width = ui.calc_text_size(&label)[0] + 2.0 * unsafe { ui.style() }.frame_padding[0];
+            let last_right = ui.item_rect_max()[0];
+            if last_right + spacing + width < window_right {
+                ui.same_line();
+            }
+        }
+
+        let _color = tab
+            .selected_extensions
+            .contains(extension)
+            .then(|| ui.push_style_color(imgui::StyleColor::Button, selected_color));
+        if ui.small_button(&label) {
+            clicked = Some(extension.clone());
+        }
+    }
+
+    if let Some(extension) = clicked {
+        tab.toggle_extension(&extension);
+    }
+}
+
+/// Checkbox overriding a global setting, returns true if the setting is overridden, in
+/// which case the widget editing it is drawn by the caller.
+fn draw_override_checkbox<T: Copy>(ui: &Ui, label: &str, value: &mut Option<T>, global: T) -> bool {
+    let mut overridden = value.is_some();
+    if ui.checkbox(label, &mut overridden) {
+        *value = overridden.then_some(global);
+    }
+    return overridden;
+}
+
+/// Options of the search overriding the global settings for a single tab.
+fn draw_tab_overrides(ui: &Ui, overrides: &mut TabOverrides, settings: &Settings) {
+    ui.text("Override the settings for this tab:");
+    show_help(ui, help::TAB_OVERRIDES_USAGE);
+
+    if draw_override_checkbox(ui, "Binary files", &mut overrides.binary_mode, settings.binary_mode) {
+        if let Some(mode) = overrides.binary_mode.as_mut() {
+            let mut idx = BinaryMode::ALL.iter().position(|value| value == mode).unwrap_or(0);
+            ui.same_line();
+            let _w = ui.push_item_width(200.0);
+            if ui.combo_simple_string("##tab-binary", &mut idx, &BinaryMode::NAMES) {
+                *mode = BinaryMode::ALL[idx];
+            }
+        }
+    }
+
+    if draw_override_checkbox(ui, "Number of threads", &mut overrides.number_of_threads, settings.number_of_threads) {
+        if let Some(threads) = overrides.number_of_threads.as_mut() {
+            ui.same_line();
+            let _w = ui.push_item_width(100.0);
+            if ui.input_int("##tab-threads", threads).build() {
+                *threads = (*threads).clamp(0, search::max_thread_count());
+            }
+        }
+    }
+
+    if cfg!(feature = "zip") && draw_override_checkbox(ui, "Search in archives", &mut overrides.search_archives, settings.search_archives) {
+        if let Some(archives) = overrides.search_archives.as_mut() {
+            ui.same_line();
+            ui.checkbox("##tab-archives", archives);
+        }
+    }
+
+    if ui.button("Inherit all") {
+        *overrides = TabOverrides::default();
+    }
+}
+
+/// Input for a number of context lines, which can't be negative.
+fn draw_context_input(ui: &Ui, label: &str, lines: &mut usize) {
+    let mut value = *lines as i32;
+    let _w = ui.push_item_width(70.0);
+    if ui.input_int(label, &mut value).build() {
+        *lines = value.max(0) as usize;
+    }
+}
+
+/// Input for a bound of a line range, empty when unbounded.
+fn draw_line_bound(ui: &Ui, label: &str, hint: &str, bound: &mut Option<u64>) {
+    let mut text = bound.map(|value| value.to_string()).unwrap_or_default();
+    let _w = ui.push_item_width(70.0);
+    if ui.input_text(label, &mut text).chars_decimal(true).hint(hint).build() {
+        *bound = text.trim().parse().ok();
+    }
+}
+
+/// Draw the list of paths with their own patterns, returns true if enter was pressed in one of them.
+fn draw_search_roots(ui: &Ui, config: &mut SearchConfig) -> bool {
+    let mut search = false;
+    let mut removed = None;
+    for (idx, root) in config.roots.iter_mut().enumerate() {
+        let _stack = ui.push_id_usize(idx);
+        if idx != 0 {
+            ui.table_next_column();
+            ui.table_next_column();
+        }
+
+        let _w = ui.push_item_width(300.0);
+        search |= ui.input_text("##root-path", &mut root.path).hint("path").enter_returns_true(true).build();
+        ui.same_line();
+        search |= ui.input_text("##root-globs", &mut root.globs).hint("*.txt *.cpp").enter_returns_true(true).build();
+        ui.same_line();
+        if ui.button("-") {
+            removed = Some(idx);
+        }
+    }
+
+    if let Some(idx) = removed {
+        config.roots.remove(idx);
+    }
+
+    if !config.roots.is_empty() {
+        ui.table_next_column();
+        ui.table_next_column();
+    }
+    if ui.button("+##root") {
+        config.roots.push(SearchRoot { path: String::new(), globs: String::new() });
+    }
+    ui.same_line();
+    ui.checkbox("Any depth", &mut config.globs_any_depth);
+    show_help(ui, help::GLOBS_ANY_DEPTH_USAGE);
+    ui.same_line();
+    ui.checkbox("Ignore case##globs", &mut config.globs_ignore_case);
+    show_help(ui, help::GLOBS_IGNORE_CASE_USAGE);
+    show_help(ui, help::GLOBS_USAGE);
+
+    return search;
+}
+
+/// Receive the results of the search and the replace of a tab. Done every frame for every
+/// tab, such that the label of the tabs in the background show their progress.
+fn update_tab(tab: &mut SearchTab, settings: &Settings) {
+    if tab.fold_repeats != settings.fold_repeated_context {
+        tab.fold_repeats = settings.fold_repeated_context;
+        tab.update_filter();
+    }
+    if tab.sort_modified.is_some() && !settings.show_modified_time {
+        tab.sort_by_modified(None);
+    }
+    if tab.update_pending_search() && !tab.reselect() && settings.scroll_to_first_match {
+        tab.scroll_to_first_match = !tab.results.is_empty();
+    }
+    if settings.max_results_memory > 0 {
+        tab.trim_results(settings.max_results_memory as usize * 1024 * 1024);
+    }
+    if tab.update_pending_replace() {
+        // The results are outdated now that the files changed, so search again.
+        let replace_errors = tab.replace_errors;
+        search_parallel(tab, settings);
+        if replace_errors != 0 {
+            tab.error_message = Some(format!("Failed to replace in {} file(s), see the log", replace_errors));
+        }
+    }
+}
+
+/// Label of the tab, followed by a spinner and the number of results while it's searching.
+fn tab_label(ui: &Ui, tab: &SearchTab, tab_id: usize) -> String {
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+    let name = if tab.name.is_empty() { tab.config.paths_label() } else { tab.name.clone() };
+    if !tab.is_searching() {
+        return format!("{}###{}", name, tab_id);
+    }
+
+    let frame = (ui.time() * 8.0) as usize % SPINNER.len();
+    return format!("{} {} ({})###{}", name, SPINNER[frame], tab.results.len(), tab_id);
+}
+
+fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab, settings: &Settings, fonts: &support::Fonts) {
+    let mut flags = TabItemFlags::empty();
+    if state.set_selected_tab == Some(tab_id) {
+        flags |= TabItemFlags::SET_SELECTED;
+        state.set_selected_tab = None;
+    }
+
+    flags |= TabItemFlags::TRAILING;
+
+    let label = tab_label(ui, &tab, tab_id);
+    let mut keep_open = true;
+    TabItem::new(label).opened(&mut keep_open).flags(flags).build(ui, || {
+        // If we enter this block, we are in the selected tab.
+        state.selected_tab = tab_id;
+
+        // The tab header is still the last item, so we can check if it was double clicked.
+        if ui.is_item_hovered() && ui.is_mouse_double_clicked(MouseButton::Left) {
+            ui.open_popup("##rename-tab");
+        }
+
+        if let Some(_p) = ui.begin_popup("##rename-tab") {
+            ui.text("Tab name:");
+            if ui.is_window_appearing() {
+                ui.set_keyboard_focus_here();
+            }
+            if ui
+                .input_text("##tab-name", &mut tab.name)
+                .hint(tab.config.paths_label())
+                .enter_returns_true(true)
+                .build()
+            {
+                ui.close_current_popup();
+            }
+        }
+
+        let mut search = false;
+        // Search triggered by pressing enter in a query, only done if the queries are long enough.
+        let mut enter_search = false;
+        // The preview is opened outside of the table, such that the popup has the same id when drawn.
+        let mut preview_replace = false;
+        if let Some(_t) = ui.begin_table_with_flags("Basic-Table", 2, TableFlags::SIZING_FIXED_FIT) {
+            // ui.text("Search:");
+
+            ui.table_setup_column_with(TableColumnSetup { name: "##labels", flags: TableColumnFlags::WIDTH_FIXED, init_width_or_weight: 0.0, user_id: Id::default() });
+            ui.table_setup_column_with(TableColumnSetup { name: "##widgets", flags: TableColumnFlags::WIDTH_STRETCH, init_width_or_weight: 0.0, user_id: Id::default() });
+            ui.table_next_row();
+
+            ui.table_next_column();
+            ui.text("Paths:");
+            ui.table_next_column();
+            if tab.config.per_root_globs {
+                search |= draw_search_roots(ui, &mut tab.config);
+            } else if ui
+                .input_text("##paths", &mut tab.config.paths)
+                .enter_returns_true(true)
+                .build()
+            {
+                // Keep the focus in the search input making it easier to iterate.
+                ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+                search = true;
+            }
+            ui.same_line();
+            if ui.checkbox("Per path patterns", &mut tab.config.per_root_globs) {
+                tab.init_roots();
+            }
+            show_help(ui, help::PATHS_USAGE);
+
+            if !tab.config.per_root_globs {
+                ui.table_next_column();
+                ui.text("Patterns:");
+                ui.table_next_column();
+                if ui
+                    .input_text("##globs", &mut tab.config.globs)
+                    .enter_returns_true(true)
+                    .hint("*.txt *.cpp")
+                    .build()
+                {
+                    search = true;
+                    // Keep the focus in the search input making it easier to iterate.
+                    ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+                }
+                ui.same_line();
+                ui.checkbox("Any depth", &mut tab.config.globs_any_depth);
+                show_help(ui, help::GLOBS_ANY_DEPTH_USAGE);
+                ui.same_line();
+                ui.checkbox("Ignore case##globs", &mut tab.config.globs_ignore_case);
+                show_help(ui, help::GLOBS_IGNORE_CASE_USAGE);
+                show_help(ui, help::GLOBS_USAGE);
+            }
+
+            ui.table_next_column();
+            ui.text("Exclude dirs:");
+            ui.table_next_column();
+            if ui
+                .input_text("##exclude-dirs", &mut tab.config.exclude_dirs)
+                .enter_returns_true(true)
+                .hint("node_modules, target, .git")
+                .build()
+            {
+                search = true;
+                // Keep the focus in the search input making it easier to iterate.
+                ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+            }
+            ui.same_line();
+            ui.checkbox("Hidden files", &mut tab.config.hidden);
+            show_help(ui, help::EXCLUDE_DIRS_USAGE);
+            ui.same_line();
+            ui.checkbox("Only modified (git)", &mut tab.config.only_modified);
+            show_help(ui, help::ONLY_MODIFIED_USAGE);
+            ui.same_line();
+            ui.checkbox("Only tracked (git)", &mut tab.config.only_tracked);
+            show_help(ui, help::ONLY_TRACKED_USAGE);
+
+            let mut rehighlight = false;
+            let queries = std::mem::replace(&mut tab.config.queries, vec![]);
+            for (idx, mut query) in queries.into_iter().enumerate() {
+                // Dropping this value pop the id from IMGUI stack.
+                let _stack = ui.push_id_usize(idx);
+
+                ui.table_next_column();
+
+                // How can we calculate that dynamically such that the button fits in the window?
+                ui.table_next_column();
+                let _w = ui.push_item_width(450.0);
+                if idx == 0 && std::mem::take(&mut state.focus_query) {
+                    ui.set_keyboard_focus_here();
+                }
+                let error_style = query.syntax_error.as_ref().map(|_| (
+                    ui.push_style_color(imgui::StyleColor::Border, [1.0, 0.0, 0.0, 1.0]),
+                    ui.push_style_var(StyleVar::FrameBorderSize(1.0)),
+                ));
+                if ui
+                    .input_text("##search", &mut query.query)
+                    .hint("(press enter to search)")
+                    .enter_returns_true(true)
+                    .build()
+                {
+                    enter_search = true;
+
+                    // Keep the focus in the search input making it easier to iterate.
+                    ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+                }
+                let mut edited = ui.is_item_edited();
+                drop(error_style);
+
+                if let Some(error) = &query.syntax_error {
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(error);
+                    }
+                }
+
+                ui.same_line();
+                edited |= ui.checkbox("Regex syntax", &mut query.regex_syntax);
+                ui.same_line();
+                ui.disabled(!query.regex_syntax, || {
+                    edited |= ui.checkbox("PCRE", &mut query.pcre);
+                });
+                if ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+                    ui.tooltip_text(help::PCRE_USAGE);
+                }
                 ui.same_line();
-                ui.checkbox("Regex syntax", &mut query.regex_syntax);
+                ui.disabled(query.regex_syntax, || {
+                    ui.checkbox("Any word", &mut query.any_literal);
+                });
+                if ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+                    ui.tooltip_text(help::ANY_WORD_USAGE);
+                }
+                if edited {
+                    query.syntax_error = query.check_syntax();
+                    // Refining a regex highlights it in the current results, the files
+                    // are only searched again when pressing enter.
+                    rehighlight |= query.regex_syntax && query.syntax_error.is_none();
+                }
                 ui.same_line();
                 ui.checkbox("Ignore case", &mut query.ignore_case);
                 ui.same_line();
                 ui.checkbox("Invert match", &mut query.invert_match);
                 ui.same_line();
+                draw_context_input(ui, "Before##before-context", &mut query.before_context);
+                ui.same_line();
+                draw_context_input(ui, "After##after-context", &mut query.after_context);
+                show_help(ui, help::CONTEXT_USAGE);
+                ui.same_line();
+                draw_line_bound(ui, "##min-line", "min line", &mut query.line_range.min);
+                ui.same_line();
+                draw_line_bound(ui, "##max-line", "max line", &mut query.line_range.max);
+                show_help(ui, help::LINE_RANGE_USAGE);
+                ui.same_line();
 
                 let add = ui.button("+");
                 ui.same_line();
@@ -355,20 +2351,89 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
                 }
 
                 if add {
-                    tab.config.queries.push(SearchQuery::new());
+                    tab.config.queries.push(settings.default_query());
                 }
             }
+
+            if rehighlight && !tab.is_searching() && !tab.is_replacing() {
+                tab.rehighlight();
+            }
+
+            ui.table_next_column();
+            ui.text("Search in:");
+            ui.table_next_column();
+            ui.radio_button("Contents", &mut tab.config.mode, SearchMode::Contents);
+            ui.same_line();
+            ui.radio_button("File names", &mut tab.config.mode, SearchMode::FileNames);
+            ui.same_line();
+            ui.radio_button("Both", &mut tab.config.mode, SearchMode::Both);
+            show_help(ui, help::SEARCH_MODE_USAGE);
+            ui.same_line();
+            ui.disabled(tab.config.mode != SearchMode::FileNames, || {
+                ui.checkbox("Fuzzy", &mut tab.config.fuzzy);
+            });
+            if ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+                ui.tooltip_text(help::FUZZY_USAGE);
+            }
+            ui.same_line();
+            ui.disabled(!tab.config.queries.iter().any(|query| query.regex_syntax), || {
+                ui.checkbox("Capture groups", &mut tab.config.captures);
+            });
+            if ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+                ui.tooltip_text(help::CAPTURES_USAGE);
+            }
+            ui.same_line();
+            ui.disabled(tab.config.mode == SearchMode::FileNames, || {
+                ui.checkbox("Paragraphs", &mut tab.config.paragraphs);
+            });
+            if ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+                ui.tooltip_text(help::PARAGRAPHS_USAGE);
+            }
+
+            if tab.config.queries.len() > 1 {
+                ui.table_next_column();
+                ui.text("Combine:");
+                ui.table_next_column();
+                ui.radio_button("Match all queries", &mut tab.config.combination, QueryCombination::All);
+                ui.same_line();
+                ui.radio_button("Match any query", &mut tab.config.combination, QueryCombination::Any);
+                show_help(ui, help::COMBINE_USAGE);
+            }
+
+            ui.table_next_column();
+            ui.text("Replace:");
+            ui.table_next_column();
+            {
+                let _w = ui.push_item_width(450.0);
+                ui.input_text("##replace", &mut tab.replace).hint("(replacement text)").build();
+            }
+            ui.same_line();
+            ui.checkbox("Preserve case", &mut tab.preserve_case);
+            ui.same_line();
+            ui.disabled(tab.is_searching() || tab.is_replacing() || tab.results.is_empty() || tab.stdin.is_some(), || {
+                preview_replace = ui.button("Replace all...");
+            });
+            show_help(ui, help::REPLACE_USAGE);
         }
 
         // We always have at least 1 query line, so if they were all removed, re-create a default one.
         if tab.config.queries.is_empty() {
-            tab.config.queries.push(SearchQuery::new());
+            tab.config.queries.push(settings.default_query());
         }
 
         if ui.button("Search") {
             search = true;
         }
 
+        if enter_search && !search {
+            let min_len = settings.min_query_len.max(0) as usize;
+            if tab.config.queries_have_min_len(min_len) {
+                search = true;
+            } else {
+                tab.error_message = Some(format!("Type at least {} characters to search, or press Search", min_len));
+            }
+        }
+
         ui.same_line();
         let color = ui.push_style_color(StyleColor::Button, [1.0, 0.0, 0.0, 1.0]);
         if ui.button("Cancel") {
@@ -376,6 +2441,29 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
         }
         color.end();
 
+        if tab.is_searching() {
+            ui.same_line();
+            let label = if tab.is_paused() { "Resume" } else { "Pause" };
+            if ui.button(label) {
+                tab.toggle_pause();
+            }
+        }
+
+        ui.same_line();
+        let label = if tab.overrides.is_empty() { "Options###tab-options" } else { "Options*###tab-options" };
+        if ui.button(label) {
+            ui.open_popup("##tab-overrides");
+        }
+        if let Some(_p) = ui.begin_popup("##tab-overrides") {
+            draw_tab_overrides(ui, &mut tab.overrides, settings);
+        }
+
+        if preview_replace {
+            tab.replace_preview = Some(ReplacePreview::new(tab.file_replacements()));
+            ui.open_popup("Replace preview##replace-preview");
+        }
+        draw_replace_preview(ui, &mut tab, settings);
+
         if let Some(error_message) = &tab.error_message {
             ui.same_line();
 
@@ -402,26 +2490,148 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
         let footer_height = height_seperator + ui.frame_height();
 
         ui.separator();
-        ui.child_window("##result").size([0.0, -footer_height]).build(|| {
-            let clip = ListClipper::new(tab.results.len() as i32);
+        draw_extension_chips(ui, &mut tab);
+        draw_find_in_results(ui, &mut tab);
+
+        let results_height = ui.content_region_avail()[1] - footer_height;
+        if settings.show_minimap {
+            tab.update_minimap(bucket_count(results_height));
+        } else {
+            tab.clear_minimap();
+        }
+        let minimap_width = if tab.minimap.is_some() { MINIMAP_WIDTH + unsafe { ui.style() }.item_spacing[0] } else { 0.0 };
+        let mut visible_lines = None;
+        ui.child_window("##result").size([-minimap_width, -footer_height]).build(|| {
+            if tab.scroll_to_first_match {
+                ui.set_scroll_y(0.0);
+            }
+
+            if tab.minimap.is_some() && tab.visible_rows() != 0 {
+                // Estimated from the scroll like the row of the results found, the header row takes the first row.
+                let row_height = ui.text_line_height() + 2.0 * unsafe { ui.style() }.cell_padding[1];
+                let first = ((ui.scroll_y() / row_height) as usize).min(tab.visible_rows() - 1);
+                let last = (first + (ui.window_size()[1] / row_height) as usize).saturating_sub(1).clamp(first, tab.visible_rows() - 1);
+                visible_lines = Some((tab.results[tab.visible_row(first)].line_number, tab.results[tab.visible_row(last)].line_number));
+            }
+
+            // The row is only drawn by the clipper once it's close to the visible area, so first
+            // scroll to its estimated position, and center it once it's drawn.
+            let find_row = tab.find.scroll_to.and_then(|idx| tab.visible_position(idx));
+            if let Some(row) = find_row {
+                let row_height = ui.text_line_height() + 2.0 * unsafe { ui.style() }.cell_padding[1];
+                ui.set_scroll_y(row as f32 * row_height - ui.window_size()[1] / 2.0);
+            } else {
+                tab.find.scroll_to = None;
+            }
+
+            let results_font = match settings.results_font {
+                ResultsFont::Monospace => fonts.monospace,
+                ResultsFont::Proportional => fonts.proportional,
+            };
+            let _font = ui.push_font(results_font);
+
+            let clip = ListClipper::new(tab.visible_rows() as i32);
             let mut tok = clip.begin(ui);
 
-            let flags = TableFlags::REORDERABLE | TableFlags::RESIZABLE | TableFlags::SIZING_FIXED_FIT;
-            if let Some(_t) = ui.begin_table_with_flags("table-headers", 3, flags) {
+            // The root column is only useful when there is more than one root to distinguish.
+            let show_root = tab.searched_roots > 1;
+            let show_modified = settings.show_modified_time;
+            let columns = if show_root { 4 } else { 3 } + usize::from(show_modified) + tab.capture_columns;
+
+            // The layout of the table is saved by imgui, use a different id for each set of columns
+            // to not restore the widths of the wrong columns.
+            let mut table_id = String::from(if show_root { "##results-with-root" } else { "##results" });
+            if show_modified {
+                table_id.push_str("-modified");
+            }
+            if tab.capture_columns != 0 {
+                table_id.push_str(&format!("-captures-{}", tab.capture_columns));
+            }
+            let mut flags = TableFlags::REORDERABLE | TableFlags::RESIZABLE | TableFlags::SIZING_FIXED_FIT;
+            if settings.highlight_match_rows {
+                flags |= TableFlags::ROW_BG;
+            }
+            if show_modified {
+                // Only the modification time is sortable, the tristate allows going back to the received order.
+                flags |= TableFlags::SORTABLE | TableFlags::SORT_TRISTATE;
+            }
+            if let Some(_t) = ui.begin_table_with_flags(table_id, columns, flags) {
                 let avail_width = ui.content_region_avail()[0];
-                ui.table_setup_column_with(TableColumnSetup { name: "File", flags: TableColumnFlags::WIDTH_FIXED, init_width_or_weight: 0.5 * avail_width, user_id: Id::default() });
-                ui.table_setup_column_with(TableColumnSetup { name: "Line", flags: TableColumnFlags::WIDTH_FIXED, init_width_or_weight: 0.1 * avail_width, user_id: Id::default() });
-                ui.table_setup_column_with(TableColumnSetup { name: "Text", flags: TableColumnFlags::WIDTH_STRETCH, init_width_or_weight: 0.0, user_id: Id::default() });
+                let fixed = TableColumnFlags::WIDTH_FIXED | TableColumnFlags::NO_SORT;
+                ui.table_setup_column_with(TableColumnSetup { name: "File", flags: fixed, init_width_or_weight: 0.5 * avail_width, user_id: Id::default() });
+                if show_root {
+                    ui.table_setup_column_with(TableColumnSetup { name: "Root", flags: fixed, init_width_or_weight: 0.1 * avail_width, user_id: Id::default() });
+                }
+                ui.table_setup_column_with(TableColumnSetup { name: "Line", flags: fixed, init_width_or_weight: 0.1 * avail_width, user_id: Id::default() });
+                let modified_column = if show_root { 3 } else { 2 };
+                if show_modified {
+                    let flags = TableColumnFlags::WIDTH_FIXED | TableColumnFlags::PREFER_SORT_DESCENDING;
+                    ui.table_setup_column_with(TableColumnSetup { name: "Modified", flags, init_width_or_weight: 0.12 * avail_width, user_id: Id::default() });
+                }
+                for group in 1..=tab.capture_columns {
+                    let name = format!("Group {}", group);
+                    ui.table_setup_column_with(TableColumnSetup { name: name.as_str(), flags: fixed, init_width_or_weight: 0.1 * avail_width, user_id: Id::default() });
+                }
+                let stretch = TableColumnFlags::WIDTH_STRETCH | TableColumnFlags::NO_SORT;
+                ui.table_setup_column_with(TableColumnSetup { name: "Text", flags: stretch, init_width_or_weight: 0.0, user_id: Id::default() });
                 ui.table_headers_row();
 
+                if let Some(mut sort_specs) = ui.table_sort_specs_mut() {
+                    if sort_specs.should_sort() {
+                        let direction = sort_specs
+                            .specs()
+                            .iter()
+                            .find(|spec| spec.column_idx() == modified_column)
+                            .and_then(|spec| spec.sort_direction());
+                        tab.sort_by_modified(direction);
+                        sort_specs.set_sorted();
+                    }
+                }
+
+                let mut hovered_row = None;
+                // Applied once the rows are drawn, as it changes the visible rows.
+                let mut dismissed_row = None;
+                let mut clear_dismissed = false;
                 while tok.step() {
                     for row_num in tok.display_start()..tok.display_end() {
-                        let row_id = row_num as usize;
+                        let row_id = tab.visible_row(row_num as usize);
                         let _stack = ui.push_id_usize(row_id);
 
                         ui.table_next_column();
+                        if settings.highlight_match_rows {
+                            // Replace the alternating colors of `ROW_BG`, only the matched lines are tinted.
+                            let color = if tab.results[row_id].kind == EntryKind::Match { [0.26, 0.59, 0.98, 0.15] } else { [0.0, 0.0, 0.0, 0.0] };
+                            ui.table_set_bg_color(TableBgTarget::ROW_BG0, color);
+                        }
+                        if tab.find.opened && tab.find.rows.binary_search(&row_id).is_ok() {
+                            let current = tab.find.rows.get(tab.find.current) == Some(&row_id);
+                            let alpha = if current { 0.4 } else { 0.15 };
+                            ui.table_set_bg_color(TableBgTarget::ROW_BG1, [1.0, 0.875, 0.0, alpha]);
+                        }
+
+                        if tab.find.scroll_to == Some(row_id) {
+                            ui.set_scroll_here_y_with_ratio(0.5);
+                            tab.find.scroll_to = None;
+                        }
+
+                        if tab.results[row_id].context_break {
+                            draw_context_separator(ui);
+                        }
+
+                        // The first row is only drawn once scrolled to the top, so it may take a frame.
+                        if row_num == 0 && std::mem::take(&mut tab.scroll_to_first_match) {
+                            ui.set_keyboard_focus_here();
+                        }
+
+                        if tab.focus_row == Some(row_id) {
+                            tab.focus_row = None;
+                            ui.set_keyboard_focus_here();
+                        }
+
+                        // The path is drawn after the selectable, such that the matches in the file name can be highlighted.
+                        let path_pos = ui.cursor_pos();
                         if ui
-                            .selectable_config(tab.results[row_id].path.as_ref())
+                            .selectable_config("##file")
                             .span_all_columns(true)
                             .selected(tab.results[row_id].selected)
                             .build()
@@ -438,21 +2648,229 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
                             tab.last_focused_row = Some(row_id);
                         }
 
+                        if ui.is_item_hovered() && ui.is_mouse_double_clicked(MouseButton::Left) && tab.results[row_id].has_file() {
+                            double_click_result(ui, &mut tab, row_id, settings);
+                        }
+
+                        if ui.is_item_hovered() {
+                            hovered_row = Some(row_id);
+                            if let Some(text) = tab.preview.text(row_id, &tab.results[row_id]) {
+                                ui.tooltip_text(text);
+                            }
+                        }
+
+                        if ui.is_item_clicked_with_button(MouseButton::Right) {
+                            ui.open_popup("##row-context");
+                        }
+
+                        if let Some(_p) = ui.begin_popup("##row-context") {
+                            let has_file = tab.results[row_id].has_file();
+                            if ui.menu_item_config("Open with default app").enabled(has_file).build() {
+                                let opened = archive::local_path(tab.results[row_id].path.as_str())
+                                    .and_then(|path| sys::open_file(&path));
+                                if let Err(err) = opened {
+                                    let error = err.to_string();
+                                    log!("{}", error);
+                                    tab.error_message = Some(error);
+                                }
+                            }
+
+                            if ui.menu_item_config("Open terminal in file directory").enabled(has_file).build() {
+                                let opened = archive::local_path(tab.results[row_id].path.as_str())
+                                    .and_then(|path| open_terminal(&settings.terminal, &path.to_string_lossy()));
+                                if let Err(err) = opened {
+                                    let error = err.to_string();
+                                    log!("{}", error);
+                                    tab.error_message = Some(error);
+                                }
+                            }
+
+                            let has_hex_editor = !settings.hex_editor.is_empty();
+                            if ui.menu_item_config("Open in hex editor").enabled(has_hex_editor && has_file).build() {
+                                let result = &tab.results[row_id];
+                                let offset = match result.kind {
+                                    EntryKind::FileName => 0,
+                                    _ => result.offset + result.matches.first().map_or(0, |matche| matche.start as u64),
+                                };
+                                let opened = archive::local_path(result.path.as_str())
+                                    .and_then(|path| open_hex_editor(&settings.hex_editor, &path.to_string_lossy(), offset));
+                                if let Err(err) = opened {
+                                    let error = err.to_string();
+                                    log!("{}", error);
+                                    tab.error_message = Some(error);
+                                }
+                            }
+
+                            if ui.menu_item("Dismiss") {
+                                dismissed_row = Some(row_id);
+                            }
+
+                            if ui.menu_item_config("Clear dismissed").enabled(tab.dismissed > 0).build() {
+                                clear_dismissed = true;
+                            }
+
+                            ui.separator();
+                            if ui.menu_item("Copy file list") {
+                                ui.set_clipboard_text(tab.file_list());
+                            }
+
+                            if ui.menu_item("Copy line numbers of this file") {
+                                let path = Rc::clone(&tab.results[row_id].path);
+                                ui.set_clipboard_text(tab.line_numbers(&path));
+                            }
+
+                            if ui.menu_item("Copy match with context") {
+                                ui.set_clipboard_text(tab.excerpt(row_id));
+                            }
+
+                            if ui.menu_item("Copy snippet file path") {
+                                let snippet = tab.snippet(row_id);
+                                match state.snippets.create(&tab.results[row_id].path, &snippet) {
+                                    Ok(path) => ui.set_clipboard_text(path.to_string_lossy()),
+                                    Err(err) => {
+                                        let error = format!("Failed to create the snippet file, error: {}", err);
+                                        log!("{}", error);
+                                        tab.error_message = Some(error);
+                                    }
+                                }
+                            }
+
+                            if ui.menu_item("Copy location URL") {
+                                let result = &tab.results[row_id];
+                                let line_number = std::cmp::max(result.line_number, 1) as usize;
+                                match build_location_url(&settings.location_url, &result.path, line_number) {
+                                    Ok(url) => ui.set_clipboard_text(url),
+                                    Err(err) => {
+                                        let error = format!("Failed to build the location URL of '{}', error: {}", result.path, err);
+                                        log!("{}", error);
+                                        tab.error_message = Some(error);
+                                    }
+                                }
+                            }
+
+                            if ui.menu_item("Copy results as Markdown table") {
+                                ui.set_clipboard_text(export::markdown_table(tab.export_rows(settings.relative_paths)));
+                            }
+
+                            if ui.menu_item("Copy results as Markdown code blocks") {
+                                ui.set_clipboard_text(export::markdown_code_blocks(tab.export_rows(settings.relative_paths)));
+                            }
+
+                            if ui.menu_item_config("Copy capture groups as CSV").enabled(tab.capture_columns != 0).build() {
+                                ui.set_clipboard_text(export::captures_csv(tab.export_rows(settings.relative_paths), tab.capture_columns));
+                            }
+
+                            let copy_matches = ui.menu_item("Copy matched text");
+                            let copy_unique_matches = ui.menu_item("Copy unique matched text");
+                            if copy_matches || copy_unique_matches {
+                                let (text, truncated) = tab.matched_text(copy_unique_matches);
+                                if truncated {
+                                    let message = format!("Only copied the first {} bytes of matched text", text.len());
+                                    log!("{}", message);
+                                    tab.error_message = Some(message);
+                                }
+                                ui.set_clipboard_text(text);
+                            }
+
+                            let result = &tab.results[row_id];
+                            let mut terms: Vec<String> = Vec::new();
+                            for matche in result.matches.iter() {
+                                let term = String::from_utf8_lossy(&result.bytes[matche.start..matche.end]).into_owned();
+                                if !terms.contains(&term) {
+                                    terms.push(term);
+                                }
+                            }
+
+                            if !terms.is_empty() {
+                                ui.separator();
+                            }
+
+                            for term in terms.into_iter() {
+                                if ui.menu_item(format!("Search \"{}\" in new tab", term)) {
+                                    let mut new_tab = SearchTab::from_context(tab.config.paths.clone(), settings);
+                                    new_tab.config.queries[0].query = term;
+                                    // The term is the text that matched, so it must be searched literally.
+                                    new_tab.config.queries[0].regex_syntax = false;
+                                    new_tab.config.queries[0].invert_match = false;
+                                    state.new_tabs.push(new_tab);
+                                }
+                            }
+                        }
+
+                        // When grouped, the path is only drawn on the first line of each file.
+                        let same_file = settings.group_by_file
+                            && row_num > 0
+                            && Rc::ptr_eq(&tab.results[tab.visible_row(row_num as usize - 1)].path, &tab.results[row_id].path);
+
+                        if !same_file {
+                            ui.set_cursor_pos(path_pos);
+                            draw_path(ui, &tab.results[row_id], settings.relative_paths, settings.match_highlight);
+                        }
+
+                        if show_root {
+                            ui.table_next_column();
+                            if !same_file {
+                                ui.text(tab.results[row_id].root.as_ref());
+                            }
+                        }
+
                         ui.table_next_column();
-                        ui.text(format!("{}", tab.results[row_id].line_number));
+                        if tab.results[row_id].kind != EntryKind::FileName {
+                            ui.text(format!("{}", tab.results[row_id].line_number));
+                        }
+
+                        if show_modified {
+                            ui.table_next_column();
+                            if !same_file {
+                                ui.text(tab.results[row_id].modified_text());
+                            }
+                        }
+
+                        for group in 0..tab.capture_columns {
+                            ui.table_next_column();
+                            ui.text(tab.results[row_id].capture_text(group));
+                        }
 
                         ui.table_next_column();
-                        draw_result(ui, &tab.results[row_id]);
+                        draw_result(ui, &tab.results[row_id], settings);
+                        if tab.fold_repeats && tab.results[row_id].kind == EntryKind::Context {
+                            let count = tab.repeat_count(row_id);
+                            if count > 1 {
+                                ui.same_line();
+                                ui.text_disabled(format!("(\u{d7}{})", count));
+                            }
+                        }
                     }
                 }
+                tab.preview.hover(hovered_row);
+                if let Some(row) = dismissed_row {
+                    tab.dismiss(row);
+                }
+                if clear_dismissed {
+                    tab.clear_dismissed();
+                }
             }
         });
 
+        if let Some(minimap) = tab.minimap.as_ref() {
+            ui.same_line();
+            if let Some(line) = draw_minimap(ui, minimap, visible_lines, [MINIMAP_WIDTH, results_height]) {
+                tab.scroll_to_line(line);
+            }
+        }
+
         ui.separator();
         let duration = tab.search_duration();
+        let filtered_text = match (tab.filtered_rows.is_some(), tab.dismissed) {
+            (false, _) => String::new(),
+            (true, 0) => format!(" ({} shown)", tab.visible_rows()),
+            (true, dismissed) => format!(" ({} shown, {} dismissed)", tab.visible_rows(), dismissed),
+        };
+
         let footer_text = format!(
-            "{} result(s) in {} file(s) ({} file(s) searched)      Duration: {}.{} secs",
+            "{} result(s){} in {} file(s) ({} file(s) searched)      Duration: {}.{} secs",
             tab.results.len(),
+            filtered_text,
             tab.file_searched_with_results,
             tab.file_searched,
             duration.as_secs(),
@@ -461,16 +2879,86 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
 
         ui.text(footer_text);
 
+        ui.same_line();
+        ui.text("      Go to result:");
+        ui.same_line();
+        ui.disabled(tab.visible_rows() == 0, || {
+            let _w = ui.push_item_width(80.0);
+            if ui.input_int("##go-to-result", &mut tab.go_to_result).step(0).enter_returns_true(true).build() {
+                tab.go_to_result = tab.go_to_result.clamp(1, tab.visible_rows() as i32);
+                tab.go_to_result(tab.go_to_result);
+            }
+        });
+
+        if settings.show_memory {
+            ui.same_line();
+            ui.text(format!("      Memory: {:.1} MB", tab.results_memory as f64 / (1024.0 * 1024.0)));
+        }
+
+        if tab.trimmed_results > 0 {
+            ui.same_line();
+            ui.text_colored([1.0, 0.875, 0.0, 1.0], format!("{} result(s) dropped to limit the memory", tab.trimmed_results));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(help::SETTINGS_MAX_RESULTS_MEMORY_HELP);
+            }
+        }
+
+        if let Some(location) = tab.selected_location() {
+            ui.same_line();
+            ui.text(format!("      {}", location));
+        }
+
+        if settings.show_timing {
+            let timing = tab.timing;
+            ui.same_line();
+            ui.text(format!(
+                "      Walk: {:.3} secs, Search: {:.3} secs, Collect: {:.3} secs",
+                timing.walk.as_secs_f64(),
+                timing.search.as_secs_f64(),
+                timing.collect.as_secs_f64(),
+            ));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(help::TIMING_USAGE);
+            }
+        }
+
+        if !tab.file_errors.is_empty() {
+            const MAX_LISTED_ERRORS: usize = 20;
+
+            ui.same_line();
+            ui.text_colored([1.0, 0.875, 0.0, 1.0], format!("{} file(s) skipped due to errors", tab.file_errors.len()));
+            if ui.is_item_hovered() {
+                let mut listed = tab.file_errors.iter().take(MAX_LISTED_ERRORS).cloned().collect::<Vec<String>>().join("\n");
+                if tab.file_errors.len() > MAX_LISTED_ERRORS {
+                    listed.push_str(&format!("\n... and {} more", tab.file_errors.len() - MAX_LISTED_ERRORS));
+                }
+                ui.tooltip_text(listed);
+            }
+        }
+
         // @Enhancement: This is wasteful
-        let searching_text = if tab.is_searching() {
+        let searching_text = if tab.is_replacing() {
+            "Replacing..."
+        } else if tab.is_paused() {
+            "Paused..."
+        } else if tab.is_searching() {
             "Searching..."
         } else {
             "Done..."
         };
 
-        let searching_text_width = ui.calc_text_size(searching_text)[0];
+        let mut searching_text_width = ui.calc_text_size(searching_text)[0];
+        let show_spinner = tab.is_searching() && !tab.is_paused();
+        if show_spinner {
+            searching_text_width += ui.text_line_height() + unsafe { ui.style() }.item_spacing[0];
+        }
+
         let window_width = ui.window_content_region_max()[0];
         ui.same_line_with_pos(window_width - searching_text_width);
+        if show_spinner {
+            draw_spinner(ui);
+            ui.same_line();
+        }
         ui.text(searching_text);
     }); // build end
 
@@ -480,9 +2968,56 @@ fn draw_tab(ui: &Ui, state: &mut SearchTabs, tab_id: usize, mut tab: SearchTab,
 }
 
 fn main() {
-    let system = support::init("Search");
+    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let args = cli::parse_command_line(raw_args.iter().cloned()).unwrap_or_else(|err| {
+        // Scripts expect the error on stderr and a failed status, rather than a window.
+        if cli::wants_print(&raw_args) {
+            sys::attach_parent_console();
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+        log!("Failed to parse the command line, error: {}", err);
+        cli::Args::default()
+    });
+
+    // The paths are searched when given, even if something is piped. Stdin is only read with
+    // a query to search it: launchers and IDEs may keep a stdin open without ever closing it,
+    // which would block reading it before the window is created.
+    let read_stdin = args.paths.is_none() && (args.print || args.query.is_some());
+    let stdin = if read_stdin { cli::read_piped_stdin() } else { None };
+
+    if args.print {
+        logger::print_to_stderr();
+        sys::attach_parent_console();
+        std::process::exit(cli::run_headless(args, &settings::load_settings(), stdin));
+    }
+
+    // The fonts are built with the window, before the settings are opened, so their settings are read on their own.
+    let startup_settings = settings::load_settings();
+    let mut system = support::init("Search", startup_settings.cjk_glyphs, startup_settings.results_font == ResultsFont::Proportional);
+    let toolbar = Toolbar::load(&mut system);
+    let always_on_top = Rc::clone(&system.always_on_top);
+    let fonts = system.fonts;
     let mut settings = SettingsWindow::open_setting();
-    let mut hotkeys = HotkeysWindow::new();
+    system.imgui.load_ini_settings(&settings.settings.layout);
+
+    if !settings.settings.global_hotkey.is_empty() {
+        let hotkey = parse_hotkey(&settings.settings.global_hotkey).and_then(|hotkey| sys::GlobalHotkey::register(&hotkey));
+        match hotkey {
+            Ok(hotkey) => system.global_hotkey = Some(hotkey),
+            Err(err) => log!("Failed to register the global hotkey '{}', error: {}", settings.settings.global_hotkey, err),
+        }
+    }
+    let restored = Rc::clone(&system.restored);
+    let close_requested = Rc::clone(&system.close_requested);
+    let mut windows = ToolWindows {
+        hotkeys: HotkeysWindow::new(),
+        log: LogWindow::new(),
+        workspace: WorkspaceWindow::new(),
+        rg_import: RgImportWindow::new(),
+        statistics: StatisticsWindow::new(),
+    };
+    let mut palette = CommandPalette::new();
 
     let mut pending_command: Option<Child> = None;
     let mut commands = VecDeque::new();
@@ -490,15 +3025,69 @@ fn main() {
         tabs: Vec::new(),
         selected_tab: 0,
         set_selected_tab: None,
+        new_tabs: Vec::new(),
+        focus_query: false,
+        snippets: snippet::SnippetFiles::new(),
+        picked_folder: None,
+        confirm_quit: false,
     };
 
-    state.tabs.push(SearchTab::from_context(cwd()));
+    let mut first_tab = SearchTab::from_context(cwd(), &settings.settings);
+    if settings.settings.restore_workspace_search {
+        first_tab.restore_workspace_search(&settings.settings.workspace_summaries);
+    }
+    args.apply_switches(&mut first_tab.config);
+    if let Some(paths) = args.paths {
+        first_tab.config.paths = paths;
+    }
+    if let Some(patterns) = args.patterns {
+        first_tab.config.globs = patterns;
+    }
+    if let Some(stdin) = stdin {
+        first_tab.name = String::from(STDIN_PATH);
+        first_tab.stdin = Some(Arc::new(stdin));
+    }
+
+    if let Some(query) = args.query {
+        // New tabs are searched as soon as they are added.
+        first_tab.config.queries[0].query = query;
+        state.new_tabs.push(first_tab);
+    } else {
+        state.tabs.push(first_tab);
+    }
 
     system.main_loop(move |keep_running, ui| {
+        if restored.replace(false) {
+            state.focus_query = true;
+        }
+        if close_requested.replace(false) {
+            request_quit(keep_running, &mut state);
+        }
+
         let window_size = ui.io().display_size;
 
         settings.draw_settings(ui);
-        hotkeys.draw_hotkeys_help(ui);
+        settings.update_layout(ui);
+        settings.autosave();
+        windows.hotkeys.draw_hotkeys_help(ui);
+        windows.log.draw_log(ui);
+        if windows.statistics.draw_statistics(ui) {
+            if let Some(tab) = state.tabs.get(state.selected_tab) {
+                windows.statistics.open(tab.statistics());
+            }
+        }
+        if let Some(path) = windows.workspace.draw_workspace(ui) {
+            open_workspace(&path, &mut state, &mut settings.settings, &mut windows.workspace);
+        }
+        if let Some(command) = windows.rg_import.draw_import(ui) {
+            let mut tab = SearchTab::default();
+            tab.config = command.config;
+            // Like ripgrep, search the current directory if no path is given.
+            if tab.config.paths.is_empty() {
+                tab.config.paths = cwd();
+            }
+            state.tabs.push(tab);
+        }
 
         let window = ui.window("Search##main")
             .position([0.0, 0.0], Condition::FirstUseEver)
@@ -518,11 +3107,11 @@ fn main() {
                     let new_tab = if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
                         tab.clone_for_tab()
                     } else {
-                        SearchTab::from_context(cwd())
+                        SearchTab::from_context(cwd(), &settings.settings)
                     };
                     state.tabs.push(new_tab);
                 } else {
-                    state.tabs.push(SearchTab::from_context(cwd()));
+                    state.tabs.push(SearchTab::from_context(cwd(), &settings.settings));
                 }
             }
 
@@ -547,6 +3136,46 @@ fn main() {
                 state.set_selected_tab = Some(new_id);
             }
 
+            if key_ctrl && key_shift && ui.is_key_index_released(VirtualKeyCode::F as i32) {
+                if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                    tab.find.opened = true;
+                    tab.find.focus = true;
+                }
+            }
+
+            if key_ctrl && ui.is_key_index_released(VirtualKeyCode::L as i32) {
+                if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                    tab.cancel_search(true);
+                }
+            }
+
+            if key_ctrl && key_shift && ui.is_key_index_released(VirtualKeyCode::P as i32) {
+                palette.open();
+            }
+
+            // The text inputs have their own undo, which is kept while typing.
+            let undo = ui.is_key_index_released(VirtualKeyCode::Z as i32);
+            let redo = ui.is_key_index_released(VirtualKeyCode::Y as i32);
+            if key_ctrl && (undo || redo) && !ui.io().want_text_input {
+                if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                    navigate_history(tab, &settings.settings, redo);
+                }
+            }
+
+            if key_ctrl && ui.is_key_index_released(VirtualKeyCode::O as i32) {
+                open_folder(&mut state);
+            }
+            receive_picked_folder(&mut state, &settings.settings);
+
+            if let Some(action) = palette.draw_palette(ui) {
+                run_palette_action(ui, action, keep_running, &mut state, &mut settings, &mut windows);
+            }
+
+            if key_ctrl && ui.is_key_index_released(VirtualKeyCode::Q as i32) {
+                request_quit(keep_running, &mut state);
+            }
+            draw_quit_confirmation(ui, keep_running, &mut state);
+
             // Detect the hotkey that select the tab to the right.
             if key_ctrl && ui.is_key_index_released(VirtualKeyCode::W as i32) {
                 if !state.tabs.is_empty() {
@@ -564,31 +3193,64 @@ fn main() {
 
             if ui.is_key_index_released(VirtualKeyCode::F4 as i32) {
                 if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
-                    if !settings.settings.editor_path.is_empty() {
-                        if let Some(last_focused_row) = tab.last_focused_row {
-                            let command = build_command(
-                                &settings.settings.editor_path,
-                                tab.results[last_focused_row].path.as_ref().clone(),
-                                tab.results[last_focused_row].line_number as usize,
-                            );
-
-                            if let Ok(command) = command {
-                                commands.push_back(command);
-                            } else {
-                                println!("Invalid editor '{}'", settings.settings.editor_path);
+                    let editor = settings.settings.editor_command();
+                    let has_extension_editors = !settings.settings.extension_editors.is_empty();
+                    if is_multi_file_editor(editor) {
+                        let focused = tab
+                            .last_focused_row
+                            .filter(|row| tab.results[*row].has_file())
+                            .and_then(|row| archive::local_path(&tab.results[row].path).ok())
+                            .map(|path| path.to_string_lossy().into_owned());
+                        match tab.editor_files().and_then(|files| files_editor_commands(files, focused.as_deref(), &settings.settings)) {
+                            Ok((editor_commands, skipped)) => {
+                                commands.extend(editor_commands);
+                                if skipped != 0 {
+                                    log!("{} file(s) not opened, the editor of their extension opens a single file", skipped);
+                                }
+                            }
+                            Err(err) => log!("Failed to open the results in an editor, error: {}", err),
+                        }
+                    } else if !editor.is_empty() || has_extension_editors {
+                        if let Some(last_focused_row) = tab.last_focused_row.filter(|row| tab.results[*row].has_file()) {
+                            match result_editor_command(&tab.results[last_focused_row], &settings.settings) {
+                                Ok(command) => commands.push_back(command),
+                                Err(err) => {
+                                    let error = format!("Failed to open '{}' in an editor, error: {}", tab.results[last_focused_row].path, err);
+                                    log!("{}", error);
+                                    tab.error_message = Some(error);
+                                }
                             }
                         }
                     } else {
                         let error = String::from("Editor not configured");
-                        println!("{}", error);
+                        log!("{}", error);
                         tab.error_message = Some(error);
                     }
                 }
             }
 
+            for tab in state.tabs.iter_mut() {
+                let row = tab.open_in_editor.take().filter(|row| *row < tab.results.len());
+                if let Some(row) = row {
+                    match result_editor_command(&tab.results[row], &settings.settings) {
+                        Ok(command) => commands.push_back(command),
+                        Err(err) => {
+                            let error = err.to_string();
+                            log!("{}", error);
+                            tab.error_message = Some(error);
+                        }
+                    }
+                }
+            }
+
             if ui.is_key_index_released(VirtualKeyCode::F1 as i32) {
-                hotkeys.toggle_open();
+                windows.hotkeys.toggle_open();
+            }
+
+            if ui.is_key_index_released(VirtualKeyCode::F12 as i32) {
+                settings.settings.always_on_top = !settings.settings.always_on_top;
             }
+            always_on_top.set(settings.settings.always_on_top);
 
             if let Some(mut child) = pending_command.take() {
                 if let Ok(None) = child.try_wait() {
@@ -597,11 +3259,33 @@ fn main() {
             };
 
             while pending_command.is_none() {
-                if let Some(mut command) = commands.pop_front() {
-                    if let Ok(child) = command.spawn() {
-                        pending_command = Some(child);
-                    } else {
-                        println!("Failed to start editor '{:?}' with args '{:?}'", command.get_program(), command.get_args());
+                if let Some(EditorCommand { mut command, files }) = commands.pop_front() {
+                    match command.spawn() {
+                        Ok(child) => pending_command = Some(child),
+                        Err(err) => {
+                            log!("Failed to start editor '{:?}' with args '{:?}', error: {}", command.get_program(), command.get_args(), err);
+
+                            // Open the file with the default application instead, such that a misconfigured
+                            // editor doesn't prevent opening it. Not done for several files, which could
+                            // start as many applications.
+                            let opened = match files.as_slice() {
+                                [path] => match sys::open_file(path) {
+                                    Ok(()) => true,
+                                    Err(err) => {
+                                        log!("{}", err);
+                                        false
+                                    }
+                                },
+                                _ => false,
+                            };
+                            if !opened {
+                                let error = format!("Failed to start editor '{}' to open {} file(s)", command.get_program().to_string_lossy(), files.len());
+                                log!("{}", error);
+                                if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                                    tab.error_message = Some(error);
+                                }
+                            }
+                        }
                     }
                 } else {
                     break;
@@ -609,7 +3293,38 @@ fn main() {
             }
 
             if let Some(_) = ui.begin_menu_bar() {
-                draw_menu(ui, keep_running, &mut state, &mut settings, &mut hotkeys);
+                draw_menu(ui, keep_running, &mut state, &mut settings, &mut windows);
+            }
+
+            let action = if settings.settings.hide_toolbar {
+                None
+            } else {
+                toolbar.draw(ui)
+            };
+
+            match action {
+                Some(ToolbarAction::NewTab) => state.tabs.push(SearchTab::from_context(cwd(), &settings.settings)),
+                Some(ToolbarAction::Search) => {
+                    if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                        search_parallel(tab, &settings.settings);
+                    }
+                },
+                Some(ToolbarAction::Cancel) => {
+                    if let Some(tab) = state.tabs.get_mut(state.selected_tab) {
+                        tab.cancel_search(false);
+                    }
+                },
+                Some(ToolbarAction::Settings) => settings.open(true),
+                Some(ToolbarAction::Export) => {
+                    if let Some(tab) = state.tabs.get(state.selected_tab) {
+                        ui.set_clipboard_text(tab.export_text());
+                    }
+                },
+                None => (),
+            }
+
+            for tab in state.tabs.iter_mut() {
+                update_tab(tab, &settings.settings);
             }
 
             let tab_flags = TabBarFlags::REORDERABLE | TabBarFlags::AUTO_SELECT_NEW_TABS;
@@ -617,9 +3332,86 @@ fn main() {
                 let tabs = std::mem::replace(&mut state.tabs, vec![]);
                 for (tab_id, tab) in tabs.into_iter().enumerate() {
                     let _stack = ui.push_id_usize(tab_id);
-                    draw_tab(ui, &mut state, tab_id, tab, &settings.settings);
+                    draw_tab(ui, &mut state, tab_id, tab, &settings.settings, &fonts);
+                }
+
+                for mut tab in std::mem::take(&mut state.new_tabs).into_iter() {
+                    search_parallel(&mut tab, &settings.settings);
+                    state.set_selected_tab = Some(state.tabs.len());
+                    state.tabs.push(tab);
                 }
             });
+
+            // Remember the last search started in the workspace.
+            for tab in state.tabs.iter_mut().filter(|tab| tab.searched) {
+                tab.searched = false;
+                if let Some(workspace) = current_workspace() {
+                    push_workspace_summary(&mut settings.settings.workspace_summaries, tab.workspace_summary(workspace));
+                }
+            }
         });
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn match_entry(path: &str, line_number: u64) -> UiSearchEntry {
+        let entry = SearchResultEntry {
+            line_number,
+            offset: 0,
+            bytes: b"needle\n".to_vec(),
+            matches: vec![SearchMatch { start: 0, end: 6, query: 0 }],
+            kind: EntryKind::Match,
+            context_break: false,
+            score: 0,
+            captures: Vec::new(),
+        };
+        UiSearchEntry::new(Rc::new(path.to_owned()), Rc::new(String::from(".")), entry, None, 0)
+    }
+
+    #[test]
+    fn hidden_results_not_replaced() {
+        let mut tab = SearchTab::default();
+        tab.results = vec![match_entry("a.rs", 1), match_entry("a.rs", 2), match_entry("b.md", 1), match_entry("c.rs", 3)];
+        assert_eq!(tab.file_replacements().len(), 3);
+
+        tab.dismiss(1);
+        tab.toggle_extension("rs");
+        let files = tab.file_replacements();
+        let lines: Vec<(PathBuf, Vec<u64>)> = files
+            .iter()
+            .map(|file| (file.path.clone(), file.lines.iter().map(|line| line.line_number).collect()))
+            .collect();
+        assert_eq!(lines, vec![(PathBuf::from("a.rs"), vec![1]), (PathBuf::from("c.rs"), vec![3])]);
+    }
+
+    #[test]
+    fn minimap_follows_the_visible_rows() {
+        let mut tab = SearchTab::default();
+        tab.searched_roots = 1;
+        tab.results = vec![match_entry("a.rs", 1), match_entry("a.rs", 5), match_entry("b.md", 2)];
+        tab.update_minimap(4);
+        assert!(tab.minimap.is_none());
+        assert_eq!(tab.minimap_generation, Some(tab.results_generation));
+
+        // Only the rows of a single file are left, without changing the number of results.
+        tab.dismiss(2);
+        tab.update_minimap(4);
+        assert!(tab.minimap.is_some());
+    }
+
+    #[test]
+    fn jump_selects_a_single_row() {
+        let mut tab = SearchTab::default();
+        tab.results = vec![match_entry("a.rs", 1), match_entry("a.rs", 2), match_entry("b.md", 1)];
+        tab.results[0].selected = true;
+        tab.results[1].selected = true;
+        tab.last_selected_row = Some(1);
+
+        tab.select_and_scroll_to(2);
+        let selected: Vec<bool> = tab.results.iter().map(|result| result.selected).collect();
+        assert_eq!(selected, vec![false, false, true]);
+    }
+}
@@ -0,0 +1,150 @@
+use grep::{
+    matcher::{ByteSet, Captures, LineMatchKind, LineTerminator, Match, Matcher, NoCaptures, NoError},
+    regex::RegexMatcher,
+};
+
+/// Matcher of a query, either the default regex engine or `fancy_regex`, which supports
+/// lookaround and backreferences but is slower.
+#[derive(Clone)]
+pub enum QueryMatcher {
+    // Boxed, as it's much larger than the other variant.
+    Regex(Box<RegexMatcher>),
+    Fancy(fancy_regex::Regex),
+}
+
+impl QueryMatcher {
+    pub fn fancy(pattern: &str, ignore_case: bool) -> Result<Self, fancy_regex::Error> {
+        let flags = if ignore_case { "(?mi)" } else { "(?m)" };
+        let regex = fancy_regex::Regex::new(&format!("{}{}", flags, pattern))?;
+        return Ok(Self::Fancy(regex));
+    }
+
+    /// Byte ranges of the capture groups of the first match found from `at`, without the
+    /// whole match. Empty if nothing matched, groups that didn't participate are `None`.
+    pub fn capture_groups(&self, haystack: &[u8], at: usize) -> Vec<Option<(usize, usize)>> {
+        match self {
+            Self::Regex(matcher) => {
+                let mut captures = match matcher.new_captures() {
+                    Ok(captures) => captures,
+                    Err(_) => return Vec::new(),
+                };
+                if !matcher.captures_at(haystack, at, &mut captures).unwrap_or(false) {
+                    return Vec::new();
+                }
+                (1..captures.len()).map(|idx| captures.get(idx).map(|group| (group.start(), group.end()))).collect()
+            }
+            Self::Fancy(regex) => fancy_capture_groups(regex, haystack, at),
+        }
+    }
+}
+
+/// Same as `fancy_find_at`, but returns the capture groups of the match.
+fn fancy_capture_groups(regex: &fancy_regex::Regex, haystack: &[u8], at: usize) -> Vec<Option<(usize, usize)>> {
+    let mut offset = 0;
+    for chunk in haystack.utf8_chunks() {
+        let text = chunk.valid();
+        let end = offset + text.len();
+        if at <= end {
+            let start = at.saturating_sub(offset);
+            if let Ok(Some(captures)) = regex.captures_from_pos(text, start) {
+                return (1..captures.len())
+                    .map(|idx| captures.get(idx).map(|group| (offset + group.start(), offset + group.end())))
+                    .collect();
+            }
+        }
+        offset = end + chunk.invalid().len();
+    }
+
+    return Vec::new();
+}
+
+/// `fancy_regex` only searches UTF-8 text, so every valid chunk of the haystack is searched
+/// separately. Matches can't span invalid bytes.
+fn fancy_find_at(regex: &fancy_regex::Regex, haystack: &[u8], at: usize) -> Option<Match> {
+    let mut offset = 0;
+    for chunk in haystack.utf8_chunks() {
+        let text = chunk.valid();
+        let end = offset + text.len();
+        if at <= end {
+            let start = at.saturating_sub(offset);
+            // A failure, such as exceeding the backtracking limit, is reported as no match.
+            if let Ok(Some(found)) = regex.find_from_pos(text, start) {
+                return Some(Match::new(offset + found.start(), offset + found.end()));
+            }
+        }
+        offset = end + chunk.invalid().len();
+    }
+
+    return None;
+}
+
+impl Matcher for QueryMatcher {
+    type Captures = NoCaptures;
+    type Error = NoError;
+
+    fn find_at(&self, haystack: &[u8], at: usize) -> Result<Option<Match>, NoError> {
+        match self {
+            Self::Regex(matcher) => matcher.find_at(haystack, at),
+            Self::Fancy(regex) => Ok(fancy_find_at(regex, haystack, at)),
+        }
+    }
+
+    fn new_captures(&self) -> Result<NoCaptures, NoError> {
+        Ok(NoCaptures::new())
+    }
+
+    fn non_matching_bytes(&self) -> Option<&ByteSet> {
+        match self {
+            Self::Regex(matcher) => matcher.non_matching_bytes(),
+            Self::Fancy(_) => None,
+        }
+    }
+
+    // Without a line terminator, the searcher looks for matches line by line, which is
+    // needed for `fancy_regex` as it could otherwise match across lines.
+    fn line_terminator(&self) -> Option<LineTerminator> {
+        match self {
+            Self::Regex(matcher) => matcher.line_terminator(),
+            Self::Fancy(_) => None,
+        }
+    }
+
+    fn find_candidate_line(&self, haystack: &[u8]) -> Result<Option<LineMatchKind>, NoError> {
+        match self {
+            Self::Regex(matcher) => matcher.find_candidate_line(haystack),
+            Self::Fancy(regex) => Ok(fancy_find_at(regex, haystack, 0).map(|found| LineMatchKind::Confirmed(found.start()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fancy_lookaround_and_invalid_utf8() {
+        let matcher = QueryMatcher::fancy(r"foo(?=bar)", false).unwrap();
+        let found = matcher.find_at(b"foobaz foobar", 0).unwrap().unwrap();
+        assert_eq!((found.start(), found.end()), (7, 10));
+
+        // The invalid byte is skipped, and the offsets are still in bytes.
+        let matcher = QueryMatcher::fancy("b", true).unwrap();
+        let found = matcher.find_at(b"a\xffB", 0).unwrap().unwrap();
+        assert_eq!((found.start(), found.end()), (2, 3));
+        assert!(matcher.find_at(b"a\xffB", 3).unwrap().is_none());
+
+        assert!(QueryMatcher::fancy(r"foo(?=bar", false).is_err());
+    }
+
+    #[test]
+    fn capture_groups_of_first_match() {
+        let regex = grep::regex::RegexMatcher::new(r"(\w+)=(\d+)?").unwrap();
+        let matcher = QueryMatcher::Regex(Box::new(regex));
+        assert_eq!(matcher.capture_groups(b"a=1 b=", 0), vec![Some((0, 1)), Some((2, 3))]);
+        assert_eq!(matcher.capture_groups(b"a=1 b=", 3), vec![Some((4, 5)), None]);
+        assert!(matcher.capture_groups(b"none", 0).is_empty());
+
+        let matcher = QueryMatcher::fancy(r"(\w+)(?==)", false).unwrap();
+        assert_eq!(matcher.capture_groups(b"\xff key=value", 0), vec![Some((2, 5))]);
+    }
+}
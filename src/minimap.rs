@@ -0,0 +1,118 @@
+use imgui::*;
+
+/// Width of the strip drawn beside the results.
+pub const MINIMAP_WIDTH: f32 = 14.0;
+
+/// Height of the strip covered by a bucket, the number of buckets follows the height of the strip.
+const BUCKET_HEIGHT: f32 = 2.0;
+
+/// Number of matches per range of lines of a file, shown beside the results to see where
+/// the matches are and jump to them.
+pub struct MatchDensity {
+    /// Largest line of the matches, the size of the file isn't known so the last bucket ends there.
+    max_line: u64,
+    buckets: Vec<usize>,
+}
+
+impl MatchDensity {
+    /// Count the matches of `lines` in `bucket_count` ranges of lines of the same size.
+    pub fn new(lines: &[u64], bucket_count: usize) -> Self {
+        let max_line = lines.iter().copied().max().unwrap_or(0).max(1);
+        let mut density = Self { max_line, buckets: vec![0; bucket_count.max(1)] };
+        for line in lines.iter() {
+            let bucket = density.bucket(*line);
+            density.buckets[bucket] += 1;
+        }
+        return density;
+    }
+
+    /// Whether the strip was resized since the counts were computed.
+    pub fn is_stale(&self, bucket_count: usize) -> bool {
+        self.buckets.len() != bucket_count.max(1)
+    }
+
+    /// Index of the bucket counting `line`.
+    fn bucket(&self, line: u64) -> usize {
+        let bucket = line.saturating_sub(1) * self.buckets.len() as u64 / self.max_line;
+        return (bucket as usize).min(self.buckets.len() - 1);
+    }
+
+    /// Line at `fraction` of the height of the strip, between 0 and 1.
+    fn line_at(&self, fraction: f32) -> u64 {
+        let line = 1 + (fraction.clamp(0.0, 1.0) as f64 * self.max_line as f64) as u64;
+        return line.min(self.max_line);
+    }
+
+    /// Vertical position, between 0 and 1, of the start of `line` in the strip.
+    fn position_of(&self, line: u64) -> f32 {
+        return (line.saturating_sub(1) as f64 / self.max_line as f64).min(1.0) as f32;
+    }
+}
+
+/// Number of buckets for a strip of `height`.
+pub fn bucket_count(height: f32) -> usize {
+    return (height / BUCKET_HEIGHT).max(1.0) as usize;
+}
+
+/// Draw the strip of the match density, with the lines currently visible in the results
+/// outlined. Returns the line clicked or dragged to, for the results to scroll to it.
+pub fn draw_minimap(ui: &Ui, density: &MatchDensity, visible_lines: Option<(u64, u64)>, size: [f32; 2]) -> Option<u64> {
+    let [x, y] = ui.cursor_screen_pos();
+    ui.invisible_button("##minimap", size);
+    let active = ui.is_item_active();
+    let hovered = ui.is_item_hovered();
+
+    let draw_list = ui.get_window_draw_list();
+    let background = ui.style_color(imgui::StyleColor::FrameBg);
+    draw_list.add_rect([x, y], [x + size[0], y + size[1]], background).filled(true).build();
+
+    let most = density.buckets.iter().copied().max().unwrap_or(0).max(1);
+    let bucket_height = size[1] / density.buckets.len() as f32;
+    for (idx, count) in density.buckets.iter().enumerate().filter(|(_, count)| **count != 0) {
+        // Even a single match must stand out from the background.
+        let alpha = 0.35 + 0.65 * (*count as f32 / most as f32);
+        let top = y + idx as f32 * bucket_height;
+        draw_list
+            .add_rect([x, top], [x + size[0], top + bucket_height.max(1.0)], [0.26, 0.59, 0.98, alpha])
+            .filled(true)
+            .build();
+    }
+
+    if let Some((first, last)) = visible_lines {
+        let top = y + density.position_of(first) * size[1];
+        let bottom = y + density.position_of(last + 1) * size[1];
+        draw_list.add_rect([x, top], [x + size[0], bottom.max(top + 2.0)], [1.0, 1.0, 1.0, 0.6]).build();
+    }
+
+    let fraction = (ui.io().mouse_pos[1] - y) / size[1];
+    if hovered {
+        let line = density.line_at(fraction);
+        let count = density.buckets[density.bucket(line)];
+        ui.tooltip_text(format!("Around line {}: {} match(es)", line, count));
+    }
+
+    if active {
+        return Some(density.line_at(fraction));
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_counted_per_range_of_lines() {
+        let density = MatchDensity::new(&[1, 2, 3, 50, 100], 4);
+        assert_eq!(density.buckets, vec![3, 1, 0, 1]);
+        assert_eq!(density.line_at(0.0), 1);
+        assert_eq!(density.line_at(0.5), 51);
+        assert_eq!(density.line_at(1.0), 100);
+        assert!(!density.is_stale(4));
+        assert!(density.is_stale(8));
+
+        let density = MatchDensity::new(&[], 0);
+        assert_eq!(density.buckets, vec![0]);
+    }
+}
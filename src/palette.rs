@@ -0,0 +1,175 @@
+use imgui::*;
+
+use crate::fuzzy::FuzzyPattern;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PaletteAction {
+    NewTab,
+    OpenFolder,
+    DuplicateTab,
+    CloseTab,
+    Search,
+    PreviousSearch,
+    NextSearch,
+    CancelSearch,
+    ClearResults,
+    FindInResults,
+    ExportResults,
+    Statistics,
+    TogglePerRootGlobs,
+    ToggleGroupByFile,
+    ToggleRelativePaths,
+    ToggleToolbar,
+    ToggleAlwaysOnTop,
+    OpenWorkspace,
+    ImportRipgrep,
+    Settings,
+    Hotkeys,
+    Log,
+    Quit,
+}
+
+/// Every action of the palette, with its name and its hotkey, if any.
+const ACTIONS: [(PaletteAction, &str, &str); 23] = [
+    (PaletteAction::NewTab, "New tab", "Ctrl+T"),
+    (PaletteAction::OpenFolder, "Open folder in a new tab", "Ctrl+O"),
+    (PaletteAction::DuplicateTab, "Duplicate tab", "Ctrl+Shift+T"),
+    (PaletteAction::CloseTab, "Close tab", "Ctrl+W"),
+    (PaletteAction::Search, "Search", "Enter"),
+    (PaletteAction::PreviousSearch, "Go back to the previous search", "Ctrl+Z"),
+    (PaletteAction::NextSearch, "Go forward to the next search", "Ctrl+Y"),
+    (PaletteAction::CancelSearch, "Cancel search", "Esc"),
+    (PaletteAction::ClearResults, "Clear results", "Ctrl+L"),
+    (PaletteAction::FindInResults, "Find in results", "Ctrl+Shift+F"),
+    (PaletteAction::ExportResults, "Export results to the clipboard", ""),
+    (PaletteAction::Statistics, "Statistics of the results", ""),
+    (PaletteAction::TogglePerRootGlobs, "Toggle per path patterns", ""),
+    (PaletteAction::ToggleGroupByFile, "Toggle grouping the results by file", ""),
+    (PaletteAction::ToggleRelativePaths, "Toggle relative paths", ""),
+    (PaletteAction::ToggleToolbar, "Toggle the toolbar", ""),
+    (PaletteAction::ToggleAlwaysOnTop, "Toggle always on top", "F12"),
+    (PaletteAction::OpenWorkspace, "Open workspace", ""),
+    (PaletteAction::ImportRipgrep, "Import ripgrep command", ""),
+    (PaletteAction::Settings, "Settings", ""),
+    (PaletteAction::Hotkeys, "Hotkeys", "F1"),
+    (PaletteAction::Log, "Log", ""),
+    (PaletteAction::Quit, "Quit", "Ctrl+Q"),
+];
+
+/// Indices, in `ACTIONS`, of the actions matching the query, the best matches first.
+/// Every action is listed, in order, if the query is empty.
+fn filter_actions(query: &str) -> Vec<usize> {
+    let pattern = FuzzyPattern::new(query, true);
+    if query.trim().is_empty() {
+        return (0..ACTIONS.len()).collect();
+    }
+
+    let mut matched: Vec<(usize, u32)> = ACTIONS
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (_, name, _))| pattern.find(name).map(|matched| (idx, matched.score)))
+        .collect();
+    // The sort is stable, so actions with the same score stay in order.
+    matched.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    return matched.into_iter().map(|(idx, _)| idx).collect();
+}
+
+/// Popup listing every action, filtered as the user types, such as in a code editor.
+pub struct CommandPalette {
+    open_requested: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self { open_requested: false, query: String::new(), selected: 0 }
+    }
+
+    pub fn open(&mut self) {
+        self.open_requested = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Returns the action to run, once the user picked one.
+    pub fn draw_palette(&mut self, ui: &Ui) -> Option<PaletteAction> {
+        if std::mem::take(&mut self.open_requested) {
+            ui.open_popup("##command-palette");
+        }
+
+        let _p = ui.begin_popup("##command-palette")?;
+
+        if ui.is_window_appearing() {
+            ui.set_keyboard_focus_here();
+        }
+        let entered = {
+            let _w = ui.push_item_width(500.0);
+            ui.input_text("##palette-query", &mut self.query)
+                .hint("Type the name of an action")
+                .enter_returns_true(true)
+                .build()
+        };
+        if ui.is_item_edited() {
+            self.selected = 0;
+        }
+
+        let actions = filter_actions(&self.query);
+        if ui.is_key_pressed(Key::DownArrow) && !actions.is_empty() {
+            self.selected = (self.selected + 1) % actions.len();
+        }
+        if ui.is_key_pressed(Key::UpArrow) && !actions.is_empty() {
+            self.selected = (self.selected + actions.len() - 1) % actions.len();
+        }
+        self.selected = self.selected.min(actions.len().saturating_sub(1));
+
+        let mut picked = None;
+        if entered {
+            picked = actions.get(self.selected).copied();
+        }
+
+        for (row, idx) in actions.iter().enumerate() {
+            let (_, name, hotkey) = ACTIONS[*idx];
+            if ui.selectable_config(name).selected(row == self.selected).build() {
+                picked = Some(*idx);
+            }
+            if !hotkey.is_empty() {
+                ui.same_line_with_pos(400.0);
+                ui.text_disabled(hotkey);
+            }
+        }
+
+        if actions.is_empty() {
+            ui.text_disabled("No matching action");
+        }
+
+        if picked.is_some() || ui.is_key_pressed(Key::Escape) {
+            ui.close_current_popup();
+        }
+
+        return picked.map(|idx| ACTIONS[idx].0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(query: &str) -> Vec<&'static str> {
+        filter_actions(query).into_iter().map(|idx| ACTIONS[idx].1).collect()
+    }
+
+    #[test]
+    fn every_action_without_query() {
+        assert_eq!(filter_actions("").len(), ACTIONS.len());
+        assert_eq!(names("  ")[0], "New tab");
+    }
+
+    #[test]
+    fn actions_filtered_fuzzily() {
+        let matched = names("clres");
+        assert_eq!(matched[0], "Clear results");
+        assert!(!matched.contains(&"Quit"));
+        assert!(names("zzz").is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{bail, Result};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{self, TryRecvError},
+};
+
+pub struct LineReplacement {
+    pub line_number: u64,
+    /// Content of the line when it was searched, used to detect if the file changed since.
+    pub bytes: Vec<u8>,
+    pub matches: Vec<(usize, usize)>,
+}
+
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub lines: Vec<LineReplacement>,
+}
+
+pub struct ReplaceResult {
+    pub replaced: usize,
+    pub error: Option<String>,
+}
+
+/// Adapt the case of `replacement` to the case of `matched`. All-caps, lowercase
+/// and capitalized matches are recognized, any other case returns the
+/// replacement unchanged. A single uppercase letter is taken as capitalized, and
+/// only the first letter of the replacement is changed for capitalized matches.
+pub fn preserve_case(matched: &str, replacement: &str) -> String {
+    let mut letters = matched.chars().filter(|value| value.is_alphabetic());
+    let first = if let Some(first) = letters.next() {
+        first
+    } else {
+        return replacement.to_owned();
+    };
+
+    let rest_lowercase = letters.clone().all(|value| value.is_lowercase());
+    let rest_uppercase = letters.all(|value| value.is_uppercase());
+
+    if first.is_uppercase() && rest_lowercase {
+        let mut chars = replacement.chars();
+        if let Some(first) = chars.next() {
+            return first.to_uppercase().chain(chars).collect();
+        }
+    }
+
+    if first.is_uppercase() && rest_uppercase {
+        return replacement.to_uppercase();
+    }
+
+    if first.is_lowercase() && rest_lowercase {
+        return replacement.to_lowercase();
+    }
+
+    return replacement.to_owned();
+}
+
+/// Text substituted to `matched`, with the case of `matched` if `keep_case`.
+pub fn replacement_text(matched: &[u8], replacement: &str, keep_case: bool) -> String {
+    if keep_case {
+        return preserve_case(&String::from_utf8_lossy(matched), replacement);
+    }
+
+    return replacement.to_owned();
+}
+
+/// Returns the line with every match substituted by the replacement.
+pub fn replace_line(bytes: &[u8], matches: &[(usize, usize)], replacement: &str, keep_case: bool) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut copied = 0;
+    for &(start, end) in matches.iter() {
+        result.extend_from_slice(&bytes[copied..start]);
+        result.extend_from_slice(replacement_text(&bytes[start..end], replacement, keep_case).as_bytes());
+        copied = end;
+    }
+    result.extend_from_slice(&bytes[copied..]);
+    return result;
+}
+
+fn replace_in_file(file: &FileReplacement, replacement: &str, keep_case: bool) -> Result<usize> {
+    let content = fs::read(&file.path)?;
+
+    let mut replaced = 0;
+    let mut result = Vec::with_capacity(content.len());
+    let mut lines = file.lines.iter().peekable();
+    for (idx, line) in content.split_inclusive(|&value| value == b'\n').enumerate() {
+        let line_number = (idx + 1) as u64;
+        match lines.next_if(|value| value.line_number == line_number) {
+            Some(replace) => {
+                if replace.bytes != line {
+                    bail!("Line {} changed since the search", line_number);
+                }
+
+                result.extend(replace_line(line, &replace.matches, replacement, keep_case));
+                replaced += replace.matches.len();
+            }
+            None => result.extend_from_slice(line),
+        }
+    }
+
+    if let Some(line) = lines.next() {
+        bail!("Line {} doesn't exist anymore", line.line_number);
+    }
+
+    fs::write(&file.path, result)?;
+    return Ok(replaced);
+}
+
+/// Lines that would be replaced, shown before the files are written such that each line
+/// can be excluded from the replace.
+pub struct ReplacePreview {
+    pub files: Vec<FileReplacement>,
+    /// Index of the file and of the line in the file, for each line of the preview.
+    pub rows: Vec<(usize, usize)>,
+    /// Whether each row is replaced.
+    pub included: Vec<bool>,
+}
+
+impl ReplacePreview {
+    pub fn new(files: Vec<FileReplacement>) -> Self {
+        let rows: Vec<(usize, usize)> = files
+            .iter()
+            .enumerate()
+            .flat_map(|(file, replacement)| (0..replacement.lines.len()).map(move |line| (file, line)))
+            .collect();
+        let included = vec![true; rows.len()];
+        Self { files, rows, included }
+    }
+
+    pub fn line(&self, row: usize) -> (&FileReplacement, &LineReplacement) {
+        let (file, line) = self.rows[row];
+        (&self.files[file], &self.files[file].lines[line])
+    }
+
+    pub fn included_count(&self) -> usize {
+        self.included.iter().filter(|included| **included).count()
+    }
+
+    /// Files with only the included lines, the files without any are dropped.
+    pub fn into_included(self) -> Vec<FileReplacement> {
+        let mut included = self.included.into_iter();
+        self.files
+            .into_iter()
+            .filter_map(|mut file| {
+                file.lines.retain(|_| included.next().unwrap_or(false));
+                (!file.lines.is_empty()).then_some(file)
+            })
+            .collect()
+    }
+}
+
+pub struct PendingReplace {
+    rx: mpsc::Receiver<ReplaceResult>,
+}
+
+impl PendingReplace {
+    pub fn try_recv(&self) -> std::result::Result<ReplaceResult, TryRecvError> {
+        self.rx.try_recv()
+    }
+}
+
+pub fn spawn_replace(files: Vec<FileReplacement>, replacement: String, keep_case: bool) -> PendingReplace {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for file in files.iter() {
+            let result = match replace_in_file(file, &replacement, keep_case) {
+                Ok(replaced) => ReplaceResult { replaced, error: None },
+                Err(err) => {
+                    log!("Failed to replace in '{}', error: {}", file.path.to_string_lossy(), err);
+                    ReplaceResult { replaced: 0, error: Some(err.to_string()) }
+                }
+            };
+
+            if tx.send(result).is_err() {
+                break;
+            }
+        }
+    });
+
+    return PendingReplace { rx };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserve_case_of_simple_words() {
+        assert_eq!(preserve_case("foo", "bar"), "bar");
+        assert_eq!(preserve_case("FOO", "bar"), "BAR");
+        assert_eq!(preserve_case("Foo", "bar"), "Bar");
+        assert_eq!(preserve_case("Foo", "BAR"), "BAR");
+        assert_eq!(preserve_case("Foo", "barBaz"), "BarBaz");
+        assert_eq!(preserve_case("foo", "BaR"), "bar");
+    }
+
+    #[test]
+    fn preserve_case_falls_back_to_replacement() {
+        assert_eq!(preserve_case("fOo", "bAr"), "bAr");
+        assert_eq!(preserve_case("123", "bAr"), "bAr");
+        assert_eq!(preserve_case("", "bAr"), "bAr");
+    }
+
+    #[test]
+    fn preserve_case_ignores_non_letters() {
+        assert_eq!(preserve_case("FOO_BAR", "baz"), "BAZ");
+        assert_eq!(preserve_case("_foo", "BAZ"), "baz");
+        assert_eq!(preserve_case("Foo2", "baz"), "Baz");
+    }
+
+    #[test]
+    fn preserve_case_of_single_letters() {
+        assert_eq!(preserve_case("F", "barBaz"), "BarBaz");
+        assert_eq!(preserve_case("f", "BarBaz"), "barbaz");
+    }
+
+    #[test]
+    fn replace_every_match_of_a_line() {
+        let line = b"Foo foo FOO\n";
+        let matches = [(0, 3), (4, 7), (8, 11)];
+        assert_eq!(replace_line(line, &matches, "bar", false), b"bar bar bar\n");
+        assert_eq!(replace_line(line, &matches, "bar", true), b"Bar bar BAR\n");
+    }
+
+    #[test]
+    fn preview_without_excluded_lines() {
+        let file = |path: &str, line_numbers: &[u64]| FileReplacement {
+            path: PathBuf::from(path),
+            lines: line_numbers
+                .iter()
+                .map(|&line_number| LineReplacement { line_number, bytes: b"foo\n".to_vec(), matches: vec![(0, 3)] })
+                .collect(),
+        };
+
+        let mut preview = ReplacePreview::new(vec![file("a.rs", &[1, 4, 9]), file("b.rs", &[2])]);
+        assert_eq!(preview.rows, vec![(0, 0), (0, 1), (0, 2), (1, 0)]);
+        assert_eq!(preview.line(3).1.line_number, 2);
+
+        preview.included[1] = false;
+        preview.included[3] = false;
+        assert_eq!(preview.included_count(), 2);
+
+        let files: Vec<(PathBuf, Vec<u64>)> = preview
+            .into_included()
+            .into_iter()
+            .map(|file| (file.path, file.lines.iter().map(|line| line.line_number).collect()))
+            .collect();
+        assert_eq!(files, vec![(PathBuf::from("a.rs"), vec![1, 9])]);
+    }
+}
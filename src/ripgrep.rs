@@ -0,0 +1,297 @@
+use anyhow::{bail, Result};
+use imgui::*;
+
+use crate::{
+    args,
+    search::{QueryCombination, SearchConfig, SearchQuery},
+};
+
+/// Search described by a ripgrep command line.
+pub struct RgCommand {
+    pub config: SearchConfig,
+    /// Arguments that are not supported and were ignored.
+    pub ignored: Vec<String>,
+}
+
+fn flag_value(flag: &str, inline: Option<String>, it: &mut impl Iterator<Item = String>) -> Result<String> {
+    if let Some(value) = inline {
+        return Ok(value);
+    }
+
+    match it.next() {
+        Some(value) => Ok(value),
+        None => bail!("Expected a value after '{}'", flag),
+    }
+}
+
+fn parse_context(flag: &str, value: &str) -> Result<usize> {
+    match value.parse::<usize>() {
+        Ok(lines) => Ok(lines),
+        _ => bail!("Invalid number of lines '{}' for '{}'", value, flag),
+    }
+}
+
+/// Quote the glob if needed, such that it can be parsed back from `SearchConfig::globs`.
+fn quote_glob(glob: &str) -> String {
+    if glob.contains(' ') || glob.contains('\t') || glob.contains('"') {
+        return format!("\"{}\"", glob.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+
+    return glob.to_owned();
+}
+
+/// Parse a ripgrep command line, such as `rg -i -g "*.rs" needle src`, into a search config.
+pub fn parse_rg_command(command: &str) -> Result<RgCommand> {
+    let mut args = args::parse_args(command)?.into_iter().peekable();
+    if args.peek().is_some_and(|program| program == "rg" || program.ends_with("/rg") || program.ends_with("rg.exe")) {
+        args.next();
+    }
+
+    let mut patterns = Vec::new();
+    let mut positionals = Vec::new();
+    let mut globs = Vec::new();
+    let mut ignored = Vec::new();
+    let mut context_lines = None;
+    let mut before_context = None;
+    let mut after_context = None;
+    let mut ignore_case = false;
+    let mut fixed_strings = false;
+    let mut word = false;
+    let mut invert_match = false;
+    let mut pcre = false;
+    let mut hidden = false;
+    let mut glob_case_insensitive = false;
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positionals.extend(args.by_ref());
+            break;
+        }
+
+        if !arg.starts_with('-') || arg == "-" {
+            positionals.push(arg);
+            continue;
+        }
+
+        // Split "--flag=value" and "-Cvalue" in the flag and its value.
+        let (flag, inline) = if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, value)) => (format!("--{}", name), Some(value.to_owned())),
+                None => (arg.clone(), None),
+            }
+        } else if arg.len() > 2 && matches!(arg.as_bytes()[1], b'e' | b'g' | b'C' | b'A' | b'B') {
+            // The flag is a single ASCII byte, so the value starts on a character boundary.
+            (arg[..2].to_owned(), Some(arg[2..].to_owned()))
+        } else {
+            (arg.clone(), None)
+        };
+
+        match flag.as_str() {
+            "-e" | "--regexp" => patterns.push(flag_value(&flag, inline, &mut args)?),
+            "-g" | "--glob" => globs.push(quote_glob(&flag_value(&flag, inline, &mut args)?)),
+            "--iglob" => {
+                globs.push(quote_glob(&flag_value(&flag, inline, &mut args)?));
+                glob_case_insensitive = true;
+            }
+            "--glob-case-insensitive" => glob_case_insensitive = true,
+            "-C" | "--context" => context_lines = Some(parse_context(&flag, &flag_value(&flag, inline, &mut args)?)?),
+            "-A" | "--after-context" => after_context = Some(parse_context(&flag, &flag_value(&flag, inline, &mut args)?)?),
+            "-B" | "--before-context" => before_context = Some(parse_context(&flag, &flag_value(&flag, inline, &mut args)?)?),
+            "--hidden" | "-." => hidden = true,
+            "--ignore-case" | "--smart-case" => ignore_case = true,
+            "--case-sensitive" => ignore_case = false,
+            "--fixed-strings" => fixed_strings = true,
+            "--word-regexp" => word = true,
+            "--invert-match" => invert_match = true,
+            "--pcre2" => pcre = true,
+            _ if !flag.starts_with("--") && inline.is_none() => {
+                // Short flags can be grouped, for instance "-iw".
+                for value in flag[1..].chars() {
+                    match value {
+                        'i' | 'S' => ignore_case = true,
+                        's' => ignore_case = false,
+                        'F' => fixed_strings = true,
+                        'w' => word = true,
+                        'v' => invert_match = true,
+                        'P' => pcre = true,
+                        '.' => hidden = true,
+                        _ => ignored.push(format!("-{}", value)),
+                    }
+                }
+            }
+            _ => ignored.push(arg),
+        }
+    }
+
+    // Without -e, the first positional argument is the pattern.
+    if patterns.is_empty() && !positionals.is_empty() {
+        patterns.push(positionals.remove(0));
+    }
+
+    if patterns.is_empty() {
+        bail!("Expected a pattern to search");
+    }
+
+    let queries = patterns
+        .into_iter()
+        .map(|pattern| {
+            let mut query = SearchQuery::new();
+            query.regex_syntax = !fixed_strings || word;
+            query.query = match (fixed_strings, word) {
+                (false, false) => pattern,
+                (false, true) => format!(r"\b(?:{})\b", pattern),
+                (true, false) => pattern,
+                (true, true) => format!(r"\b{}\b", regex::escape(&pattern)),
+            };
+            query.ignore_case = ignore_case;
+            query.invert_match = invert_match;
+            query.pcre = pcre;
+            // Like ripgrep, -A and -B take precedence over -C.
+            query.before_context = before_context.or(context_lines).unwrap_or(0);
+            query.after_context = after_context.or(context_lines).unwrap_or(0);
+            query
+        })
+        .collect();
+
+    let mut config = SearchConfig::default();
+    config.paths = args::join_quoted(positionals.iter().map(String::as_str), ';');
+    config.globs = globs.join(" ");
+    config.hidden = hidden;
+    // ripgrep applies it per glob for --iglob, but the config only has a single option.
+    config.globs_ignore_case = glob_case_insensitive;
+    config.queries = queries;
+    // Multiple patterns match a line if any of them matches.
+    config.combination = QueryCombination::Any;
+
+    return Ok(RgCommand { config, ignored });
+}
+
+pub struct RgImportWindow {
+    opened: bool,
+    command: String,
+    error: Option<String>,
+}
+
+impl RgImportWindow {
+    pub fn new() -> Self {
+        Self { opened: false, command: String::new(), error: None }
+    }
+
+    pub fn open(&mut self) {
+        self.opened = true;
+        self.error = None;
+    }
+
+    /// Returns the parsed command, once the user imported a valid one.
+    pub fn draw_import(&mut self, ui: &Ui) -> Option<RgCommand> {
+        if !self.opened {
+            return None;
+        }
+
+        let display_size = ui.io().display_size;
+        let window_size = [600.0, 120.0];
+        let pos_x = (display_size[0] / 2.0) - (window_size[0] / 2.0);
+        let pos_y = (display_size[1] / 2.0) - (window_size[1] / 2.0);
+
+        let window = ui
+            .window("Import ripgrep command")
+            .size(window_size, Condition::Appearing)
+            .position([pos_x, pos_y], Condition::Appearing)
+            .collapsible(false)
+            .opened(&mut self.opened);
+
+        let mut confirmed = false;
+        window.build(|| {
+            ui.text("Command:");
+            let _w = ui.push_item_width(-1.0);
+            confirmed |= ui
+                .input_text("##rg-command", &mut self.command)
+                .hint("rg -i -g \"*.rs\" needle src")
+                .enter_returns_true(true)
+                .build();
+
+            confirmed |= ui.button("Import");
+
+            if let Some(error) = &self.error {
+                ui.text_colored([1.0, 0.0, 0.0, 1.0], error);
+            }
+        });
+
+        if !confirmed {
+            return None;
+        }
+
+        match parse_rg_command(&self.command) {
+            Ok(command) => {
+                for arg in command.ignored.iter() {
+                    log!("Ignored unsupported ripgrep argument '{}'", arg);
+                }
+
+                self.opened = false;
+                return Some(command);
+            }
+            Err(err) => {
+                log!("Failed to import ripgrep command '{}', error: {}", self.command, err);
+                self.error = Some(err.to_string());
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_simple_command() {
+        let command = parse_rg_command(r#"rg -i -g "*.rs" -g "!target dir/**" -C3 needle src tests"#).unwrap();
+        let config = command.config;
+        assert_eq!(config.paths, "src;tests");
+        assert_eq!(config.glob_list().unwrap(), vec!["*.rs", "!target dir/**"]);
+        assert_eq!((config.queries[0].before_context, config.queries[0].after_context), (3, 3));
+        assert_eq!(config.queries.len(), 1);
+        assert_eq!(config.queries[0].query, "needle");
+        assert!(config.queries[0].regex_syntax);
+        assert!(config.queries[0].ignore_case);
+        assert!(command.ignored.is_empty());
+        assert!(!config.globs_ignore_case);
+
+        let command = parse_rg_command("rg -P --iglob=*.TXT needle(?=s)").unwrap();
+        assert!(command.config.queries[0].pcre);
+        assert_eq!(command.config.glob_list().unwrap(), vec!["*.TXT"]);
+        assert!(command.config.globs_ignore_case);
+
+        // -A and -B take precedence over -C, whatever their order.
+        let command = parse_rg_command("rg -A 5 -C2 needle").unwrap();
+        assert_eq!((command.config.queries[0].before_context, command.config.queries[0].after_context), (2, 5));
+        let command = parse_rg_command("rg --before-context=1 needle").unwrap();
+        assert_eq!((command.config.queries[0].before_context, command.config.queries[0].after_context), (1, 0));
+    }
+
+    #[test]
+    fn import_multiple_patterns_and_grouped_flags() {
+        let command = parse_rg_command("rg -Fwv --hidden -e a.b --regexp=c --sort path -- -x").unwrap();
+        let config = command.config;
+        // The value of an unknown flag can't be known, so it's taken as a path.
+        assert_eq!(config.paths, "path;-x");
+        assert!(config.hidden);
+        assert_eq!(config.combination, QueryCombination::Any);
+
+        let queries: Vec<&str> = config.queries.iter().map(|query| query.query.as_str()).collect();
+        assert_eq!(queries, vec![r"\ba\.b\b", r"\bc\b"]);
+        assert!(config.queries.iter().all(|query| query.regex_syntax && query.invert_match && !query.ignore_case));
+        assert_eq!(command.ignored, vec!["--sort"]);
+    }
+
+    #[test]
+    fn import_invalid_commands() {
+        assert!(parse_rg_command("rg").is_err());
+        assert!(parse_rg_command("rg -i").is_err());
+        assert!(parse_rg_command("rg -C").is_err());
+        assert!(parse_rg_command("rg -C x needle").is_err());
+        // A multibyte short flag is unsupported, it's ignored rather than splitting a character.
+        assert_eq!(parse_rg_command("rg -\u{e9} foo").unwrap().ignored, vec!["-\u{e9}"]);
+        assert_eq!(parse_rg_command("rg -\u{e9}x foo").unwrap().ignored, vec!["-\u{e9}", "-x"]);
+    }
+}
@@ -1,66 +1,238 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use grep::{
-    matcher::Matcher,
-    regex::{RegexMatcher, RegexMatcherBuilder},
-    searcher::{self, BinaryDetection, Searcher, SearcherBuilder, SinkMatch},
+    matcher::{LineTerminator, Matcher},
+    regex::RegexMatcherBuilder,
+    searcher::{self, BinaryDetection, Searcher, SearcherBuilder, SinkContext, SinkMatch},
 };
 use ignore::{
     WalkBuilder, WalkState,
     overrides::{Override, OverrideBuilder}
 };
 use std::{
+    collections::BTreeMap,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc::{self, TryRecvError}, Arc,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
     thread,
 };
 use regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{args, fuzzy::FuzzyPattern, matcher::QueryMatcher};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SearchMatch {
+    pub start: usize,
+    pub end: usize,
+    /// Index, in `SearchConfig::queries`, of the query that found this match.
+    pub query: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EntryKind {
+    /// Line matched by the queries.
+    Match,
+    /// Line that is only part of the context of a match.
+    Context,
+    /// The file name matched by the queries, in which case `bytes` is the file name.
+    FileName,
+}
 
 pub struct SearchResultEntry {
     pub line_number: u64,
+    /// Offset, in bytes, of the start of the line in the file.
+    pub offset: u64,
     pub bytes: Vec<u8>,
-    pub matches: Vec<(usize, usize)>,
+    pub matches: Vec<SearchMatch>,
+    pub kind: EntryKind,
+    /// Whether this line starts a new group of contiguous lines, that is,
+    /// lines were skipped between the previous entry and this one.
+    pub context_break: bool,
+    /// Score of the fuzzy match of the file name, 0 when it's not matched fuzzily.
+    pub score: u32,
+    /// Byte ranges of the capture groups of the first match of the line, at most
+    /// `MAX_CAPTURE_GROUPS`. Only set when the capture groups are extracted.
+    pub captures: Vec<Option<(usize, usize)>>,
 }
 
+/// Default for `SearchConfig::max_paragraph_lines`.
+pub const DEFAULT_MAX_PARAGRAPH_LINES: usize = 20;
+
+/// Capture groups after this one aren't extracted, to keep a reasonable number of columns.
+pub const MAX_CAPTURE_GROUPS: usize = 8;
+
 pub struct SearchResult {
     pub path: PathBuf,
+    /// Search path, as specified in the config, from which `path` was reached.
+    pub root: PathBuf,
     pub entries: Vec<SearchResultEntry>,
+    /// Set if the file couldn't be searched, in which case there is no entries.
+    pub error: Option<String>,
+    /// Time spent walking the directories since the previous file was searched by the same thread.
+    pub walk_duration: Duration,
+    /// Time spent searching the file.
+    pub search_duration: Duration,
+    /// Modification time of the file, only read if something was found in it.
+    pub modified: Option<SystemTime>,
+}
+
+/// Returns the line without its line terminator, either "\n" or "\r\n".
+pub fn trim_line_terminator(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.strip_suffix(b"\r").unwrap_or(bytes)
+}
+
+/// Returns the range of bytes of the line to display, such that lines longer than `max_len`
+/// are truncated around `first_match`. 0 never truncates. The range doesn't cut a UTF-8
+/// sequence, unless it has no character boundary at all, such as in some binary lines.
+pub fn display_range(bytes: &[u8], first_match: usize, max_len: usize) -> std::ops::Range<usize> {
+    // The line terminator isn't displayed, in particular the '\r' of CRLF files.
+    let len = trim_line_terminator(bytes).len();
+    if max_len == 0 || len <= max_len {
+        return 0..len;
+    }
+
+    let start = std::cmp::min(first_match.saturating_sub(max_len / 2), len - max_len);
+    let end = start + max_len;
+
+    // Don't cut in the middle of a UTF-8 sequence.
+    let is_continuation = |value: u8| (value & 0xC0) == 0x80;
+    let mut char_start = start;
+    while char_start < end && is_continuation(bytes[char_start]) {
+        char_start += 1;
+    }
+    let mut char_end = end;
+    while char_end > char_start && char_end < len && is_continuation(bytes[char_end]) {
+        char_end -= 1;
+    }
+
+    if char_start >= char_end {
+        return start..end;
+    }
+    return char_start..char_end;
+}
+
+/// Lines around `line_number`, at most `count` above and below, each prefixed with its
+/// line number. The line itself is marked with a '>' and long lines are truncated.
+pub fn surrounding_lines(reader: impl std::io::BufRead, line_number: u64, count: u64) -> std::io::Result<String> {
+    const MAX_LINE_LEN: usize = 200;
+
+    let first = line_number.saturating_sub(count).max(1);
+    let last = line_number.saturating_add(count);
+    let mut text = String::new();
+    for (idx, line) in (1..=last).zip(reader.split(b'\n')) {
+        let line = line?;
+        if idx < first {
+            continue;
+        }
+
+        let line = trim_line_terminator(&line);
+        let line = String::from_utf8_lossy(&line[..line.len().min(MAX_LINE_LEN)]);
+        let marker = if idx == line_number { '>' } else { ' ' };
+        text.push_str(&format!("{}{:>5}: {}\n", marker, idx, line));
+    }
+
+    return Ok(text.trim_end().to_owned());
+}
+
+fn is_separator(value: char) -> bool {
+    value == '/' || value == '\\'
+}
+
+/// Returns the path relative to the root it was found from. If the root is the
+/// file itself, the file name is returned rather than an empty path. Paths that
+/// are not in the root are returned unchanged.
+pub fn relative_path<'a>(path: &'a str, root: &str) -> &'a str {
+    let root = root.trim_end_matches(is_separator);
+    let rest = match path.strip_prefix(root) {
+        Some(rest) => rest,
+        None => return path,
+    };
+
+    if rest.is_empty() {
+        return path.rsplit(is_separator).next().unwrap_or(path);
+    }
+
+    // The root "src" doesn't contain "src2/main.rs", the root must be followed by a separator.
+    let relative = rest.trim_start_matches(is_separator);
+    if relative.len() == rest.len() || relative.is_empty() {
+        return path;
+    }
+
+    return relative;
 }
 
 #[derive(Debug)]
-pub struct SearchError;
+pub struct SearchError(String);
 impl searcher::SinkError for SearchError {
     fn error_message<T: std::fmt::Display>(message: T) -> Self {
-        println!("Error: {}", message);
-        Self
+        log!("Error: {}", message);
+        Self(message.to_string())
+    }
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
 struct SearchSink<'a, 'm> {
     results: &'a mut Vec<SearchResultEntry>,
-    matcher: &'m RegexMatcher,
+    matcher: &'m QueryMatcher,
+    query: usize,
+    line_range: LineRange,
+    context_break: bool,
+    captures: bool,
+}
+
+impl SearchSink<'_, '_> {
+    /// Whether the line is kept, or `None` when it's past the range and the search can stop.
+    fn keep_line(&self, line_number: u64) -> Option<bool> {
+        if self.line_range.max.is_some_and(|max| line_number > max) {
+            return None;
+        }
+        return Some(self.line_range.contains(line_number));
+    }
 }
 
 impl searcher::Sink for SearchSink<'_, '_> {
     type Error = SearchError;
 
     fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        match self.keep_line(mat.line_number().unwrap()) {
+            Some(true) => {}
+            Some(false) => return Ok(true),
+            None => return Ok(false),
+        }
+
         let mut at = 0;
         let mut matches = Vec::new();
         while let Ok(Some(matche)) = self.matcher.find_at(mat.bytes(), at) {
             assert_eq!(mat.bytes()[matche], mat.bytes()[matche.start()..matche.end()]);
-            matches.push((matche.start(), matche.end()));
+            matches.push(SearchMatch { start: matche.start(), end: matche.end(), query: self.query });
             at = matche.end();
         }
 
+        let mut captures = Vec::new();
+        if self.captures {
+            captures = self.matcher.capture_groups(mat.bytes(), 0);
+            captures.truncate(MAX_CAPTURE_GROUPS);
+        }
+
         let bytes = mat.bytes().to_vec();
         let result = SearchResultEntry {
             line_number: mat.line_number().unwrap(),
+            offset: mat.absolute_byte_offset(),
             bytes,
             matches,
+            kind: EntryKind::Match,
+            context_break: std::mem::take(&mut self.context_break),
+            score: 0,
+            captures,
         };
 
         self.results.push(result);
@@ -68,32 +240,95 @@ impl searcher::Sink for SearchSink<'_, '_> {
         // Continue search
         Ok(true)
     }
+
+    fn context(&mut self, _searcher: &Searcher, context: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        match self.keep_line(context.line_number().unwrap()) {
+            Some(true) => {}
+            Some(false) => return Ok(true),
+            None => return Ok(false),
+        }
+
+        let result = SearchResultEntry {
+            line_number: context.line_number().unwrap(),
+            offset: context.absolute_byte_offset(),
+            bytes: context.bytes().to_vec(),
+            matches: Vec::new(),
+            kind: EntryKind::Context,
+            context_break: std::mem::take(&mut self.context_break),
+            score: 0,
+            captures: Vec::new(),
+        };
+
+        self.results.push(result);
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        self.context_break = true;
+        Ok(true)
+    }
 }
 
 pub struct PendingSearch {
     rx: mpsc::Receiver<SearchResult>,
     quit: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
     start_time: Instant,
+    paused_time: Option<Instant>,
+    paused_duration: Duration,
 }
 
 impl PendingSearch {
     pub fn new(rx: mpsc::Receiver<SearchResult>) -> Self {
         let quit = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
         let start_time = Instant::now();
-        Self { rx, quit, start_time: start_time }
+        Self {
+            rx,
+            quit,
+            paused,
+            start_time: start_time,
+            paused_time: None,
+            paused_duration: Duration::from_secs(0),
+        }
     }
 
     pub fn signal_stop(&self) {
         self.quit.store(true, Ordering::Relaxed);
     }
 
+    pub fn pause(&mut self) {
+        if self.paused_time.is_none() {
+            self.paused_time = Some(Instant::now());
+            self.paused.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_time) = self.paused_time.take() {
+            self.paused_duration += paused_time.elapsed();
+            self.paused.store(false, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_time.is_some()
+    }
+
+    /// Time spent searching, excluding the time during which the search was paused.
     pub fn elapsed(&self) -> Duration {
-        self.start_time.elapsed()
+        let end_time = self.paused_time.unwrap_or_else(Instant::now);
+        end_time.duration_since(self.start_time).saturating_sub(self.paused_duration)
     }
 
     pub fn try_recv(&self) -> std::result::Result<SearchResult, TryRecvError> {
         self.rx.try_recv()
     }
+
+    /// Wait for the next result, fails once the search is done.
+    pub fn recv(&self) -> std::result::Result<SearchResult, mpsc::RecvError> {
+        self.rx.recv()
+    }
 }
 
 impl Drop for PendingSearch {
@@ -104,56 +339,565 @@ impl Drop for PendingSearch {
 
 #[derive(Clone)]
 pub struct SearchWorker {
-    matcher: RegexMatcher,
+    matcher: QueryMatcher,
     searcher: Searcher,
+    query: usize,
+    line_range: LineRange,
+    /// Set when the file names are matched fuzzily, in which case `matcher` isn't used for them.
+    fuzzy: Option<FuzzyPattern>,
+    /// Whether the capture groups of the matches are extracted.
+    captures: bool,
+    /// Largest number of lines of a paragraph, when only the paragraphs of the matches are kept.
+    paragraph_lines: Option<usize>,
 }
 
 impl SearchWorker {
-    pub fn search_path(&mut self, dir_entry: ignore::DirEntry, search_binary: bool) -> Option<SearchResult> {
+    fn search_path(&mut self, path: &Path, bin_detection: BinaryDetection) -> Result<Vec<SearchResultEntry>, String> {
         let mut entries = Vec::new();
         let search_sink = SearchSink {
             results: &mut entries,
             matcher: &self.matcher,
+            query: self.query,
+            line_range: self.line_range,
+            context_break: false,
+            captures: self.captures,
         };
 
-        let bin_detection = if search_binary {
-            BinaryDetection::none()
-        } else {
-            if dir_entry.depth() == 0 {
-                // If the depth of the entry is 0, it means the file was specified
-                // explicitly. So, we don't exclude this file if we detect it to be
-                // a binary.
-                BinaryDetection::convert(b'\x00')
-            } else {
-                BinaryDetection::quit(b'\x00')
-            }
+        self.searcher.set_binary_detection(bin_detection);
+        if let Err(err) = self.searcher.search_path(&self.matcher, path, search_sink) {
+            log!("Failed to search in path '{:?}', error: {:?}", path, err);
+            return Err(err.to_string());
+        }
+
+        return Ok(entries);
+    }
+
+    fn search_slice(&mut self, bytes: &[u8], bin_detection: BinaryDetection) -> Result<Vec<SearchResultEntry>, String> {
+        let mut entries = Vec::new();
+        let search_sink = SearchSink {
+            results: &mut entries,
+            matcher: &self.matcher,
+            query: self.query,
+            line_range: self.line_range,
+            context_break: false,
+            captures: self.captures,
         };
 
         self.searcher.set_binary_detection(bin_detection);
+        if let Err(err) = self.searcher.search_slice(&self.matcher, bytes, search_sink) {
+            return Err(err.to_string());
+        }
 
-        let path = dir_entry.into_path();
-        if let Err(err) = self.searcher.search_path(&self.matcher, &path, search_sink) {
-            println!("Failed to search in path '{:?}', error: {:?}", path, err);
-            return None;
+        return Ok(entries);
+    }
+}
+
+/// How the results of multiple queries are combined for a single file.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum QueryCombination {
+    /// The file is a result only if every query matched somewhere in it.
+    #[default]
+    All,
+    /// The file is a result if any query matched in it.
+    Any,
+}
+
+/// Which symbolic links are followed while walking the directories.
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum SymlinkMode {
+    /// Links are skipped, only the files they point to in the searched paths are searched.
+    #[default]
+    NoFollow,
+    /// Links to files are searched, but links to directories are not walked, which can't
+    /// lead to cycles.
+    FollowFiles,
+    /// Every link is followed, the walker skips the links leading to a cycle.
+    FollowAll,
+}
+
+impl SymlinkMode {
+    pub const NAMES: [&'static str; 3] = ["Don't follow", "Follow files only", "Follow files and directories"];
+    pub const ALL: [SymlinkMode; 3] = [Self::NoFollow, Self::FollowFiles, Self::FollowAll];
+}
+
+/// Whether the entry found by the walker is searched. With `follow_links` enabled on the
+/// walker, the links are already resolved and `FollowAll` searches every file.
+fn is_searched_entry(entry: &ignore::DirEntry, symlink_mode: SymlinkMode) -> bool {
+    let file_type = match entry.file_type() {
+        Some(file_type) => file_type,
+        None => return false,
+    };
+
+    if file_type.is_file() {
+        return true;
+    }
+
+    if symlink_mode == SymlinkMode::FollowFiles && entry.path_is_symlink() {
+        // The link isn't resolved by the walker, check what it points to.
+        return std::fs::metadata(entry.path()).is_ok_and(|metadata| metadata.is_file());
+    }
+
+    return false;
+}
+
+/// How the files containing a NUL byte, which are likely binary, are searched.
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum BinaryMode {
+    /// Stop searching the file at the first NUL byte.
+    #[default]
+    Skip,
+    /// Replace the NUL bytes with line terminators, such that the lines stay short.
+    Convert,
+    /// Search the file as if it was text.
+    Search,
+}
+
+impl BinaryMode {
+    pub const NAMES: [&'static str; 3] = ["Skip", "Convert NUL to new lines", "Search as text"];
+    pub const ALL: [BinaryMode; 3] = [Self::Skip, Self::Convert, Self::Search];
+
+    /// Files specified explicitly are never skipped, like ripgrep does.
+    pub fn detection(self, explicit: bool) -> BinaryDetection {
+        match self {
+            Self::Skip if explicit => BinaryDetection::convert(b'\x00'),
+            Self::Skip => BinaryDetection::quit(b'\x00'),
+            Self::Convert => BinaryDetection::convert(b'\x00'),
+            Self::Search => BinaryDetection::none(),
+        }
+    }
+}
+
+/// What the queries are matched against.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum SearchMode {
+    #[default]
+    Contents,
+    FileNames,
+    /// A file is listed if either its name or its content match.
+    Both,
+}
+
+/// Match the queries against the file name, returning an entry if the file name
+/// matches according to `combination`.
+fn match_file_name(workers: &[SearchWorker], combination: QueryCombination, path: &Path) -> Option<SearchResultEntry> {
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+
+    let mut matched_count = 0;
+    let mut matches = Vec::new();
+    let mut score = 0;
+    for worker in workers.iter() {
+        let mut found = false;
+        if let Some(pattern) = &worker.fuzzy {
+            if let Some(fuzzy) = pattern.find(&file_name) {
+                found = true;
+                if !worker.searcher.invert_match() {
+                    score += fuzzy.score;
+                    matches.extend(fuzzy.ranges.into_iter().map(|(start, end)| SearchMatch { start, end, query: worker.query }));
+                }
+            }
+        } else {
+            let _ = worker.matcher.find_iter(file_name.as_bytes(), |matche| {
+                found = true;
+                if !worker.searcher.invert_match() {
+                    matches.push(SearchMatch { start: matche.start(), end: matche.end(), query: worker.query });
+                }
+                true
+            });
+        }
+
+        if found != worker.searcher.invert_match() {
+            matched_count += 1;
+        }
+    }
+
+    let matched = match combination {
+        QueryCombination::All => matched_count == workers.len(),
+        QueryCombination::Any => matched_count != 0,
+    };
+
+    if !matched {
+        return None;
+    }
+
+    return Some(SearchResultEntry {
+        line_number: 0,
+        offset: 0,
+        bytes: file_name.into_bytes(),
+        matches: fuse_matches(matches),
+        kind: EntryKind::FileName,
+        context_break: false,
+        score,
+        captures: Vec::new(),
+    });
+}
+
+/// Merge the entries found by each query into a single list ordered by line
+/// numbers. Entries from different queries on the same line are merged into
+/// a single entry containing the matches of every query.
+fn combine_entries(per_query: Vec<Vec<SearchResultEntry>>, combination: QueryCombination) -> Vec<SearchResultEntry> {
+    if combination == QueryCombination::All && per_query.iter().any(|entries| entries.is_empty()) {
+        return Vec::new();
+    }
+
+    let mut lines: BTreeMap<u64, SearchResultEntry> = BTreeMap::new();
+    for entry in per_query.into_iter().flatten() {
+        if let Some(line) = lines.get_mut(&entry.line_number) {
+            line.matches.extend(entry.matches);
+            if entry.kind == EntryKind::Match {
+                line.kind = EntryKind::Match;
+            }
+            line.context_break |= entry.context_break;
+            if line.captures.is_empty() {
+                line.captures = entry.captures;
+            }
+        } else {
+            lines.insert(entry.line_number, entry);
+        }
+    }
+
+    let mut entries: Vec<SearchResultEntry> = lines.into_values().collect();
+    for entry in entries.iter_mut() {
+        entry.matches = fuse_matches(std::mem::take(&mut entry.matches));
+    }
+
+    return entries;
+}
+
+/// Sort the matches and fuse the overlapping ones. Matches of different queries can
+/// overlap, but they are drawn one after the other. The fused match is attributed to
+/// the query that matched first.
+fn fuse_matches(mut matches: Vec<SearchMatch>) -> Vec<SearchMatch> {
+    matches.sort();
+
+    let mut merged: Vec<SearchMatch> = Vec::with_capacity(matches.len());
+    for matche in matches.into_iter() {
+        match merged.last_mut() {
+            Some(last) if matche.start < last.end => last.end = std::cmp::max(last.end, matche.end),
+            _ => merged.push(matche),
+        }
+    }
+
+    return merged;
+}
+
+/// Find the matches of the queries in lines that were already searched, which is
+/// much faster than searching the files again.
+pub struct Highlighter {
+    matchers: Vec<(usize, QueryMatcher)>,
+}
+
+impl Highlighter {
+    /// Fails if any query doesn't compile. Empty and inverted queries don't highlight anything.
+    pub fn new(queries: &[SearchQuery]) -> Result<Self> {
+        let mut matchers = Vec::with_capacity(queries.len());
+        for (idx, query) in queries.iter().enumerate() {
+            if !query.query.is_empty() && !query.invert_match {
+                matchers.push((idx, query.matcher()?));
+            }
+        }
+
+        return Ok(Self { matchers });
+    }
+
+    pub fn find(&self, bytes: &[u8]) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        for (query, matcher) in self.matchers.iter() {
+            let _ = matcher.find_iter(bytes, |matche| {
+                matches.push(SearchMatch { start: matche.start(), end: matche.end(), query: *query });
+                true
+            });
+        }
+
+        return fuse_matches(matches);
+    }
+}
+
+/// Search a file with every worker and combine their results.
+pub fn search_file(
+    workers: &mut [SearchWorker],
+    combination: QueryCombination,
+    mode: SearchMode,
+    root: &Path,
+    dir_entry: ignore::DirEntry,
+    binary_mode: BinaryMode,
+) -> SearchResult {
+    // If the depth of the entry is 0, it means the file was specified explicitly.
+    let bin_detection = binary_mode.detection(dir_entry.depth() == 0);
+
+    let path = dir_entry.into_path();
+
+    let mut entries = Vec::new();
+    if mode != SearchMode::Contents {
+        entries.extend(match_file_name(workers, combination, &path));
+    }
+
+    let mut error = None;
+    if mode != SearchMode::FileNames {
+        let found = search_with_workers(workers, combination, |worker| {
+            worker.search_path(&path, bin_detection.clone())
+        });
+
+        match found {
+            Ok(found) => entries.extend(found),
+            Err(err) => error = Some(err),
         }
+    }
+
+    // Read by the thread searching the file, rather than when the results are received.
+    let modified = if entries.is_empty() { None } else { std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok() };
+
+    return SearchResult {
+        path,
+        root: root.to_path_buf(),
+        entries,
+        error,
+        walk_duration: Duration::ZERO,
+        search_duration: Duration::ZERO,
+        modified,
+    };
+}
+
+/// Path of the result of the input piped to the application, there is no file to open.
+pub const STDIN_PATH: &str = "<stdin>";
+
+/// Search the input piped to the application as if it was a single file. File names
+/// aren't matched, as it has none.
+pub fn search_stdin(workers: &mut [SearchWorker], combination: QueryCombination, bytes: &[u8], binary_mode: BinaryMode) -> SearchResult {
+    let started = Instant::now();
+    // The input is given explicitly, like a path on the command line.
+    let bin_detection = binary_mode.detection(true);
+
+    let found = search_with_workers(workers, combination, |worker| {
+        worker.search_slice(bytes, bin_detection.clone())
+    });
+
+    let (entries, error) = match found {
+        Ok(entries) => (entries, None),
+        Err(err) => (Vec::new(), Some(err)),
+    };
+
+    return SearchResult {
+        path: PathBuf::from(STDIN_PATH),
+        root: PathBuf::new(),
+        entries,
+        error,
+        walk_duration: Duration::ZERO,
+        search_duration: started.elapsed(),
+        modified: None,
+    };
+}
+
+/// Search the piped input on another thread, such that it's received like the results of
+/// `spawn_search`.
+pub fn spawn_stdin_search(config: &SearchConfig, bytes: Arc<Vec<u8>>, binary_mode: BinaryMode) -> Result<PendingSearch> {
+    let (tx, rx) = mpsc::channel();
+    let pending_search = PendingSearch::new(rx);
+
+    let mut workers = config.workers();
+    if workers.is_empty() {
+        bail!("No workers, search is not possible");
+    }
+
+    let combination = config.combination;
+    std::thread::spawn(move || {
+        let _ = tx.send(search_stdin(&mut workers, combination, &bytes, binary_mode));
+    });
+
+    return Ok(pending_search);
+}
 
-        let result = SearchResult {
+/// Search every file contained in a zip archive, returning a result per file.
+#[cfg(feature = "zip")]
+fn search_archive(
+    workers: &mut [SearchWorker],
+    combination: QueryCombination,
+    mode: SearchMode,
+    root: &Path,
+    path: &Path,
+    binary_mode: BinaryMode,
+) -> Vec<SearchResult> {
+    use std::io::Read;
+
+    // Entries of an archive are never specified explicitly.
+    let bin_detection = binary_mode.detection(false);
+
+    let file_error = |path: PathBuf, error: String| {
+        log!("Failed to search in archive '{}', error: {}", path.to_string_lossy(), error);
+        SearchResult {
             path,
-            entries: entries,
+            root: root.to_path_buf(),
+            entries: Vec::new(),
+            error: Some(error),
+            walk_duration: Duration::ZERO,
+            search_duration: Duration::ZERO,
+            modified: None,
+        }
+    };
+
+    let mut archive = match std::fs::File::open(path).map_err(|err| err.to_string()).and_then(|file| {
+        zip::ZipArchive::new(file).map_err(|err| err.to_string())
+    }) {
+        Ok(archive) => archive,
+        Err(err) => return vec![file_error(path.to_path_buf(), err)],
+    };
+
+    let mut results = Vec::new();
+    for idx in 0..archive.len() {
+        let mut file = match archive.by_index(idx) {
+            Ok(file) => file,
+            Err(err) => {
+                results.push(file_error(path.to_path_buf(), err.to_string()));
+                continue;
+            }
+        };
+
+        if file.is_dir() {
+            continue;
+        }
+
+        let entry_path = crate::archive::entry_path(path, file.name());
+
+        let mut entries = Vec::new();
+        if mode != SearchMode::Contents {
+            entries.extend(match_file_name(workers, combination, &entry_path));
+        }
+
+        if mode != SearchMode::FileNames {
+            let mut bytes = Vec::new();
+            let found = file.read_to_end(&mut bytes).map_err(|err| err.to_string()).and_then(|_| {
+                search_with_workers(workers, combination, |worker| {
+                    worker.search_slice(&bytes, bin_detection.clone())
+                })
+            });
+
+            match found {
+                Ok(found) => entries.extend(found),
+                Err(err) => {
+                    results.push(file_error(entry_path, err));
+                    continue;
+                }
+            }
+        }
+
+        results.push(SearchResult {
+            path: entry_path,
+            root: root.to_path_buf(),
+            entries,
+            error: None,
+            walk_duration: Duration::ZERO,
+            search_duration: Duration::ZERO,
+            modified: None,
+        });
+    }
+
+    return results;
+}
+
+fn search_with_workers<F>(
+    workers: &mut [SearchWorker],
+    combination: QueryCombination,
+    mut search: F,
+) -> Result<Vec<SearchResultEntry>, String>
+where
+    F: FnMut(&mut SearchWorker) -> Result<Vec<SearchResultEntry>, String>,
+{
+    let mut per_query = Vec::with_capacity(workers.len());
+    for worker in workers.iter_mut() {
+        let entries = search(worker)?;
+
+        // No need to search with the other queries if we already know the file is excluded.
+        if entries.is_empty() && combination == QueryCombination::All {
+            return Ok(Vec::new());
+        }
+
+        per_query.push(entries);
+    }
+
+    let entries = combine_entries(per_query, combination);
+    return match workers.first().and_then(|worker| worker.paragraph_lines) {
+        Some(max_lines) => Ok(keep_paragraphs(entries, max_lines)),
+        None => Ok(entries),
+    };
+}
+
+fn is_blank_line(bytes: &[u8]) -> bool {
+    trim_line_terminator(bytes).iter().all(|value| value.is_ascii_whitespace())
+}
+
+/// Keep the paragraphs, lines delimited by blank lines, containing a match. The entries must
+/// have enough context lines to fill the paragraphs. Paragraphs longer than `max_lines` are
+/// cut around their first match.
+fn keep_paragraphs(entries: Vec<SearchResultEntry>, max_lines: usize) -> Vec<SearchResultEntry> {
+    let max_lines = max_lines.max(1);
+
+    // Consecutive lines that aren't blank, the blank lines are dropped.
+    let mut paragraphs: Vec<Vec<SearchResultEntry>> = Vec::new();
+    let mut last_line = None;
+    for entry in entries.into_iter() {
+        if is_blank_line(&entry.bytes) {
+            last_line = None;
+            continue;
+        }
+
+        let follows = last_line.is_some_and(|line| line + 1 == entry.line_number);
+        last_line = Some(entry.line_number);
+        match paragraphs.last_mut() {
+            Some(paragraph) if follows => paragraph.push(entry),
+            _ => paragraphs.push(vec![entry]),
+        }
+    }
+
+    let mut kept = Vec::new();
+    for mut paragraph in paragraphs.into_iter() {
+        let first_match = match paragraph.iter().position(|entry| entry.kind == EntryKind::Match) {
+            Some(first_match) => first_match,
+            None => continue,
         };
 
-        return Some(result);
+        let start = first_match.saturating_sub(max_lines / 2).min(paragraph.len().saturating_sub(max_lines));
+        paragraph.truncate(start + max_lines);
+        for (idx, mut entry) in paragraph.into_iter().skip(start).enumerate() {
+            // Lines were skipped between the paragraphs, at least the blank line.
+            entry.context_break = idx == 0 && !kept.is_empty();
+            kept.push(entry);
+        }
+    }
+
+    return kept;
+}
+
+/// Lines of a file in which the matches are kept, the bounds are inclusive and `None`
+/// means unbounded.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct LineRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl LineRange {
+    pub fn contains(&self, line_number: u64) -> bool {
+        return line_number >= self.min.unwrap_or(0) && line_number <= self.max.unwrap_or(u64::MAX);
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SearchQuery {
     pub query: String,
     pub regex_syntax: bool,
+    /// Whether the regex is matched with `fancy_regex`, which supports lookaround and
+    /// backreferences like PCRE. Only used with the regex syntax.
+    pub pcre: bool,
+    /// Whether the query is a whitespace separated list of literals, any of which matches.
+    /// Only used without the regex syntax.
+    pub any_literal: bool,
     pub ignore_case: bool,
     pub invert_match: bool,
     pub before_context: usize,
     pub after_context: usize,
+    /// Only the matches and context lines in this range are kept.
+    pub line_range: LineRange,
+    /// Result of the last call to `check_syntax`, kept to avoid re-compiling the regex every frame.
+    pub syntax_error: Option<String>,
 }
 
 impl SearchQuery {
@@ -161,14 +905,57 @@ impl SearchQuery {
         Self {
             query: String::new(),
             regex_syntax: false,
+            pcre: false,
+            any_literal: false,
             ignore_case: true,
             invert_match: false,
             before_context: 0,
             after_context: 0,
+            line_range: LineRange::default(),
+            syntax_error: None,
+        }
+    }
+
+    /// Options of the query as they are applied, for the log of the searches.
+    pub fn describe_options(&self) -> String {
+        let mut options = Vec::new();
+        options.push(match (self.regex_syntax, self.pcre, self.any_literal) {
+            (true, true, _) => "PCRE regex",
+            (true, false, _) => "regex",
+            (false, _, true) => "any literal",
+            (false, _, false) => "literal",
+        }.to_owned());
+        if self.ignore_case {
+            options.push(String::from("ignore case"));
+        }
+        if self.invert_match {
+            options.push(String::from("invert match"));
+        }
+        if self.before_context != 0 || self.after_context != 0 {
+            options.push(format!("context {} before, {} after", self.before_context, self.after_context));
+        }
+        if self.line_range != LineRange::default() {
+            let bound = |bound: Option<u64>| bound.map_or(String::new(), |line| line.to_string());
+            options.push(format!("lines {}-{}", bound(self.line_range.min), bound(self.line_range.max)));
+        }
+        return options.join(", ");
+    }
+
+    /// Returns the error message if the query uses the regex syntax and doesn't compile.
+    /// Literal queries are always valid.
+    pub fn check_syntax(&self) -> Option<String> {
+        if !self.regex_syntax || self.query.is_empty() {
+            return None;
         }
+
+        self.matcher().err().map(|err| err.to_string())
     }
 
-    fn matcher(&self) -> Result<RegexMatcher> {
+    fn matcher(&self) -> Result<QueryMatcher> {
+        if self.regex_syntax && self.pcre {
+            return Ok(QueryMatcher::fancy(&self.query, self.ignore_case)?);
+        }
+
         let mut builder = RegexMatcherBuilder::new();
         builder
             .case_smart(self.ignore_case)
@@ -177,21 +964,27 @@ impl SearchQuery {
             .unicode(true)
             .octal(false)
             .line_terminator(Some(b'\n'))
+            .crlf(true)
             .dot_matches_new_line(false);
 
         let matcher = if self.regex_syntax {
             builder.build(&self.query)
+        } else if self.any_literal && self.query.split_whitespace().next().is_some() {
+            let literals: Vec<String> = self.query.split_whitespace().map(regex::escape).collect();
+            builder.build_literals(&literals)
         } else {
             let escaped_query = regex::escape(&self.query);
             builder.build_literals(&[escaped_query])
         }?;
 
-        return Ok(matcher);
+        return Ok(QueryMatcher::Regex(Box::new(matcher)));
     }
 
     fn searcher(&self, line_number: bool) -> Searcher {
         let mut builder = SearcherBuilder::new();
         let searcher = builder
+            // Must match the line terminator of the matcher, which handles CRLF.
+            .line_terminator(LineTerminator::crlf())
             .invert_match(self.invert_match)
             .line_number(line_number)
             .before_context(self.before_context)
@@ -200,94 +993,445 @@ impl SearchQuery {
         return searcher;
     }
 
-    fn search_worker(&self, line_number: bool) -> Result<SearchWorker> {
+    fn search_worker(&self, line_number: bool, query: usize) -> Result<SearchWorker> {
         let matcher = self.matcher()?;
         let searcher = self.searcher(line_number);
-        return Ok(SearchWorker { matcher, searcher });
+        return Ok(SearchWorker { matcher, searcher, query, line_range: self.line_range, fuzzy: None, captures: false, paragraph_lines: None });
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct SearchConfig {
-    /// List of paths seperated by a semi-column ';'.
+/// List of globs, split like a command line such that globs containing spaces
+/// can be quoted. Arguments copied from ripgrep, `-g <glob>`, `--glob <glob>`
+/// and `--glob=<glob>`, are also accepted.
+fn parse_globs(value: &str) -> Result<Vec<String>> {
+    let mut globs = Vec::new();
+    let mut it = args::parse_args(value)?.into_iter();
+    while let Some(arg) = it.next() {
+        if arg == "-g" || arg == "--glob" {
+            if let Some(glob) = it.next() {
+                globs.push(glob);
+            } else {
+                bail!("Expected a glob after '{}'", arg);
+            }
+        } else if let Some(glob) = arg.strip_prefix("--glob=") {
+            globs.push(glob.to_owned());
+        } else {
+            globs.push(arg);
+        }
+    }
+    return Ok(globs);
+}
+
+/// Path searched with its own globs, when `SearchConfig::per_root_globs` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchRoot {
+    pub path: String,
+    /// List of globs separated by a space, same as `SearchConfig::globs`.
+    pub globs: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchConfig {
+    /// List of paths seperated by a semi-column ';'.
     pub paths: String,
 
     /// List of globs seperated by a space.
     pub globs: String,
 
+    /// Whether globs are made to match at any depth, rather than relative to the search paths.
+    pub globs_any_depth: bool,
+
+    /// Whether globs match the paths regardless of case, independently of the queries.
+    pub globs_ignore_case: bool,
+
+    /// Whether `roots` are searched, each with its own globs, rather than `paths` with `globs`.
+    pub per_root_globs: bool,
+
+    /// Paths with their own globs, only used if `per_root_globs` is set.
+    pub roots: Vec<SearchRoot>,
+
+    /// Names of directories to exclude, separated by a comma ',' or a semicolon ';'.
+    pub exclude_dirs: String,
+
+    /// Whether hidden files and directories are searched.
+    pub hidden: bool,
+
+    /// Whether only the files modified according to `git status` are searched. Roots
+    /// that aren't in a git repository are searched entirely.
+    pub only_modified: bool,
+
+    /// Whether only the files tracked by git, according to `git ls-files`, are searched. Roots
+    /// that aren't in a git repository are searched entirely.
+    pub only_tracked: bool,
+
     /// List of queries that are to be executed sequentially.
     pub queries: Vec<SearchQuery>,
+
+    /// How the results of the queries are combined.
+    pub combination: QueryCombination,
+
+    /// Whether the queries are matched against the file names, the contents or both.
+    pub mode: SearchMode,
+
+    /// Whether the file names are matched fuzzily, only used when searching the file names only.
+    pub fuzzy: bool,
+
+    /// Whether the capture groups of the regex queries are extracted from the matched lines.
+    pub captures: bool,
+
+    /// Whether the results are the paragraphs, delimited by blank lines, containing the matches
+    /// rather than the matched lines with their context.
+    pub paragraphs: bool,
+
+    /// Largest number of lines kept per paragraph, when searching the paragraphs.
+    pub max_paragraph_lines: usize,
 }
 
 impl SearchConfig {
     pub fn default() -> Self {
-        Self { paths: String::new(), globs: String::new(), queries: Vec::new() }
+        Self {
+            paths: String::new(),
+            globs: String::new(),
+            globs_any_depth: false,
+            globs_ignore_case: false,
+            per_root_globs: false,
+            roots: Vec::new(),
+            exclude_dirs: String::new(),
+            hidden: false,
+            only_modified: false,
+            only_tracked: false,
+            queries: Vec::new(),
+            combination: QueryCombination::default(),
+            mode: SearchMode::default(),
+            fuzzy: false,
+            captures: false,
+            paragraphs: false,
+            max_paragraph_lines: DEFAULT_MAX_PARAGRAPH_LINES,
+        }
     }
 
     pub fn with_paths(paths: String) -> Self {
         let queries = vec![SearchQuery::new()];
-        Self { paths, globs: String::new(), queries }
+        Self {
+            paths,
+            globs: String::new(),
+            globs_any_depth: false,
+            globs_ignore_case: false,
+            per_root_globs: false,
+            roots: Vec::new(),
+            exclude_dirs: String::new(),
+            hidden: false,
+            only_modified: false,
+            only_tracked: false,
+            queries,
+            combination: QueryCombination::default(),
+            mode: SearchMode::default(),
+            fuzzy: false,
+            captures: false,
+            paragraphs: false,
+            max_paragraph_lines: DEFAULT_MAX_PARAGRAPH_LINES,
+        }
     }
 
-    pub fn paths(&self) -> Vec<&Path> {
-        let paths: Vec<&Path> = self
-            .paths
-            .split(';')
-            .filter(|value| !value.is_empty())
-            .map(|value| Path::new(value))
+    /// Whether there is a query and every query has at least `min_len` characters.
+    /// Empty queries are ignored, because they are not searched.
+    pub fn queries_have_min_len(&self, min_len: usize) -> bool {
+        let mut queries = self.queries.iter().filter(|query| !query.query.is_empty()).peekable();
+        queries.peek().is_some() && queries.all(|query| query.query.chars().count() >= min_len)
+    }
+
+    /// Whether the file names are matched fuzzily, and ranked by the score of their match.
+    pub fn fuzzy_file_names(&self) -> bool {
+        self.fuzzy && self.mode == SearchMode::FileNames
+    }
+
+    /// Paths shown to the user, for instance as the name of the tab.
+    pub fn paths_label(&self) -> String {
+        if !self.per_root_globs {
+            return self.paths.clone();
+        }
+
+        return args::join_quoted(self.roots.iter().map(|root| root.path.as_str()), ';');
+    }
+
+    /// Paths separated by a semicolon ';', paths containing one can be quoted.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        args::split_quoted(&self.paths, ';').into_iter().map(PathBuf::from).collect()
+    }
+
+    /// Paths to walk, without the paths that are already contained in an other
+    /// path, such that no file is searched twice.
+    pub fn search_roots(&self) -> Vec<PathBuf> {
+        let paths = self.paths();
+        let canonicals: Vec<PathBuf> = paths
+            .iter()
+            .map(|path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
             .collect();
-        paths
+
+        let mut roots = Vec::with_capacity(paths.len());
+        for (idx, path) in paths.iter().enumerate() {
+            let canonical = &canonicals[idx];
+            let is_contained = canonicals.iter().enumerate().any(|(other_idx, other)| {
+                // For duplicated paths, only keep the first one.
+                if canonical == other {
+                    other_idx < idx
+                } else {
+                    canonical.starts_with(other)
+                }
+            });
+
+            if !is_contained {
+                roots.push(path.to_path_buf());
+            }
+        }
+
+        return roots;
     }
 
-    pub fn overrides(&self) -> Override {
-        if self.globs.is_empty() {
-            Override::empty()
+    /// Paths walked by the search, each with its globs as they are given to the walker, to
+    /// understand why a search doesn't find what is expected.
+    pub fn describe_roots(&self) -> Result<Vec<String>> {
+        let mut roots = Vec::new();
+        if self.per_root_globs {
+            for root in self.roots.iter().filter(|root| !root.path.is_empty()) {
+                roots.push((PathBuf::from(&root.path), parse_globs(&root.globs)?));
+            }
         } else {
-            let path = std::env::current_dir().unwrap_or(PathBuf::from("/"));
-            let mut builder = OverrideBuilder::new(path);
-            for glob in self.globs.split(' ').filter(|value| !value.is_empty()) {
-                if let Err(err) = builder.add(&glob) {
-                    println!("Failed to add glob '{}' with error: {}", glob, err);
-                }
+            let globs = self.glob_list()?;
+            roots.extend(self.search_roots().into_iter().map(|root| (root, globs.clone())));
+        }
+
+        let mut lines = Vec::with_capacity(roots.len() + 1);
+        for (root, globs) in roots.into_iter() {
+            let globs = self.effective_globs(&globs);
+            let globs = if globs.is_empty() { String::from("none") } else { globs.join(" ") };
+            lines.push(format!("Path '{}', globs: {}", root.to_string_lossy(), globs));
+        }
+
+        lines.push(format!(
+            "Mode: {:?}, hidden files: {}, only modified files: {}, only tracked files: {}, globs ignore case: {}",
+            self.mode, self.hidden, self.only_modified, self.only_tracked, self.globs_ignore_case
+        ));
+        return Ok(lines);
+    }
+
+    /// Queries of the search with their options, for the log of the searches.
+    pub fn describe_queries(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .queries
+            .iter()
+            .enumerate()
+            .map(|(idx, query)| format!("Query {}: '{}' ({})", idx + 1, query.query, query.describe_options()))
+            .collect();
+        if self.queries.len() > 1 {
+            lines.push(format!("Combination: {:?}", self.combination));
+        }
+        return lines;
+    }
+
+    pub fn glob_list(&self) -> Result<Vec<String>> {
+        return parse_globs(&self.globs);
+    }
+
+    /// Globs excluding the directories of `exclude_dirs` at any depth.
+    pub fn exclude_dir_globs(&self) -> Vec<String> {
+        self.exclude_dirs
+            .split([',', ';'])
+            .map(|name| name.trim().trim_matches(['/', '\\']))
+            .filter(|name| !name.is_empty())
+            .map(|name| format!("!**/{}/**", name))
+            .collect()
+    }
+
+    pub fn overrides(&self) -> Result<Override> {
+        return self.build_overrides(&self.glob_list()?);
+    }
+
+    /// Paths to walk, each with the overrides filtering its files.
+    pub fn walk_roots(&self) -> Result<Vec<(PathBuf, Override)>> {
+        if !self.per_root_globs {
+            let overrides = self.overrides()?;
+            return Ok(self.search_roots().into_iter().map(|root| (root, overrides.clone())).collect());
+        }
+
+        // Paths can overlap with different globs, so none of them is skipped.
+        let mut roots = Vec::with_capacity(self.roots.len());
+        for root in self.roots.iter().filter(|root| !root.path.is_empty()) {
+            let overrides = parse_globs(&root.globs)
+                .and_then(|globs| self.build_overrides(&globs))
+                .map_err(|err| anyhow!("{} (path '{}')", err, root.path))?;
+            roots.push((PathBuf::from(&root.path), overrides));
+        }
+
+        return Ok(roots);
+    }
+
+    /// Globs given to the walker for `globs`, as rewritten by the options, followed by the
+    /// excluded directories, which then take precedence over the globs.
+    fn effective_globs(&self, globs: &[String]) -> Vec<String> {
+        let mut effective: Vec<String> = globs
+            .iter()
+            .map(|glob| if self.globs_any_depth { any_depth_glob(glob) } else { glob.clone() })
+            .collect();
+        effective.extend(self.exclude_dir_globs());
+        return effective;
+    }
+
+    fn build_overrides(&self, globs: &[String]) -> Result<Override> {
+        let globs = self.effective_globs(globs);
+        if globs.is_empty() {
+            return Ok(Override::empty());
+        }
+
+        let path = std::env::current_dir().unwrap_or(PathBuf::from("/"));
+        let mut builder = OverrideBuilder::new(path);
+        // Must be set before adding the globs, which are compiled when added.
+        builder.case_insensitive(self.globs_ignore_case)?;
+
+        // Report every invalid glob at once, rather than only the first one.
+        let mut errors = Vec::new();
+        for glob in globs.iter() {
+            if let Err(err) = builder.add(glob) {
+                errors.push(format!("'{}': {}", glob, err));
             }
+        }
 
-            builder.build().unwrap_or(Override::empty())
+        if !errors.is_empty() {
+            bail!("Invalid glob {}", errors.join(", "));
         }
+
+        return Ok(builder.build()?);
     }
 
     pub fn workers(&self) -> Vec<SearchWorker> {
         let mut workers = Vec::with_capacity(self.queries.len());
+        for (idx, query) in self.queries.iter().enumerate().filter(|(_, query)| !query.query.is_empty()) {
+            // The paragraphs are found from the context lines, enough of them are needed to fill them.
+            let mut query = query.clone();
+            if self.paragraphs {
+                query.before_context = self.max_paragraph_lines;
+                query.after_context = self.max_paragraph_lines;
+            }
 
-        let mut it = self.queries.iter().filter(|query| !query.query.is_empty());
-
-        // We need at least 1 worker which find the line numbers
-        if let Some(worker) = it.next() {
-            if let Ok(worker) = worker.search_worker(true) {
+            // Every worker needs the line numbers to combine their results.
+            if let Ok(mut worker) = query.search_worker(true, idx) {
+                if self.fuzzy_file_names() {
+                    worker.fuzzy = Some(FuzzyPattern::new(&query.query, query.ignore_case));
+                }
+                worker.captures = self.captures && query.regex_syntax && !query.invert_match;
+                worker.paragraph_lines = self.paragraphs.then_some(self.max_paragraph_lines);
                 workers.push(worker);
             } else {
-                println!("Couldn't build the workers");
-                return workers;
+                log!("Failed to create a worker for query '{}'", query.query);
             }
-        } else {
-            return workers;
         }
 
-        while let Some(query) = it.next() {
-            if let Ok(worker) = query.search_worker(false) {
-                workers.push(worker);
-            } else {
-                println!("Failed to create a worker for query '{}'", query.query);
+        return workers;
+    }
+}
+
+/// Rewrite the glob such that it matches at any depth, for instance `src/*.rs`
+/// becomes `**/src/*.rs`. Globs that are already anchored are kept as is.
+fn any_depth_glob(glob: &str) -> String {
+    let (negation, pattern) = match glob.strip_prefix('!') {
+        Some(pattern) => ("!", pattern),
+        None => ("", glob),
+    };
+
+    if pattern.starts_with('/') || pattern.starts_with("**/") {
+        return glob.to_owned();
+    }
+
+    return format!("{}**/{}", negation, pattern);
+}
+
+fn available_threads() -> usize {
+    thread::available_parallelism().map(|value| value.get()).unwrap_or(2)
+}
+
+/// Largest number of threads that can be configured.
+pub fn max_thread_count() -> i32 {
+    (available_threads() * 4) as i32
+}
+
+/// Number of threads to use for the configured value, where 0, or any negative
+/// value, means to use as many threads as there are cores.
+pub fn thread_count(number_of_threads: i32) -> usize {
+    if number_of_threads <= 0 {
+        return available_threads();
+    }
+
+    return std::cmp::min(number_of_threads as usize, available_threads() * 4);
+}
+
+/// Entry found by the walker, with the root it was found from.
+struct SearchedEntry<'a> {
+    root: &'a Path,
+    entry: ignore::DirEntry,
+    /// Time spent walking the directories since the last entry was searched.
+    walk_duration: Duration,
+}
+
+/// Returns false once the search is stopped, after waiting while it's paused.
+fn wait_while_paused(paused: &AtomicBool, quit: &AtomicBool) -> bool {
+    while paused.load(Ordering::Relaxed) && !quit.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    return !quit.load(Ordering::Relaxed);
+}
+
+/// Everything needed to search the entries found by the walker, each thread has its own copy.
+#[derive(Clone)]
+struct EntrySearcher {
+    workers: Vec<SearchWorker>,
+    combination: QueryCombination,
+    mode: SearchMode,
+    binary_mode: BinaryMode,
+    symlink_mode: SymlinkMode,
+    search_archives: bool,
+    tx: mpsc::Sender<SearchResult>,
+}
+
+impl EntrySearcher {
+    /// Search an entry found by the walker and send its results. Returns false once the results
+    /// aren't received anymore.
+    fn search(&mut self, searched: SearchedEntry) -> bool {
+        let SearchedEntry { root, entry, walk_duration } = searched;
+        if !is_searched_entry(&entry, self.symlink_mode) {
+            return true;
+        }
+
+        // Archives can only be searched when built with the "zip" feature.
+        #[cfg(not(feature = "zip"))]
+        let _ = self.search_archives;
+
+        #[cfg(feature = "zip")]
+        if self.search_archives && crate::archive::is_archive(entry.path()) {
+            let started = Instant::now();
+            let mut results = search_archive(&mut self.workers, self.combination, self.mode, root, entry.path(), self.binary_mode);
+            if let Some(first) = results.first_mut() {
+                first.walk_duration = walk_duration;
+                first.search_duration = started.elapsed();
             }
+
+            return results.into_iter().all(|result| self.tx.send(result).is_ok());
         }
 
-        return workers;
+        let started = Instant::now();
+        let mut result = search_file(&mut self.workers, self.combination, self.mode, root, entry, self.binary_mode);
+        result.walk_duration = walk_duration;
+        result.search_duration = started.elapsed();
+        return self.tx.send(result).is_ok();
     }
 }
 
 pub fn spawn_search(
     config: &SearchConfig,
-    search_binary: bool,
-    number_of_threads: usize,
+    binary_mode: BinaryMode,
+    symlink_mode: SymlinkMode,
+    search_archives: bool,
+    number_of_threads: i32,
 ) -> Result<PendingSearch> {
     let (tx, rx) = mpsc::channel();
     let pending_search = PendingSearch::new(rx);
@@ -297,64 +1441,795 @@ pub fn spawn_search(
         bail!("No workers, search is not possible");
     }
 
-    let mut builder = if let Some((first, remaining)) = config.paths().split_first() {
-        let mut builder = WalkBuilder::new(first);
-        for path in remaining {
-            builder.add(path);
-        }
-        builder
-    } else {
+    let roots = config.walk_roots()?;
+    if roots.is_empty() {
         bail!("Can't search with no path");
-    };
-
-    builder.overrides(config.overrides());
+    }
 
-    let threads = if number_of_threads == 0 {
-        thread::available_parallelism().map(|value| value.get()).unwrap_or(2)
-    } else {
-        number_of_threads as usize
+    let searcher = EntrySearcher {
+        workers,
+        combination: config.combination,
+        mode: config.mode,
+        binary_mode,
+        symlink_mode,
+        search_archives,
+        tx,
     };
+    let hidden = config.hidden;
+    let only_modified = config.only_modified;
+    let only_tracked = config.only_tracked;
 
-    let walker = builder.threads(threads).build_parallel();
+    let threads = thread_count(number_of_threads);
 
     let quit = pending_search.quit.clone();
-    std::thread::spawn(move || {
-        walker.run(|| {
-            let tx = tx.clone();
-            let quit = quit.clone();
-
-            let mut workers = workers.clone();
+    let paused = pending_search.paused.clone();
 
-            Box::new(move |result| {
-                if quit.load(Ordering::Relaxed) {
-                    return WalkState::Quit;
+    // Searching a single file is common, such as when a file is dropped on the window. A file
+    // has nothing to walk, so the parallel walker isn't worth starting its threads. Several
+    // files are still searched by the parallel walker, which searches them concurrently.
+    if !only_modified && !only_tracked && roots.len() == 1 && roots[0].0.is_file() {
+        std::thread::spawn(move || {
+            let mut searcher = searcher;
+            for (root, _) in roots.iter() {
+                let walk_started = Instant::now();
+                if !wait_while_paused(&paused, &quit) {
+                    break;
                 }
 
-                let entry = if let Ok(entry) = result {
-                    entry
-                } else {
-                    return WalkState::Continue;
+                // The walker of a file only yields the file, which it doesn't filter like the files given explicitly.
+                let entry = match WalkBuilder::new(root).follow_links(symlink_mode == SymlinkMode::FollowAll).build().next() {
+                    Some(Ok(entry)) => entry,
+                    _ => continue,
                 };
 
-                if let Some(file_type) = entry.file_type() {
-                    if !file_type.is_file() {
-                        return WalkState::Continue;
+                let searched = SearchedEntry { root, entry, walk_duration: walk_started.elapsed() };
+                if !searcher.search(searched) {
+                    break;
+                }
+            }
+        });
+        return Ok(pending_search);
+    }
+
+    std::thread::spawn(move || {
+        // Each root is walked separately, such that every result knows which
+        // root it was found from. The walker doesn't give that information and
+        // guessing from the path prefix is ambiguous when the roots overlap.
+        for (root, overrides) in roots.iter() {
+            if quit.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let mut builder = WalkBuilder::new(root);
+            // The modified files include the untracked ones, so they take precedence over the tracked files.
+            let git_files = if only_modified {
+                Some((crate::git::modified_files(root), "modified"))
+            } else if only_tracked {
+                Some((crate::git::tracked_files(root), "tracked"))
+            } else {
+                None
+            };
+            if let Some((git_files, kind)) = git_files {
+                match git_files {
+                    Ok(files) => {
+                        // Files given explicitly are never filtered by the walker, so the globs are applied here.
+                        let mut files = files.into_iter().filter(|file| !overrides.matched(file, false).is_ignore());
+                        match files.next() {
+                            Some(first) => builder = WalkBuilder::new(first),
+                            None => continue,
+                        }
+                        for file in files {
+                            builder.add(file);
+                        }
                     }
-                } else {
-                    return WalkState::Continue;
-                };
+                    Err(err) => {
+                        log!("Can't list the {} files of '{}', searching every file, error: {}", kind, root.to_string_lossy(), err);
+                    }
+                }
+            }
 
-                if let Some(result) = workers[0].search_path(entry, search_binary) {
-                    return match tx.send(result) {
-                        Ok(_) => WalkState::Continue,
-                        Err(_) => WalkState::Quit,
+            let walker = builder
+                .overrides(overrides.clone())
+                .follow_links(symlink_mode == SymlinkMode::FollowAll)
+                .hidden(!hidden)
+                .threads(threads)
+                .build_parallel();
+
+            walker.run(|| {
+                let quit = quit.clone();
+                let paused = paused.clone();
+
+                let mut searcher = searcher.clone();
+                let mut last_searched = Instant::now();
+
+                Box::new(move |result| {
+                    // Everything done by this thread since the last search is spent walking the directories.
+                    let walk_duration = last_searched.elapsed();
+
+                    if !wait_while_paused(&paused, &quit) {
+                        return WalkState::Quit;
+                    }
+
+                    let entry = if let Ok(entry) = result {
+                        entry
+                    } else {
+                        return WalkState::Continue;
                     };
-                } else {
-                    return WalkState::Continue;
-                };
-            })
-        });
+
+                    let searched = SearchedEntry { root, entry, walk_duration };
+                    let keep_going = searcher.search(searched);
+                    last_searched = Instant::now();
+
+                    return if keep_going { WalkState::Continue } else { WalkState::Quit };
+                })
+            });
+        }
     });
 
     return Ok(pending_search);
 }
+
+/// Search on the calling thread, calling `on_result` for every file searched.
+pub fn search_blocking(
+    config: &SearchConfig,
+    binary_mode: BinaryMode,
+    symlink_mode: SymlinkMode,
+    search_archives: bool,
+    number_of_threads: i32,
+    mut on_result: impl FnMut(SearchResult),
+) -> Result<()> {
+    let pending = spawn_search(config, binary_mode, symlink_mode, search_archives, number_of_threads)?;
+    while let Ok(result) = pending.recv() {
+        on_result(result);
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"fn main() {\n    println!(\"hello\");\n}\n";
+
+    #[test]
+    fn display_range_of_long_lines() {
+        assert_eq!(display_range(b"abcdef\r\n", 0, 0), 0..6);
+        assert_eq!(display_range(b"abcdef\n", 0, 10), 0..6);
+        assert_eq!(display_range(b"abcdefghij", 8, 4), 6..10);
+        assert_eq!(display_range(b"abcdefghij", 5, 4), 3..7);
+
+        // Two bytes per character, the range is moved to the character boundaries.
+        let line = "\u{e9}\u{e9}\u{e9}\u{e9}".as_bytes();
+        assert_eq!(display_range(line, 0, 3), 0..2);
+        assert_eq!(display_range(line, 5, 3), 4..6);
+
+        // Only continuation bytes, there is no boundary to move to.
+        assert_eq!(display_range(b"\xB5\xB5\xB5", 0, 2), 0..2);
+        assert_eq!(display_range(&[0x80; 3000], 2500, 2000), 1000..3000);
+        assert_eq!(display_range(&[0x80; 3000], 0, 1), 0..1);
+    }
+
+    fn query(text: &str) -> SearchQuery {
+        let mut query = SearchQuery::new();
+        query.query = String::from(text);
+        query
+    }
+
+    fn search_text(text: &[u8], queries: Vec<SearchQuery>, combination: QueryCombination) -> Vec<SearchResultEntry> {
+        let config = SearchConfig {
+            paths: String::new(),
+            globs: String::new(),
+            globs_any_depth: false,
+            globs_ignore_case: false,
+            per_root_globs: false,
+            roots: Vec::new(),
+            exclude_dirs: String::new(),
+            hidden: false,
+            only_modified: false,
+            only_tracked: false,
+            queries,
+            combination,
+            mode: SearchMode::Contents,
+            fuzzy: false,
+            captures: false,
+            paragraphs: false,
+            max_paragraph_lines: DEFAULT_MAX_PARAGRAPH_LINES,
+        };
+
+        let mut workers = config.workers();
+        assert_eq!(workers.len(), config.queries.len());
+
+        search_with_workers(&mut workers, combination, |worker| {
+            let mut entries = Vec::new();
+            let sink = SearchSink {
+                results: &mut entries,
+                matcher: &worker.matcher,
+                query: worker.query,
+                line_range: worker.line_range,
+                context_break: false,
+                captures: worker.captures,
+            };
+            worker.searcher.search_slice(&worker.matcher, text, sink).map_err(|err| err.to_string())?;
+            Ok(entries)
+        }).unwrap()
+    }
+
+    fn search(queries: &[&str], combination: QueryCombination) -> Vec<SearchResultEntry> {
+        search_text(TEXT, queries.iter().map(|text| query(text)).collect(), combination)
+    }
+
+    #[test]
+    fn stdin_searched_as_a_single_file() {
+        let config = SearchConfig { queries: vec![query("println")], ..SearchConfig::default() };
+        let result = search_stdin(&mut config.workers(), config.combination, TEXT, BinaryMode::default());
+        assert_eq!(result.path, PathBuf::from(STDIN_PATH));
+        assert!(result.error.is_none());
+        assert_eq!(result.entries.len(), 1);
+        assert_eq!(result.entries[0].line_number, 2);
+    }
+
+    #[test]
+    fn capture_groups_of_regex_queries() {
+        let mut regex = query(r"(\w+)!\((.*)\)");
+        regex.regex_syntax = true;
+        let mut config = SearchConfig { queries: vec![regex, query("main")], ..SearchConfig::default() };
+        config.combination = QueryCombination::Any;
+
+        let result = search_stdin(&mut config.workers(), config.combination, TEXT, BinaryMode::default());
+        assert!(result.entries.iter().all(|entry| entry.captures.is_empty()));
+
+        config.captures = true;
+        let result = search_stdin(&mut config.workers(), config.combination, TEXT, BinaryMode::default());
+        assert_eq!(result.entries.len(), 2);
+        // Literal queries have no groups.
+        assert!(result.entries[0].captures.is_empty());
+        assert_eq!(result.entries[1].captures, vec![Some((4, 11)), Some((13, 20))]);
+    }
+
+    #[test]
+    fn paragraphs_of_the_matches() {
+        const RECORDS: &[u8] = b"id: 1\nname: a\n\nid: 2\nname: b\nextra: c\n\nid: 3\n";
+        let mut config = SearchConfig { queries: vec![query("name")], ..SearchConfig::default() };
+        config.paragraphs = true;
+
+        let lines = |config: &SearchConfig| -> Vec<(u64, bool)> {
+            let result = search_stdin(&mut config.workers(), config.combination, RECORDS, BinaryMode::default());
+            result.entries.iter().map(|entry| (entry.line_number, entry.context_break)).collect()
+        };
+        assert_eq!(lines(&config), vec![(1, false), (2, false), (4, true), (5, false), (6, false)]);
+
+        // Cut around the first match.
+        config.max_paragraph_lines = 1;
+        assert_eq!(lines(&config), vec![(2, false), (5, true)]);
+    }
+
+    #[test]
+    fn all_queries_with_one_not_matching() {
+        let entries = search(&["main", "missing"], QueryCombination::All);
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn any_query_with_one_not_matching() {
+        let entries = search(&["missing", "main"], QueryCombination::Any);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].line_number, 1);
+        assert_eq!(entries[0].matches, vec![SearchMatch { start: 3, end: 7, query: 1 }]);
+    }
+
+    #[test]
+    fn all_queries_matching_on_different_lines() {
+        let entries = search(&["hello", "main"], QueryCombination::All);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].line_number, 1);
+        assert_eq!(entries[0].matches, vec![SearchMatch { start: 3, end: 7, query: 1 }]);
+        assert_eq!(entries[1].line_number, 2);
+        assert_eq!(entries[1].matches, vec![SearchMatch { start: 14, end: 19, query: 0 }]);
+    }
+
+    #[test]
+    fn overlapping_matches_on_the_same_line() {
+        let entries = search(&["print", "println"], QueryCombination::All);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].matches, vec![SearchMatch { start: 4, end: 11, query: 0 }]);
+    }
+
+    #[test]
+    fn context_lines_are_grouped() {
+        let mut query = query("match");
+        query.before_context = 1;
+        query.after_context = 1;
+
+        let text = b"match\na\nb\nc\nmatch\n";
+        let entries = search_text(text, vec![query], QueryCombination::All);
+        let lines: Vec<(u64, EntryKind, bool)> = entries
+            .iter()
+            .map(|entry| (entry.line_number, entry.kind, entry.context_break))
+            .collect();
+        assert_eq!(lines, vec![
+            (1, EntryKind::Match, false),
+            (2, EntryKind::Context, false),
+            (4, EntryKind::Context, true),
+            (5, EntryKind::Match, false),
+        ]);
+    }
+
+    fn overrides(globs: &str) -> Result<Override> {
+        let mut config = SearchConfig::default();
+        config.globs = String::from(globs);
+        config.overrides()
+    }
+
+    #[test]
+    fn globs_with_excludes_and_alternatives() {
+        let overrides = overrides("*.rs !main.rs *.{c,h}").unwrap();
+        assert!(overrides.matched("lib.rs", false).is_whitelist());
+        assert!(overrides.matched("main.rs", false).is_ignore());
+        assert!(overrides.matched("lib.c", false).is_whitelist());
+        assert!(overrides.matched("lib.h", false).is_whitelist());
+        assert!(overrides.matched("lib.cpp", false).is_ignore());
+    }
+
+    #[test]
+    fn globs_with_quotes_and_ripgrep_arguments() {
+        let overrides = overrides(r#""my file.txt" -g *.rs --glob=*.c --glob *.h"#).unwrap();
+        assert!(overrides.matched("my file.txt", false).is_whitelist());
+        assert!(overrides.matched("lib.rs", false).is_whitelist());
+        assert!(overrides.matched("lib.c", false).is_whitelist());
+        assert!(overrides.matched("lib.h", false).is_whitelist());
+        assert!(overrides.matched("lib.cpp", false).is_ignore());
+    }
+
+    #[test]
+    fn invalid_globs_are_all_reported() {
+        let error = overrides("*.rs a{b c{d").unwrap_err().to_string();
+        assert!(error.contains("'a{b'"), "{}", error);
+        assert!(error.contains("'c{d'"), "{}", error);
+
+        overrides("-g").unwrap_err();
+        overrides(r#""*.rs"#).unwrap_err();
+    }
+
+    #[test]
+    fn globs_matching_at_any_depth() {
+        assert_eq!(any_depth_glob("*.txt"), "**/*.txt");
+        assert_eq!(any_depth_glob("!src/*.rs"), "!**/src/*.rs");
+        assert_eq!(any_depth_glob("/src/*.rs"), "/src/*.rs");
+        assert_eq!(any_depth_glob("**/*.rs"), "**/*.rs");
+
+        let mut config = SearchConfig::default();
+        config.globs = String::from("src/*.rs");
+        assert!(config.overrides().unwrap().matched("a/src/lib.rs", false).is_ignore());
+
+        config.globs_any_depth = true;
+        assert!(config.overrides().unwrap().matched("a/src/lib.rs", false).is_whitelist());
+    }
+
+    #[test]
+    fn globs_ignoring_case() {
+        let mut config = SearchConfig::default();
+        config.globs = String::from("*.txt !README.*");
+        let overrides = config.overrides().unwrap();
+        assert!(overrides.matched("notes.TXT", false).is_ignore());
+
+        config.globs_ignore_case = true;
+        let overrides = config.overrides().unwrap();
+        assert!(overrides.matched("notes.TXT", false).is_whitelist());
+        assert!(overrides.matched("readme.txt", false).is_ignore());
+    }
+
+    #[test]
+    fn excluded_directories_take_precedence() {
+        let mut config = SearchConfig::default();
+        config.exclude_dirs = String::from(" node_modules, target/;;.git ");
+        assert_eq!(config.exclude_dir_globs(), vec!["!**/node_modules/**", "!**/target/**", "!**/.git/**"]);
+
+        config.globs = String::from("*.rs");
+        let overrides = config.overrides().unwrap();
+        assert!(overrides.matched("src/main.rs", false).is_whitelist());
+        assert!(overrides.matched("target/debug/build.rs", false).is_ignore());
+        assert!(overrides.matched("a/node_modules/b/index.rs", false).is_ignore());
+        assert!(overrides.matched("targets/main.rs", false).is_whitelist());
+    }
+
+    #[test]
+    fn overlapping_paths_are_searched_once() {
+        let dir = std::env::temp_dir().join(format!("search-overlapping-paths-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/app")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "needle\n").unwrap();
+        std::fs::write(dir.join("src/app/main.rs"), "needle\n").unwrap();
+
+        let src = dir.join("src").to_string_lossy().into_owned();
+        let app = dir.join("src/app").to_string_lossy().into_owned();
+
+        let mut config = SearchConfig::with_paths(format!("{};{};{}", app, src, src));
+        config.queries[0].query = String::from("needle");
+        assert_eq!(config.search_roots(), vec![PathBuf::from(&src)]);
+
+        let pending = spawn_search(&config, BinaryMode::Skip, SymlinkMode::NoFollow, false, 1).unwrap();
+        let mut paths = Vec::new();
+        loop {
+            match pending.try_recv() {
+                Ok(result) => paths.push(result.path),
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(1)),
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        paths.sort();
+        assert_eq!(paths, vec![dir.join("src/app/main.rs"), dir.join("src/lib.rs")]);
+    }
+
+    #[test]
+    fn roots_with_their_own_globs() {
+        let dir = std::env::temp_dir().join(format!("search-roots-globs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        for name in ["src/lib.rs", "src/notes.md", "docs/guide.md", "docs/example.rs"] {
+            std::fs::write(dir.join(name), "needle\n").unwrap();
+        }
+
+        let mut config = SearchConfig::with_paths(String::new());
+        config.queries[0].query = String::from("needle");
+        config.per_root_globs = true;
+        config.roots = vec![
+            SearchRoot { path: dir.join("src").to_string_lossy().into_owned(), globs: String::from("*.rs") },
+            SearchRoot { path: dir.join("docs").to_string_lossy().into_owned(), globs: String::from("*.md") },
+            SearchRoot { path: String::new(), globs: String::from("*.txt") },
+        ];
+        assert_eq!(config.walk_roots().unwrap().len(), 2);
+
+        let pending = spawn_search(&config, BinaryMode::Skip, SymlinkMode::NoFollow, false, 1).unwrap();
+        let mut paths = Vec::new();
+        loop {
+            match pending.try_recv() {
+                Ok(result) => paths.push(result.path),
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(1)),
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        paths.sort();
+        assert_eq!(paths, vec![dir.join("docs/guide.md"), dir.join("src/lib.rs")]);
+
+        config.roots[0].globs = String::from("\"*.rs");
+        assert!(config.walk_roots().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_followed_per_mode() {
+        let dir = std::env::temp_dir().join(format!("search-symlinks-{}", std::process::id()));
+        let outside = std::env::temp_dir().join(format!("search-symlinks-outside-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "needle\n").unwrap();
+        std::fs::write(outside.join("file.rs"), "needle\n").unwrap();
+        std::fs::write(outside.join("nested.rs"), "needle\n").unwrap();
+        std::os::unix::fs::symlink(outside.join("file.rs"), dir.join("linked.rs")).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("linked-dir")).unwrap();
+        // Points to one of its parents, only walking it once is fine.
+        std::os::unix::fs::symlink(&dir, dir.join("src/cycle")).unwrap();
+
+        let mut config = SearchConfig::with_paths(dir.to_string_lossy().into_owned());
+        config.queries[0].query = String::from("needle");
+
+        let search = |symlink_mode| {
+            let pending = spawn_search(&config, BinaryMode::Skip, symlink_mode, false, 1).unwrap();
+            let mut paths = Vec::new();
+            while let Ok(result) = pending.recv() {
+                if result.error.is_none() {
+                    paths.push(result.path.strip_prefix(&dir).unwrap().to_path_buf());
+                }
+            }
+            paths.sort();
+            paths
+        };
+
+        let no_follow = search(SymlinkMode::NoFollow);
+        let follow_files = search(SymlinkMode::FollowFiles);
+        let follow_all = search(SymlinkMode::FollowAll);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+
+        assert_eq!(no_follow, vec![PathBuf::from("src/lib.rs")]);
+        assert_eq!(follow_files, vec![PathBuf::from("linked.rs"), PathBuf::from("src/lib.rs")]);
+        assert_eq!(
+            follow_all,
+            vec![PathBuf::from("linked-dir/file.rs"), PathBuf::from("linked-dir/nested.rs"), PathBuf::from("linked.rs"), PathBuf::from("src/lib.rs")]
+        );
+    }
+
+    #[test]
+    fn file_roots_searched_without_walking() {
+        let dir = std::env::temp_dir().join(format!("search-file-roots-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("first.txt"), "needle\nhay\n").unwrap();
+        std::fs::write(dir.join("second.txt"), "hay\nneedle\n").unwrap();
+        std::fs::write(dir.join("skipped.txt"), "needle\n").unwrap();
+
+        let paths = format!("{};{}", dir.join("first.txt").to_string_lossy(), dir.join("second.txt").to_string_lossy());
+        let mut config = SearchConfig::with_paths(paths);
+        config.queries[0].query = String::from("needle");
+
+        let pending = spawn_search(&config, BinaryMode::Skip, SymlinkMode::NoFollow, false, 0).unwrap();
+        let mut found = Vec::new();
+        while let Ok(result) = pending.recv() {
+            let lines: Vec<u64> = result.entries.iter().map(|entry| entry.line_number).collect();
+            found.push((result.path.file_name().unwrap().to_string_lossy().into_owned(), result.root == result.path, lines));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, vec![(String::from("first.txt"), true, vec![1]), (String::from("second.txt"), true, vec![2])]);
+    }
+
+    #[test]
+    fn describe_the_resolved_search() {
+        let mut config = SearchConfig::with_paths(String::from("src"));
+        config.globs = String::from("*.rs -g !main.rs");
+        config.globs_any_depth = true;
+        config.exclude_dirs = String::from("target");
+        config.queries[0].query = String::from("fn \\w+");
+        config.queries[0].regex_syntax = true;
+        config.queries[0].ignore_case = false;
+        config.queries.push(query("todo"));
+
+        let roots = config.describe_roots().unwrap();
+        assert_eq!(roots[0], "Path 'src', globs: **/*.rs !**/main.rs !**/target/**");
+        assert_eq!(
+            config.describe_queries(),
+            vec!["Query 1: 'fn \\w+' (regex)", "Query 2: 'todo' (literal, ignore case)", "Combination: All"]
+        );
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn search_inside_zip_archives() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("search-zip-archives-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logs.zip");
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("a.log", options).unwrap();
+        zip.write_all(b"first\nneedle\n").unwrap();
+        zip.start_file("b.log", options).unwrap();
+        zip.write_all(b"nothing\n").unwrap();
+        zip.start_file("c.bin", options).unwrap();
+        zip.write_all(b"\x00needle\n").unwrap();
+        zip.finish().unwrap();
+
+        let mut workers = vec![query("needle").search_worker(true, 0).unwrap()];
+        let results = search_archive(&mut workers, QueryCombination::All, SearchMode::Contents, &dir, &path, BinaryMode::Skip);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let found: Vec<(PathBuf, usize)> = results.into_iter().map(|result| (result.path, result.entries.len())).collect();
+        assert_eq!(found, vec![
+            (crate::archive::entry_path(&path, "a.log"), 1),
+            (crate::archive::entry_path(&path, "b.log"), 0),
+            (crate::archive::entry_path(&path, "c.bin"), 0),
+        ]);
+    }
+
+    #[test]
+    fn queries_minimum_length() {
+        let mut config = SearchConfig::with_paths(String::new());
+        assert!(!config.queries_have_min_len(0));
+
+        config.queries = vec![query("é"), query("")];
+        assert!(config.queries_have_min_len(1));
+        assert!(!config.queries_have_min_len(2));
+
+        config.queries.push(query("needle"));
+        assert!(!config.queries_have_min_len(2));
+        config.queries[0].query = String::from("ab");
+        assert!(config.queries_have_min_len(2));
+    }
+
+    #[test]
+    fn thread_count_is_bounded() {
+        let available = available_threads();
+        assert_eq!(thread_count(0), available);
+        assert_eq!(thread_count(-1), available);
+        assert_eq!(thread_count(i32::MIN), available);
+        assert_eq!(thread_count(1), 1);
+        assert_eq!(thread_count(i32::MAX), available * 4);
+        assert_eq!(thread_count(max_thread_count()), available * 4);
+    }
+
+    #[test]
+    fn any_literal_of_the_query() {
+        let text = b"let a = foo(1);\nlet b = bar.baz;\nlet c = qux;\n";
+        let mut any = query("foo  bar.baz");
+        any.any_literal = true;
+
+        let entries = search_text(text, vec![any], QueryCombination::All);
+        let lines: Vec<(u64, Vec<(usize, usize)>)> = entries
+            .iter()
+            .map(|entry| (entry.line_number, entry.matches.iter().map(|matche| (matche.start, matche.end)).collect()))
+            .collect();
+        assert_eq!(lines, vec![(1, vec![(8, 11)]), (2, vec![(8, 15)])]);
+
+        // Without the option, the query is a single literal.
+        assert!(search_text(text, vec![query("foo  bar.baz")], QueryCombination::All).is_empty());
+    }
+
+    #[test]
+    fn asymmetric_context() {
+        let text = b"1\n2\n3\nneedle\n5\n6\n7\n";
+        let context = |before: usize, after: usize| -> Vec<(u64, EntryKind)> {
+            let mut needle = query("needle");
+            needle.before_context = before;
+            needle.after_context = after;
+            search_text(text, vec![needle], QueryCombination::All).iter().map(|entry| (entry.line_number, entry.kind)).collect()
+        };
+
+        assert_eq!(context(2, 0), vec![(2, EntryKind::Context), (3, EntryKind::Context), (4, EntryKind::Match)]);
+        assert_eq!(context(0, 1), vec![(4, EntryKind::Match), (5, EntryKind::Context)]);
+        assert_eq!(context(1, 3), vec![(3, EntryKind::Context), (4, EntryKind::Match), (5, EntryKind::Context), (6, EntryKind::Context), (7, EntryKind::Context)]);
+    }
+
+    #[test]
+    fn matches_in_line_range() {
+        let text = b"a\nb\na\nb\na\nb\na\n";
+        let line_numbers = |range: LineRange| -> Vec<u64> {
+            let mut needle = query("a");
+            needle.line_range = range;
+            search_text(text, vec![needle], QueryCombination::All).iter().map(|entry| entry.line_number).collect()
+        };
+
+        assert_eq!(line_numbers(LineRange::default()), vec![1, 3, 5, 7]);
+        assert_eq!(line_numbers(LineRange { min: Some(2), max: Some(5) }), vec![3, 5]);
+        assert_eq!(line_numbers(LineRange { min: Some(4), max: None }), vec![5, 7]);
+        assert_eq!(line_numbers(LineRange { min: None, max: Some(2) }), vec![1]);
+    }
+
+    #[test]
+    fn pcre_lookaround() {
+        let mut lookahead = query(r"fn (?=main)");
+        lookahead.regex_syntax = true;
+        lookahead.pcre = true;
+        let results = search_text(TEXT, vec![lookahead.clone()], QueryCombination::All);
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].matches[0].start, results[0].matches[0].end), (0, 3));
+
+        // Without the PCRE engine, lookaround is a syntax error.
+        lookahead.pcre = false;
+        assert!(lookahead.check_syntax().is_some());
+    }
+
+    #[test]
+    fn highlight_searched_lines() {
+        let mut regex = query(r"o+");
+        regex.regex_syntax = true;
+        let mut inverted = query("main");
+        inverted.invert_match = true;
+
+        let highlighter = Highlighter::new(&[regex, query("fo"), inverted]).unwrap();
+        let matches: Vec<(usize, usize, usize)> = highlighter
+            .find(b"foo boo")
+            .iter()
+            .map(|matche| (matche.start, matche.end, matche.query))
+            .collect();
+        assert_eq!(matches, vec![(0, 3, 1), (5, 7, 0)]);
+
+        let mut invalid = query("(");
+        invalid.regex_syntax = true;
+        assert!(Highlighter::new(&[invalid]).is_err());
+    }
+
+    #[test]
+    fn file_names_matching_queries() {
+        let config = SearchConfig {
+            paths: String::new(),
+            globs: String::new(),
+            globs_any_depth: false,
+            globs_ignore_case: false,
+            per_root_globs: false,
+            roots: Vec::new(),
+            exclude_dirs: String::new(),
+            hidden: false,
+            only_modified: false,
+            only_tracked: false,
+            queries: vec![query("main"), query(".rs")],
+            combination: QueryCombination::All,
+            mode: SearchMode::FileNames,
+            fuzzy: false,
+            captures: false,
+            paragraphs: false,
+            max_paragraph_lines: DEFAULT_MAX_PARAGRAPH_LINES,
+        };
+
+        let workers = config.workers();
+        let entry = match_file_name(&workers, QueryCombination::All, Path::new("src/main.rs")).unwrap();
+        assert_eq!(entry.kind, EntryKind::FileName);
+        assert_eq!(entry.bytes, b"main.rs");
+        assert_eq!(entry.matches, vec![
+            SearchMatch { start: 0, end: 4, query: 0 },
+            SearchMatch { start: 4, end: 7, query: 1 },
+        ]);
+
+        assert!(match_file_name(&workers, QueryCombination::All, Path::new("main.c")).is_none());
+        assert!(match_file_name(&workers, QueryCombination::Any, Path::new("main.c")).is_some());
+        assert!(match_file_name(&workers, QueryCombination::Any, Path::new("main/lib.c")).is_none());
+    }
+
+    #[test]
+    fn file_names_matching_fuzzily() {
+        let mut config = SearchConfig::with_paths(String::new());
+        config.queries[0].query = String::from("srs");
+        config.mode = SearchMode::FileNames;
+        config.fuzzy = true;
+
+        let workers = config.workers();
+        let entry = match_file_name(&workers, QueryCombination::All, Path::new("src/search.rs")).unwrap();
+        assert_eq!(entry.matches, vec![
+            SearchMatch { start: 0, end: 1, query: 0 },
+            SearchMatch { start: 7, end: 9, query: 0 },
+        ]);
+        assert!(entry.score > 0);
+        assert!(match_file_name(&workers, QueryCombination::All, Path::new("src/main.rs")).is_none());
+
+        // Fuzzy matching only applies to the file names mode.
+        config.mode = SearchMode::Both;
+        let workers = config.workers();
+        assert!(match_file_name(&workers, QueryCombination::All, Path::new("src/search.rs")).is_none());
+    }
+
+    #[test]
+    fn crlf_line_terminators() {
+        const CRLF_TEXT: &[u8] = b"first line\r\nsecond line\r\nlast";
+
+        let mut query = query("line$");
+        query.regex_syntax = true;
+        let entries = search_text(CRLF_TEXT, vec![query], QueryCombination::All);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].bytes, b"first line\r\n");
+        assert_eq!(entries[0].matches, vec![SearchMatch { start: 6, end: 10, query: 0 }]);
+        assert_eq!(trim_line_terminator(&entries[0].bytes), b"first line");
+        assert_eq!(trim_line_terminator(&entries[1].bytes), b"second line");
+    }
+
+    #[test]
+    fn paths_relative_to_their_root() {
+        assert_eq!(relative_path("src/app/main.rs", "src"), "app/main.rs");
+        assert_eq!(relative_path("src/app/main.rs", "src/"), "app/main.rs");
+        assert_eq!(relative_path("./src/main.rs", "./"), "src/main.rs");
+        assert_eq!(relative_path("/home/main.rs", "/"), "home/main.rs");
+        assert_eq!(relative_path("src2/main.rs", "src"), "src2/main.rs");
+
+        // The root is the file that was searched.
+        assert_eq!(relative_path("src/app/main.rs", "src/app/main.rs"), "main.rs");
+        assert_eq!(relative_path("main.rs", "main.rs"), "main.rs");
+
+        assert_eq!(relative_path(r"C:\Users\src\main.rs", r"C:\Users\"), r"src\main.rs");
+        assert_eq!(relative_path(r"C:\main.rs", r"C:\"), "main.rs");
+        assert_eq!(relative_path(r"C:\src\main.rs", r"C:\src\main.rs"), "main.rs");
+        assert_eq!(relative_path(r"D:\src\main.rs", r"C:\"), r"D:\src\main.rs");
+    }
+
+    #[test]
+    fn surrounding_lines_of_a_match() {
+        let text: &[u8] = b"one\ntwo\r\nthree\nfour\nfive\n";
+        assert_eq!(surrounding_lines(text, 2, 1).unwrap(), "     1: one\n>    2: two\n     3: three");
+        assert_eq!(surrounding_lines(text, 1, 1).unwrap(), ">    1: one\n     2: two");
+        assert_eq!(surrounding_lines(text, 5, 3).unwrap(), "     2: two\n     3: three\n     4: four\n>    5: five");
+    }
+
+    #[test]
+    fn trim_line_terminators() {
+        assert_eq!(trim_line_terminator(b"abc\r\n"), b"abc");
+        assert_eq!(trim_line_terminator(b"abc\n"), b"abc");
+        assert_eq!(trim_line_terminator(b"abc"), b"abc");
+        assert_eq!(trim_line_terminator(b"abc\r"), b"abc");
+        assert_eq!(trim_line_terminator(b"\n"), b"");
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::{anyhow, bail, Result};
-use crate::help;
+use crate::{help, search::{self, BinaryMode, SearchQuery, SymlinkMode}, workspace::WorkspaceSummary};
 use imgui::*;
 use serde::{Serialize, Deserialize};
 use std::{
+    collections::{BTreeMap, HashMap},
     fs,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 #[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
@@ -18,31 +20,419 @@ impl Default for StyleColor {
     fn default() -> Self { Self::Dark }
 }
 
-#[derive(Default, Serialize, Deserialize)]
+/// Font used to draw the results, the rest of the interface uses the monospace font.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Default)]
+pub enum ResultsFont {
+    #[default]
+    Monospace,
+    Proportional,
+}
+
+/// How the matches are highlighted in the results. The color of the query is either used
+/// for the text, for a line under it or for a box behind it.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Default)]
+pub enum MatchHighlightStyle {
+    #[default]
+    Color,
+    Underline,
+    Background,
+}
+
+/// What double clicking a result does, F4 always opens the results in the editor.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Default)]
+pub enum DoubleClickAction {
+    #[default]
+    OpenInEditor,
+    OpenContainingFolder,
+    OpenWithDefaultApp,
+    CopyPath,
+}
+
+impl DoubleClickAction {
+    pub const NAMES: [&'static str; 4] = ["Open in editor", "Open containing folder", "Open with default app", "Copy path"];
+    pub const ALL: [DoubleClickAction; 4] = [Self::OpenInEditor, Self::OpenContainingFolder, Self::OpenWithDefaultApp, Self::CopyPath];
+}
+
+impl StyleColor {
+    /// How much the context lines are faded toward the background. Dark text on a
+    /// light background becomes unreadable faster, so light styles are faded less.
+    pub fn context_dim(self) -> f32 {
+        match self {
+            Self::Dark => 0.45,
+            Self::Light => 0.35,
+            Self::Classic => 0.4,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EditorPreset {
+    pub name: String,
+    pub command: String,
+}
+
+/// Version of the layout of the settings file, incremented when older settings need to be
+/// migrated rather than only getting default values for the new fields.
+/// - 0: before the versions, with `editor_path` and `search_binary`
+/// - 1: `editor_presets` and `binary_mode`
+/// - 2: `symlink_mode`
+pub const SETTINGS_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Settings {
+    /// Version of the layout the settings were saved with, 0 for the files saved before
+    /// the versions existed.
     #[serde(default)]
-    pub number_of_threads: i32,
+    pub version: u32,
     #[serde(default)]
+    pub number_of_threads: i32,
+    /// Replaced by `symlink_mode`, only read to migrate older settings.
+    #[serde(default, skip_serializing)]
     pub follow_symlink: bool,
     #[serde(default)]
+    pub symlink_mode: SymlinkMode,
+    /// Replaced by `binary_mode`, only read to migrate older settings.
+    #[serde(default, skip_serializing)]
     pub search_binary: bool,
     #[serde(default)]
+    pub binary_mode: BinaryMode,
+    #[serde(default)]
+    pub search_archives: bool,
+    #[serde(default)]
+    pub hex_binary_lines: bool,
+    /// Only read to migrate the editor of older settings to `editor_presets`.
+    #[serde(default, skip_serializing)]
     pub editor_path: String,
     #[serde(default)]
+    pub editor_presets: Vec<EditorPreset>,
+    /// Index, in `editor_presets`, of the editor used to open the results.
+    #[serde(default)]
+    pub active_editor: usize,
+    #[serde(default)]
+    pub editor_in_file_dir: bool,
+    /// Command lines opening the files with these extensions, instead of the active editor.
+    /// The extensions are lowercase without the leading dot.
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub extension_editors: HashMap<String, String>,
+    #[serde(default)]
+    pub double_click_action: DoubleClickAction,
+    #[serde(default = "default_location_url")]
+    pub location_url: String,
+    /// Command opening a terminal in the directory of a result.
+    #[serde(default = "default_terminal")]
+    pub terminal: String,
+    /// Command opening a file in a hex editor at the offset of a match, empty if there is none.
+    #[serde(default)]
+    pub hex_editor: String,
+    #[serde(default)]
+    pub show_timing: bool,
+    /// Whether the paths, globs and options of every search are written to the log.
+    #[serde(default)]
+    pub verbose_search: bool,
+    #[serde(default)]
+    pub show_memory: bool,
+    /// Memory, in megabytes, the results of a tab can hold. 0 for no limit.
+    #[serde(default)]
+    pub max_results_memory: i32,
+    #[serde(default)]
+    pub context_lines: i32,
+    #[serde(default)]
+    pub default_regex_syntax: bool,
+    #[serde(default = "default_ignore_case")]
+    pub default_ignore_case: bool,
+    #[serde(default)]
+    pub default_invert_match: bool,
+    #[serde(default = "default_min_query_len")]
+    pub min_query_len: i32,
+    #[serde(default = "default_max_display_line_len")]
+    pub max_display_line_len: i32,
+    #[serde(default = "default_max_paragraph_lines")]
+    pub max_paragraph_lines: i32,
+    #[serde(default)]
     pub style_color: StyleColor,
+    #[serde(default)]
+    pub results_font: ResultsFont,
+    #[serde(default)]
+    pub match_highlight: MatchHighlightStyle,
+    /// Whether the background of the matched lines is tinted, to tell them apart from the context lines.
+    #[serde(default)]
+    pub highlight_match_rows: bool,
+    /// Whether the Japanese glyphs are loaded in the fonts, only applied on the next launch.
+    #[serde(default = "default_cjk_glyphs")]
+    pub cjk_glyphs: bool,
+    #[serde(default)]
+    pub relative_paths: bool,
+    #[serde(default)]
+    pub group_by_file: bool,
+    /// Whether runs of identical context lines are shown as a single line.
+    #[serde(default)]
+    pub fold_repeated_context: bool,
+    /// Whether the modification time of the files is shown in a column of the results.
+    #[serde(default)]
+    pub show_modified_time: bool,
+    /// Whether a strip showing where the matches are in the file is drawn beside the results
+    /// of a single file.
+    #[serde(default = "default_show_minimap")]
+    pub show_minimap: bool,
+    #[serde(default)]
+    pub hide_toolbar: bool,
+    #[serde(default = "default_scroll_to_first_match")]
+    pub scroll_to_first_match: bool,
+    /// Select the same result again when a tab is searched again, if it's still found.
+    #[serde(default)]
+    pub keep_selection_on_search: bool,
+    #[serde(default)]
+    pub always_on_top: bool,
+    #[serde(default)]
+    pub global_hotkey: String,
+    /// Most recently opened workspaces first.
+    #[serde(default)]
+    pub recent_workspaces: Vec<String>,
+    #[serde(default)]
+    pub reset_tabs_on_workspace: bool,
+    /// Whether the first tab is filled with the last search done in the workspace.
+    #[serde(default = "default_restore_workspace_search")]
+    pub restore_workspace_search: bool,
+    /// Last search of each workspace, most recent first.
+    #[serde(default)]
+    pub workspace_summaries: Vec<WorkspaceSummary>,
+    /// Seconds after a change before the settings are saved. 0 to only save them when
+    /// closing the settings window and on exit.
+    #[serde(default = "default_autosave_delay")]
+    pub autosave_delay: i32,
+    /// Layout saved by imgui, in the .ini format. It contains the width and order of the
+    /// columns of the results table.
+    #[serde(default)]
+    pub layout: String,
+}
+
+fn default_ignore_case() -> bool {
+    true
+}
+
+fn default_min_query_len() -> i32 {
+    2
+}
+
+/// Serialize a map sorted by its keys, such that the settings are saved the same way every time.
+fn serialize_sorted<S: serde::Serializer>(map: &HashMap<String, String>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let sorted: BTreeMap<&String, &String> = map.iter().collect();
+    return sorted.serialize(serializer);
+}
+
+/// Extension as the key of `Settings::extension_editors`: lowercase without the leading dot.
+pub fn normalize_extension(extension: &str) -> String {
+    return extension.trim().trim_start_matches('.').to_lowercase();
+}
+
+fn default_terminal() -> String {
+    if cfg!(windows) {
+        return String::from("wt -d {dir}");
+    }
+
+    return std::env::var("TERMINAL").unwrap_or_else(|_| String::from("x-terminal-emulator"));
+}
+
+fn default_location_url() -> String {
+    String::from("file://{path}")
+}
+
+fn default_cjk_glyphs() -> bool {
+    true
+}
+
+fn default_scroll_to_first_match() -> bool {
+    true
+}
+
+fn default_show_minimap() -> bool {
+    true
+}
+
+/// Smallest length lines are truncated to, shorter lengths would hide the matches.
+const MIN_DISPLAY_LINE_LEN: i32 = 16;
+
+/// Clamp a length lines are truncated to, 0 never truncates.
+fn clamp_display_line_len(len: i32) -> i32 {
+    if len <= 0 {
+        return 0;
+    }
+    return len.max(MIN_DISPLAY_LINE_LEN);
+}
+
+fn default_max_display_line_len() -> i32 {
+    2000
+}
+
+fn default_max_paragraph_lines() -> i32 {
+    20
+}
+
+fn default_restore_workspace_search() -> bool {
+    true
+}
+
+fn default_autosave_delay() -> i32 {
+    5
+}
+
+impl Settings {
+    /// Number of bytes of a line displayed in the results, 0 to never truncate. Settings
+    /// edited by hand may hold a length below the minimum.
+    pub fn display_line_len(&self) -> usize {
+        return clamp_display_line_len(self.max_display_line_len) as usize;
+    }
+
+    /// Command line of the active editor, empty if there is none.
+    pub fn editor_command(&self) -> &str {
+        self.editor_presets
+            .get(self.active_editor)
+            .map_or("", |preset| preset.command.as_str())
+    }
+
+    /// Command line opening `path`: the editor of its extension if there is one, otherwise
+    /// the active editor. Empty if there is none.
+    pub fn editor_command_for(&self, path: &Path) -> &str {
+        let editor = path
+            .extension()
+            .and_then(|extension| self.extension_editors.get(&normalize_extension(&extension.to_string_lossy())))
+            .filter(|command| !command.is_empty());
+        return editor.map_or_else(|| self.editor_command(), |command| command.as_str());
+    }
+
+    /// Upgrade settings saved with an older layout to the current one.
+    fn migrate(&mut self) {
+        if self.version < 1 {
+            self.migrate_editor_path();
+            self.migrate_search_binary();
+        }
+
+        if self.version < 2 {
+            self.migrate_follow_symlink();
+        }
+
+        if self.version > SETTINGS_VERSION {
+            log!("The settings were saved by a newer version (version {}), some may be lost", self.version);
+        }
+        self.version = SETTINGS_VERSION;
+    }
+
+    /// Move the editor of settings saved before the presets existed to the first preset.
+    fn migrate_editor_path(&mut self) {
+        if self.editor_path.is_empty() {
+            return;
+        }
+
+        let command = std::mem::take(&mut self.editor_path);
+        self.editor_presets.insert(0, EditorPreset { name: String::from("Default"), command });
+        self.active_editor = 0;
+    }
+
+    /// Settings saved before the binary modes existed only had the choice to search binaries fully.
+    fn migrate_search_binary(&mut self) {
+        if std::mem::take(&mut self.search_binary) {
+            self.binary_mode = BinaryMode::Search;
+        }
+    }
+
+    /// The links were either all followed or not at all before the symlink modes existed.
+    fn migrate_follow_symlink(&mut self) {
+        if std::mem::take(&mut self.follow_symlink) {
+            self.symlink_mode = SymlinkMode::FollowAll;
+        }
+    }
+
+    /// Query with the options configured as default for new queries.
+    pub fn default_query(&self) -> SearchQuery {
+        let mut query = SearchQuery::new();
+        query.regex_syntax = self.default_regex_syntax;
+        query.ignore_case = self.default_ignore_case;
+        query.invert_match = self.default_invert_match;
+        let context_lines = self.context_lines.max(0) as usize;
+        query.before_context = context_lines;
+        query.after_context = context_lines;
+        query
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            number_of_threads: 0,
+            follow_symlink: false,
+            symlink_mode: SymlinkMode::default(),
+            search_binary: false,
+            binary_mode: BinaryMode::default(),
+            search_archives: false,
+            hex_binary_lines: false,
+            editor_path: String::new(),
+            editor_presets: Vec::new(),
+            active_editor: 0,
+            editor_in_file_dir: false,
+            extension_editors: HashMap::new(),
+            double_click_action: DoubleClickAction::default(),
+            location_url: default_location_url(),
+            terminal: default_terminal(),
+            hex_editor: String::new(),
+            show_timing: false,
+            verbose_search: false,
+            show_memory: false,
+            max_results_memory: 0,
+            context_lines: 0,
+            default_regex_syntax: false,
+            default_ignore_case: default_ignore_case(),
+            default_invert_match: false,
+            min_query_len: default_min_query_len(),
+            max_display_line_len: default_max_display_line_len(),
+            max_paragraph_lines: default_max_paragraph_lines(),
+            style_color: StyleColor::default(),
+            results_font: ResultsFont::default(),
+            match_highlight: MatchHighlightStyle::default(),
+            highlight_match_rows: false,
+            cjk_glyphs: default_cjk_glyphs(),
+            relative_paths: false,
+            group_by_file: false,
+            fold_repeated_context: false,
+            show_modified_time: false,
+            show_minimap: default_show_minimap(),
+            hide_toolbar: false,
+            scroll_to_first_match: default_scroll_to_first_match(),
+            keep_selection_on_search: false,
+            always_on_top: false,
+            global_hotkey: String::new(),
+            recent_workspaces: Vec::new(),
+            reset_tabs_on_workspace: false,
+            restore_workspace_search: default_restore_workspace_search(),
+            workspace_summaries: Vec::new(),
+            autosave_delay: default_autosave_delay(),
+            layout: String::new(),
+        }
+    }
 }
 
 pub struct SettingsWindow {
     path: PathBuf,
     opened: bool,
     pub settings: Settings,
+    /// Copy of the settings edited in the window, only applied with "OK" or "Apply".
+    staged: Settings,
+    /// Settings as they were when staged, such that only what changed in the window is applied.
+    unchanged: Settings,
+    /// Settings as they were last saved or loaded, such that they are only saved when they changed.
+    saved: String,
+    /// Settings that changed since they were saved, with when they last changed.
+    pending: Option<(String, Instant)>,
+    last_autosave_check: Instant,
+    /// Extension typed in the window, added to `extension_editors` with "+".
+    new_extension: String,
 }
 
 const SETTING_FILE_NAME: &str = "search-settings.json";
 
 fn current_dir() -> Result<PathBuf> {
     let mut builder = std::env::current_exe().map_err(|err| {
-        println!("Failed to get the executable path, error: {}", err);
+        log!("Failed to get the executable path, error: {}", err);
         anyhow!("Failed to get the executable path")
     })?;
 
@@ -54,7 +444,7 @@ fn current_dir() -> Result<PathBuf> {
 pub fn enumerate_setting_paths() -> Result<Vec<PathBuf>> {
 
     let mut builder = current_dir().map_err(|err| {
-        println!("Failed to get the executable path, error: {}", err);
+        log!("Failed to get the executable path, error: {}", err);
         anyhow!("Failed to get the executable path")
     })?;
 
@@ -82,11 +472,59 @@ pub fn enumerate_setting_paths() -> Result<Vec<PathBuf>> {
     return Ok(results);
 }
 
+fn read_settings(path: &Path) -> Result<Settings> {
+    return parse_settings(&fs::read_to_string(path)?);
+}
+
+fn parse_settings(content: &str) -> Result<Settings> {
+    let mut settings: Settings = serde_json::from_str(content)?;
+    settings.migrate();
+    return Ok(settings);
+}
+
+/// Apply to `current` the settings that differ between `unchanged` and `staged`. The other
+/// settings may have changed since they were staged, for instance the layout or the options
+/// of the menus, and are kept.
+fn merge_changes(current: &Settings, unchanged: &Settings, staged: &Settings) -> Result<Settings> {
+    let unchanged = serde_json::to_value(unchanged)?;
+    let staged = serde_json::to_value(staged)?;
+    let mut merged = serde_json::to_value(current)?;
+
+    if let (Some(unchanged), Some(staged), Some(merged)) = (unchanged.as_object(), staged.as_object(), merged.as_object_mut()) {
+        for (name, value) in staged.iter() {
+            if unchanged.get(name) != Some(value) {
+                merged.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    return Ok(serde_json::from_value(merged)?);
+}
+
+/// Load the settings without applying the style, for when there is no window.
+pub fn load_settings() -> Settings {
+    if let Ok(paths) = enumerate_setting_paths() {
+        for path in paths.into_iter() {
+            if let Ok(settings) = read_settings(&path) {
+                return settings;
+            }
+        }
+    }
+
+    return Settings::default();
+}
+
 impl SettingsWindow {
     pub fn new() -> Self {
         let mut path = current_dir().unwrap_or(PathBuf::from(""));
         path.push(SETTING_FILE_NAME);
-        Self { path, settings: Settings::default(), opened: false }
+        Self::with_settings(path, Settings::default(), String::new())
+    }
+
+    fn with_settings(path: PathBuf, settings: Settings, saved: String) -> Self {
+        let staged = settings.clone();
+        let unchanged = settings.clone();
+        Self { path, opened: false, settings, staged, unchanged, saved, pending: None, last_autosave_check: Instant::now(), new_extension: String::new() }
     }
 
     fn update_style(style_color: StyleColor) {
@@ -98,23 +536,19 @@ impl SettingsWindow {
     }
 
     pub fn load_from_file(path: PathBuf) -> Result<Self> {
-        let content = fs::read_to_string(path.as_path())?;
-        let settings: Settings = serde_json::from_str(&content)?;
+        let content = fs::read_to_string(&path)?;
+        let settings = parse_settings(&content)?;
         Self::update_style(settings.style_color);
-        Ok(Self { path, settings, opened: false })
-    }
-
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        let content = serde_json::to_string_pretty(&self.settings)?;
-        fs::write(path, content.as_bytes())?;
-        Ok(())
+        // The content of the file rather than the migrated settings, such that a file saved
+        // with an older version is written again with the current one on save.
+        Ok(Self::with_settings(path, settings, content))
     }
 
     pub fn open_setting() -> Self {
         if let Ok(paths) = enumerate_setting_paths() {
             for path in paths.into_iter() {
                 if let Ok(settings) = Self::load_from_file(path) {
-                    println!("Loaded settings from '{}'", settings.path.to_string_lossy());
+                    log!("Loaded settings from '{}'", settings.path.to_string_lossy());
                     return settings;
                 }
             }
@@ -123,18 +557,103 @@ impl SettingsWindow {
         return SettingsWindow::new();
     }
 
-    pub fn save_results(&self) {
-        println!("Saving settings to '{}'...", self.path.to_string_lossy());
-        if self.save_to_file(self.path.as_path()).is_err() {
+    /// Save the settings if they changed since they were last saved or loaded.
+    pub fn save_results(&mut self) {
+        let content = match serde_json::to_string_pretty(&self.settings) {
+            Ok(content) => content,
+            Err(err) => {
+                log!("Failed to serialize the settings, error: {}", err);
+                return;
+            }
+        };
+
+        self.pending = None;
+        if content == self.saved {
+            return;
+        }
+
+        log!("Saving settings to '{}'...", self.path.to_string_lossy());
+        match fs::write(&self.path, content.as_bytes()) {
+            Ok(()) => self.saved = content,
+            // Still different from `saved`, so the next autosave or the exit will try again.
             // We could potentially create a Window with the serialized settings.
-            println!("Failed to save settings to '{}'", self.path.to_string_lossy());
+            Err(err) => log!("Failed to save settings to '{}', error: {}", self.path.to_string_lossy(), err),
         }
     }
 
+    /// Save the settings once they haven't changed for `autosave_delay`, such that they are
+    /// not lost if the application doesn't exit cleanly.
+    pub fn autosave(&mut self) {
+        const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+        // Serializing the settings every frame would be wasteful.
+        if self.settings.autosave_delay <= 0 || self.last_autosave_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_autosave_check = Instant::now();
+
+        let content = match serde_json::to_string_pretty(&self.settings) {
+            Ok(content) if content != self.saved => content,
+            _ => {
+                self.pending = None;
+                return;
+            }
+        };
+
+        // Wait for the settings to stop changing, for instance while typing in a field.
+        let changed_at = match self.pending.take() {
+            Some((pending, changed_at)) if pending == content => changed_at,
+            _ => Instant::now(),
+        };
+
+        if changed_at.elapsed() >= Duration::from_secs(self.settings.autosave_delay as u64) {
+            self.save_results();
+        } else {
+            self.pending = Some((content, changed_at));
+        }
+    }
+
+    /// Keep the layout up to date when imgui wants to save it, so it is saved with the settings.
+    pub fn update_layout(&mut self, ui: &Ui) {
+        if !ui.io().want_save_ini_settings {
+            return;
+        }
+
+        let data = unsafe { std::ffi::CStr::from_ptr(sys::igSaveIniSettingsToMemory(std::ptr::null_mut())) };
+        self.settings.layout = data.to_string_lossy().into_owned();
+        unsafe { (*sys::igGetIO()).WantSaveIniSettings = false };
+    }
+
     pub fn open(&mut self, opened: bool) {
+        // Opening it again would lose the changes that weren't applied.
+        if opened && !self.opened {
+            self.stage();
+        }
         self.opened = opened;
     }
 
+    /// Start editing a copy of the current settings.
+    fn stage(&mut self) {
+        self.staged = self.settings.clone();
+        self.unchanged = self.settings.clone();
+    }
+
+    /// Apply the changes made in the window and save them.
+    fn apply(&mut self) {
+        match merge_changes(&self.settings, &self.unchanged, &self.staged) {
+            Ok(settings) => self.settings = settings,
+            Err(err) => log!("Failed to apply the settings, error: {}", err),
+        }
+        self.stage();
+        self.save_results();
+    }
+
+    /// Forget the changes made in the window, including the preview of the style.
+    fn cancel(&mut self) {
+        Self::update_style(self.settings.style_color);
+        self.stage();
+    }
+
     pub fn draw_settings(&mut self, ui: &Ui) {
         if !self.opened {
             return;
@@ -145,6 +664,9 @@ impl SettingsWindow {
         let pos_x = (display_size[0] / 2.0) - (settings_window_size[0] / 2.0);
         let pos_y = (display_size[1] / 2.0) - (settings_window_size[1] / 2.0);
 
+        let mut ok = false;
+        let mut apply = false;
+        let mut cancel = false;
         let window = ui
             .window("Settings")
             .size(settings_window_size, Condition::Appearing)
@@ -171,41 +693,360 @@ impl SettingsWindow {
                 ui.table_next_column();
                 ui.text("Style: ");
                 ui.table_next_column();
-                if ui.radio_button("Dark", &mut self.settings.style_color, StyleColor::Dark) {
-                    Self::update_style(self.settings.style_color);
+                if ui.radio_button("Dark", &mut self.staged.style_color, StyleColor::Dark) {
+                    Self::update_style(self.staged.style_color);
                 }
                 ui.same_line();
-                if ui.radio_button("Light", &mut self.settings.style_color, StyleColor::Light) {
-                    Self::update_style(self.settings.style_color);
+                if ui.radio_button("Light", &mut self.staged.style_color, StyleColor::Light) {
+                    Self::update_style(self.staged.style_color);
                 }
                 ui.same_line();
-                if ui.radio_button("Classic", &mut self.settings.style_color, StyleColor::Classic) {
-                    Self::update_style(self.settings.style_color);
+                if ui.radio_button("Classic", &mut self.staged.style_color, StyleColor::Classic) {
+                    Self::update_style(self.staged.style_color);
                 }
 
+                ui.table_next_column();
+                ui.text("Results font: ");
+                ui.table_next_column();
+                ui.radio_button("Monospace", &mut self.staged.results_font, ResultsFont::Monospace);
+                ui.same_line();
+                ui.radio_button("Proportional", &mut self.staged.results_font, ResultsFont::Proportional);
+                help::show_help(ui, help::SETTINGS_RESULTS_FONT_HELP);
+
+                ui.table_next_column();
+                ui.text("Highlight matches with: ");
+                ui.table_next_column();
+                ui.radio_button("Text color", &mut self.staged.match_highlight, MatchHighlightStyle::Color);
+                ui.same_line();
+                ui.radio_button("Underline", &mut self.staged.match_highlight, MatchHighlightStyle::Underline);
+                ui.same_line();
+                ui.radio_button("Background", &mut self.staged.match_highlight, MatchHighlightStyle::Background);
+                help::show_help(ui, help::SETTINGS_MATCH_HIGHLIGHT_HELP);
+
+                ui.table_next_column();
+                ui.text("Highlight matched lines: ");
+                ui.table_next_column();
+                ui.checkbox("##highlight-match-rows", &mut self.staged.highlight_match_rows);
+                help::show_help(ui, help::SETTINGS_HIGHLIGHT_MATCH_ROWS_HELP);
+
+                ui.table_next_column();
+                ui.text("Japanese glyphs: ");
+                ui.table_next_column();
+                ui.checkbox("##cjk-glyphs", &mut self.staged.cjk_glyphs);
+                help::show_help(ui, help::SETTINGS_CJK_GLYPHS_HELP);
+
+                ui.table_next_column();
+                ui.text("Relative paths: ");
+                ui.table_next_column();
+                ui.checkbox("##relative-paths", &mut self.staged.relative_paths);
+                help::show_help(ui, help::SETTINGS_RELATIVE_PATHS_HELP);
+
+                ui.table_next_column();
+                ui.text("Group results by file: ");
+                ui.table_next_column();
+                ui.checkbox("##group-by-file", &mut self.staged.group_by_file);
+                help::show_help(ui, help::SETTINGS_GROUP_BY_FILE_HELP);
+
+                ui.table_next_column();
+                ui.text("Fold repeated context lines: ");
+                ui.table_next_column();
+                ui.checkbox("##fold-repeated-context", &mut self.staged.fold_repeated_context);
+                help::show_help(ui, help::SETTINGS_FOLD_REPEATED_CONTEXT_HELP);
+
+                ui.table_next_column();
+                ui.text("Show modification time: ");
+                ui.table_next_column();
+                ui.checkbox("##show-modified-time", &mut self.staged.show_modified_time);
+                help::show_help(ui, help::SETTINGS_SHOW_MODIFIED_TIME_HELP);
+
+                ui.table_next_column();
+                ui.text("Show match minimap: ");
+                ui.table_next_column();
+                ui.checkbox("##show-minimap", &mut self.staged.show_minimap);
+                help::show_help(ui, help::SETTINGS_SHOW_MINIMAP_HELP);
+
+                ui.table_next_column();
+                ui.text("Hide toolbar: ");
+                ui.table_next_column();
+                ui.checkbox("##hide-toolbar", &mut self.staged.hide_toolbar);
+
+                ui.table_next_column();
+                ui.text("Scroll to first match: ");
+                ui.table_next_column();
+                ui.checkbox("##scroll-to-first-match", &mut self.staged.scroll_to_first_match);
+                help::show_help(ui, help::SETTINGS_SCROLL_TO_FIRST_MATCH_HELP);
+
+                ui.table_next_column();
+                ui.text("Keep selection when searching again: ");
+                ui.table_next_column();
+                ui.checkbox("##keep-selection", &mut self.staged.keep_selection_on_search);
+                help::show_help(ui, help::SETTINGS_KEEP_SELECTION_HELP);
+
+                ui.table_next_column();
+                ui.text("Always on top: ");
+                ui.table_next_column();
+                ui.checkbox("##always-on-top", &mut self.staged.always_on_top);
+                help::show_help(ui, help::SETTINGS_ALWAYS_ON_TOP_HELP);
+
+                ui.table_next_column();
+                ui.text("Global hotkey: ");
+                ui.table_next_column();
+                ui.input_text("##global-hotkey", &mut self.staged.global_hotkey).hint("Ctrl+Alt+Space").build();
+                help::show_help(ui, help::SETTINGS_GLOBAL_HOTKEY_HELP);
+
+                ui.table_next_column();
+                ui.text("Reset tabs when opening a workspace: ");
+                ui.table_next_column();
+                ui.checkbox("##reset-tabs", &mut self.staged.reset_tabs_on_workspace);
+                help::show_help(ui, help::SETTINGS_RESET_TABS_HELP);
+
+                ui.table_next_column();
+                ui.text("Restore last search of workspace: ");
+                ui.table_next_column();
+                ui.checkbox("##restore-workspace-search", &mut self.staged.restore_workspace_search);
+                help::show_help(ui, help::SETTINGS_RESTORE_WORKSPACE_SEARCH_HELP);
+
                 ui.table_next_column();
                 ui.text("Number of threads: ");
                 ui.table_next_column();
-                ui.input_int("##threads", &mut self.settings.number_of_threads).build();
+                if ui.input_int("##threads", &mut self.staged.number_of_threads).build() {
+                    self.staged.number_of_threads = self.staged.number_of_threads.clamp(0, search::max_thread_count());
+                }
+                ui.same_line();
+                ui.text_disabled(format!("({} used)", search::thread_count(self.staged.number_of_threads)));
+                help::show_help(ui, help::SETTINGS_THREADS_HELP);
 
                 ui.table_next_column();
                 ui.text("Follow Symlinks: ");
                 ui.table_next_column();
-                ui.checkbox("##symlinks", &mut self.settings.follow_symlink);
+                let mut symlink_mode = SymlinkMode::ALL.iter().position(|mode| *mode == self.staged.symlink_mode).unwrap_or(0);
+                if ui.combo_simple_string("##symlinks", &mut symlink_mode, &SymlinkMode::NAMES) {
+                    self.staged.symlink_mode = SymlinkMode::ALL[symlink_mode];
+                }
+                help::show_help(ui, help::SETTINGS_SYMLINK_MODE_HELP);
 
                 ui.table_next_column();
-                ui.text("Search binary: ");
+                ui.text("Binary files: ");
                 ui.table_next_column();
-                ui.checkbox("##binary", &mut self.settings.search_binary);
+                let mut binary_mode = BinaryMode::ALL.iter().position(|mode| *mode == self.staged.binary_mode).unwrap_or(0);
+                if ui.combo_simple_string("##binary", &mut binary_mode, &BinaryMode::NAMES) {
+                    self.staged.binary_mode = BinaryMode::ALL[binary_mode];
+                }
                 help::show_help(ui, help::SETTINGS_SEARCH_BINARY_HELP);
 
+                if cfg!(feature = "zip") {
+                    ui.table_next_column();
+                    ui.text("Search in archives: ");
+                    ui.table_next_column();
+                    ui.checkbox("##archives", &mut self.staged.search_archives);
+                    help::show_help(ui, help::SETTINGS_SEARCH_ARCHIVES_HELP);
+                }
+
+                ui.table_next_column();
+                ui.text("Hex display for binary lines: ");
+                ui.table_next_column();
+                ui.checkbox("##hex", &mut self.staged.hex_binary_lines);
+                help::show_help(ui, help::SETTINGS_HEX_BINARY_LINES_HELP);
+
+                ui.table_next_column();
+                ui.text("Default context lines: ");
+                ui.table_next_column();
+                if ui.input_int("##context", &mut self.staged.context_lines).build() {
+                    self.staged.context_lines = self.staged.context_lines.max(0);
+                }
+                help::show_help(ui, help::SETTINGS_CONTEXT_HELP);
+
+                ui.table_next_column();
+                ui.text("Default query options: ");
+                ui.table_next_column();
+                ui.checkbox("Regex syntax##default-regex", &mut self.staged.default_regex_syntax);
+                ui.same_line();
+                ui.checkbox("Ignore case##default-ignore-case", &mut self.staged.default_ignore_case);
+                ui.same_line();
+                ui.checkbox("Invert match##default-invert", &mut self.staged.default_invert_match);
+                help::show_help(ui, help::SETTINGS_DEFAULT_QUERY_HELP);
+
+                ui.table_next_column();
+                ui.text("Min query length: ");
+                ui.table_next_column();
+                if ui.input_int("##min-query-len", &mut self.staged.min_query_len).build() {
+                    self.staged.min_query_len = self.staged.min_query_len.max(0);
+                }
+                help::show_help(ui, help::SETTINGS_MIN_QUERY_LEN_HELP);
+
+                ui.table_next_column();
+                ui.text("Max line length: ");
+                ui.table_next_column();
+                if ui.input_int("##max-line-len", &mut self.staged.max_display_line_len).build() {
+                    self.staged.max_display_line_len = clamp_display_line_len(self.staged.max_display_line_len);
+                }
+                help::show_help(ui, help::SETTINGS_MAX_LINE_LEN_HELP);
+
                 ui.table_next_column();
-                ui.text("Editor Path: ");
+                ui.text("Max paragraph lines: ");
                 ui.table_next_column();
-                ui.input_text("##editor", &mut self.settings.editor_path).build();
-                help::show_help(ui, help::SETTINGS_EDITOR_HELP);
+                if ui.input_int("##max-paragraph-lines", &mut self.staged.max_paragraph_lines).build() {
+                    self.staged.max_paragraph_lines = self.staged.max_paragraph_lines.max(1);
+                }
+                help::show_help(ui, help::SETTINGS_MAX_PARAGRAPH_LINES_HELP);
+
+                ui.table_next_column();
+                ui.text("Editor: ");
+                ui.table_next_column();
+                let names: Vec<&str> = self.staged.editor_presets.iter().map(|preset| preset.name.as_str()).collect();
+                let mut active = self.staged.active_editor;
+                {
+                    let _w = ui.push_item_width(200.0);
+                    if ui.combo_simple_string("##editor-preset", &mut active, &names) {
+                        self.staged.active_editor = active;
+                    }
+                }
+                ui.same_line();
+                if ui.button("+##editor-preset") {
+                    let name = format!("Editor {}", self.staged.editor_presets.len() + 1);
+                    self.staged.editor_presets.push(EditorPreset { name, command: String::new() });
+                    self.staged.active_editor = self.staged.editor_presets.len() - 1;
+                }
+                ui.same_line();
+                if ui.button("-##editor-preset") && self.staged.active_editor < self.staged.editor_presets.len() {
+                    self.staged.editor_presets.remove(self.staged.active_editor);
+                    self.staged.active_editor = self.staged.active_editor.saturating_sub(1);
+                }
+                help::show_help(ui, help::SETTINGS_EDITOR_PRESETS_HELP);
+
+                if let Some(preset) = self.staged.editor_presets.get_mut(self.staged.active_editor) {
+                    ui.table_next_column();
+                    ui.text("Editor Name: ");
+                    ui.table_next_column();
+                    ui.input_text("##editor-name", &mut preset.name).build();
+
+                    ui.table_next_column();
+                    ui.text("Editor Path: ");
+                    ui.table_next_column();
+                    ui.input_text("##editor", &mut preset.command).build();
+                    help::show_help(ui, help::SETTINGS_EDITOR_HELP);
+                }
+
+                ui.table_next_column();
+                ui.text("Run editor in file directory: ");
+                ui.table_next_column();
+                ui.checkbox("##editor-dir", &mut self.staged.editor_in_file_dir);
+                help::show_help(ui, help::SETTINGS_EDITOR_DIR_HELP);
+
+                ui.table_next_column();
+                ui.text("Editor per extension: ");
+                ui.table_next_column();
+                let mut extensions: Vec<String> = self.staged.extension_editors.keys().cloned().collect();
+                extensions.sort();
+                for extension in extensions.iter() {
+                    let _stack = ui.push_id(extension.as_str());
+                    ui.text(format!(".{}", extension));
+                    ui.same_line_with_pos(80.0);
+                    if let Some(command) = self.staged.extension_editors.get_mut(extension) {
+                        let _w = ui.push_item_width(-40.0);
+                        ui.input_text("##extension-editor", command).build();
+                    }
+                    ui.same_line();
+                    if ui.button("-##extension-editor") {
+                        self.staged.extension_editors.remove(extension);
+                    }
+                }
+                {
+                    let _w = ui.push_item_width(70.0);
+                    ui.input_text("##new-extension", &mut self.new_extension).hint("md").build();
+                }
+                ui.same_line();
+                let extension = normalize_extension(&self.new_extension);
+                ui.disabled(extension.is_empty(), || {
+                    if ui.button("+##extension-editor") {
+                        let command = self.staged.editor_command().to_string();
+                        self.staged.extension_editors.entry(extension).or_insert(command);
+                        self.new_extension.clear();
+                    }
+                });
+                help::show_help(ui, help::SETTINGS_EXTENSION_EDITORS_HELP);
+
+                ui.table_next_column();
+                ui.text("Double click on a result: ");
+                ui.table_next_column();
+                let mut double_click = DoubleClickAction::ALL.iter().position(|action| *action == self.staged.double_click_action).unwrap_or(0);
+                if ui.combo_simple_string("##double-click", &mut double_click, &DoubleClickAction::NAMES) {
+                    self.staged.double_click_action = DoubleClickAction::ALL[double_click];
+                }
+                help::show_help(ui, help::SETTINGS_DOUBLE_CLICK_HELP);
+
+                ui.table_next_column();
+                ui.text("Location URL: ");
+                ui.table_next_column();
+                ui.input_text("##location-url", &mut self.staged.location_url).build();
+                help::show_help(ui, help::SETTINGS_LOCATION_URL_HELP);
+
+                ui.table_next_column();
+                ui.text("Terminal: ");
+                ui.table_next_column();
+                ui.input_text("##terminal", &mut self.staged.terminal).build();
+                help::show_help(ui, help::SETTINGS_TERMINAL_HELP);
+
+                ui.table_next_column();
+                ui.text("Hex editor: ");
+                ui.table_next_column();
+                ui.input_text("##hex-editor", &mut self.staged.hex_editor).build();
+                help::show_help(ui, help::SETTINGS_HEX_EDITOR_HELP);
+
+                ui.table_next_column();
+                ui.text("Show timing (debug): ");
+                ui.table_next_column();
+                ui.checkbox("##show-timing", &mut self.staged.show_timing);
+                help::show_help(ui, help::TIMING_USAGE);
+
+                ui.table_next_column();
+                ui.text("Log every search (debug): ");
+                ui.table_next_column();
+                ui.checkbox("##verbose-search", &mut self.staged.verbose_search);
+                help::show_help(ui, help::SETTINGS_VERBOSE_SEARCH_HELP);
+
+                ui.table_next_column();
+                ui.text("Show memory of results: ");
+                ui.table_next_column();
+                ui.checkbox("##show-memory", &mut self.staged.show_memory);
+
+                ui.table_next_column();
+                ui.text("Max memory of results (MB): ");
+                ui.table_next_column();
+                if ui.input_int("##max-results-memory", &mut self.staged.max_results_memory).build() {
+                    self.staged.max_results_memory = self.staged.max_results_memory.max(0);
+                }
+                help::show_help(ui, help::SETTINGS_MAX_RESULTS_MEMORY_HELP);
+
+                ui.table_next_column();
+                ui.text("Autosave delay (secs): ");
+                ui.table_next_column();
+                if ui.input_int("##autosave-delay", &mut self.staged.autosave_delay).build() {
+                    self.staged.autosave_delay = self.staged.autosave_delay.max(0);
+                }
+                help::show_help(ui, help::SETTINGS_AUTOSAVE_DELAY_HELP);
             }
+
+            ui.separator();
+            ok = ui.button("OK");
+            ui.same_line();
+            apply = ui.button("Apply");
+            ui.same_line();
+            cancel = ui.button("Cancel");
         });
+
+        if ok || apply {
+            self.apply();
+        }
+        if ok || cancel {
+            self.opened = false;
+        } else if !self.opened {
+            // Closed from the title bar this frame.
+            cancel = true;
+        }
+        if cancel {
+            self.cancel();
+        }
     }
 }
 
@@ -214,3 +1055,102 @@ impl Drop for SettingsWindow {
         self.save_results();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_editor_path_to_presets() {
+        let mut settings: Settings = serde_json::from_str(r#"{ "editor_path": "subl {file}:{line}" }"#).unwrap();
+        settings.migrate_editor_path();
+        assert_eq!(settings.editor_command(), "subl {file}:{line}");
+        assert!(settings.editor_path.is_empty());
+        assert!(!serde_json::to_string(&settings).unwrap().contains("editor_path"));
+
+        let mut settings = Settings::default();
+        settings.migrate_editor_path();
+        assert!(settings.editor_presets.is_empty());
+        assert_eq!(settings.editor_command(), "");
+    }
+
+    #[test]
+    fn editor_of_the_file_extension() {
+        let mut settings = Settings::default();
+        settings.editor_presets.push(EditorPreset { name: String::from("Default"), command: String::from("vim +{line} {file}") });
+        settings.extension_editors.insert(normalize_extension(".MD"), String::from("typora {file}"));
+        settings.extension_editors.insert(String::from("txt"), String::new());
+
+        assert_eq!(settings.editor_command_for(Path::new("docs/README.md")), "typora {file}");
+        assert_eq!(settings.editor_command_for(Path::new("NOTES.Md")), "typora {file}");
+        assert_eq!(settings.editor_command_for(Path::new("src/main.rs")), "vim +{line} {file}");
+        assert_eq!(settings.editor_command_for(Path::new("notes.txt")), "vim +{line} {file}");
+        assert_eq!(settings.editor_command_for(Path::new("Makefile")), "vim +{line} {file}");
+    }
+
+    #[test]
+    fn migrate_search_binary_to_binary_mode() {
+        let mut settings: Settings = serde_json::from_str(r#"{ "search_binary": true }"#).unwrap();
+        settings.migrate_search_binary();
+        assert!(settings.binary_mode == BinaryMode::Search);
+        assert!(!serde_json::to_string(&settings).unwrap().contains("search_binary"));
+
+        let mut settings: Settings = serde_json::from_str(r#"{ "binary_mode": "Convert" }"#).unwrap();
+        settings.migrate_search_binary();
+        assert!(settings.binary_mode == BinaryMode::Convert);
+    }
+
+    #[test]
+    fn only_staged_changes_applied() {
+        let unchanged = Settings::default();
+        let mut staged = unchanged.clone();
+        staged.context_lines = 3;
+        staged.style_color = StyleColor::Light;
+
+        // Changed outside of the window since it was staged.
+        let mut current = unchanged.clone();
+        current.group_by_file = true;
+        current.layout = String::from("[Window][Settings]");
+
+        let merged = merge_changes(&current, &unchanged, &staged).unwrap();
+        assert_eq!(merged.context_lines, 3);
+        assert!(merged.style_color == StyleColor::Light);
+        assert!(merged.group_by_file);
+        assert_eq!(merged.layout, "[Window][Settings]");
+    }
+
+    #[test]
+    fn migrate_follow_symlink_to_symlink_mode() {
+        let mut settings: Settings = serde_json::from_str(r#"{ "version": 1, "follow_symlink": true }"#).unwrap();
+        settings.migrate();
+        assert!(settings.symlink_mode == SymlinkMode::FollowAll);
+        assert!(!serde_json::to_string(&settings).unwrap().contains("follow_symlink"));
+
+        let mut settings: Settings = serde_json::from_str(r#"{ "version": 1 }"#).unwrap();
+        settings.migrate();
+        assert!(settings.symlink_mode == SymlinkMode::NoFollow);
+    }
+
+    #[test]
+    fn migrate_settings_without_version() {
+        let mut settings: Settings = serde_json::from_str(r#"{ "editor_path": "vim +{line} {file}", "search_binary": true, "context_lines": 2 }"#).unwrap();
+        assert_eq!(settings.version, 0);
+        settings.migrate();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.editor_command(), "vim +{line} {file}");
+        assert!(settings.binary_mode == BinaryMode::Search);
+        assert_eq!(settings.context_lines, 2);
+
+        let content = serde_json::to_string(&settings).unwrap();
+        assert!(content.contains(&format!("\"version\":{}", SETTINGS_VERSION)), "{}", content);
+    }
+
+    #[test]
+    fn migrations_are_skipped_for_current_version() {
+        let content = format!(r#"{{ "version": {}, "search_binary": true, "binary_mode": "Skip" }}"#, SETTINGS_VERSION);
+        let mut settings: Settings = serde_json::from_str(&content).unwrap();
+        settings.migrate();
+        assert!(settings.binary_mode == BinaryMode::Skip);
+        assert_eq!(Settings::default().version, SETTINGS_VERSION);
+    }
+}
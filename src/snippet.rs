@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::{fs, path::PathBuf};
+
+/// Temporary files holding snippets of the results, such that they can be shared with
+/// other applications. They are removed when dropped, that is when the application exits.
+pub struct SnippetFiles {
+    dir: PathBuf,
+    count: usize,
+}
+
+impl SnippetFiles {
+    pub fn new() -> Self {
+        // Each instance of the application has its own directory to not remove the files of the others.
+        let dir = std::env::temp_dir().join(format!("search-snippets-{}", std::process::id()));
+        Self { dir, count: 0 }
+    }
+
+    /// Write `text` to a new file named after the file it was found in.
+    pub fn create(&mut self, source: &str, text: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+
+        let name = source.rsplit(['/', '\\']).next().unwrap_or(source);
+        self.count += 1;
+        let path = self.dir.join(format!("{}-{}.txt", self.count, name));
+        fs::write(&path, text)?;
+        return Ok(path);
+    }
+}
+
+impl Drop for SnippetFiles {
+    fn drop(&mut self) {
+        if self.count != 0 {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_removed_on_drop() {
+        let mut snippets = SnippetFiles::new();
+        snippets.dir = std::env::temp_dir().join(format!("search-snippets-test-{}", std::process::id()));
+
+        let first = snippets.create("src/main.rs", "fn main() {}\n").unwrap();
+        let second = snippets.create(r"C:\src\main.rs", "fn main() {}\n").unwrap();
+        assert_ne!(first, second);
+        assert!(first.ends_with("1-main.rs.txt"));
+        assert_eq!(fs::read_to_string(&second).unwrap(), "fn main() {}\n");
+
+        let dir = snippets.dir.clone();
+        drop(snippets);
+        assert!(!dir.exists());
+    }
+}
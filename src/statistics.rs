@@ -0,0 +1,177 @@
+use imgui::*;
+use std::collections::HashMap;
+
+use crate::search::EntryKind;
+
+/// Number of files listed in "Top files".
+const TOP_FILES: usize = 10;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ExtensionStatistics {
+    pub extension: String,
+    pub files: usize,
+    pub matches: usize,
+}
+
+/// Summary of the results of a tab, computed once rather than every frame.
+#[derive(Debug, Default, PartialEq)]
+pub struct ResultStatistics {
+    pub files: usize,
+    pub matched_lines: usize,
+    pub matches: usize,
+    /// Sorted by number of matches, the most first.
+    pub extensions: Vec<ExtensionStatistics>,
+    /// Files with the most matches, with their number of matches.
+    pub top_files: Vec<(String, usize)>,
+}
+
+impl ResultStatistics {
+    /// Compute the statistics from the path, kind and number of matches of every result.
+    /// Lines matched without a match, such as inverted matches, count as one match.
+    pub fn compute<'a>(results: impl IntoIterator<Item = (&'a str, EntryKind, usize)>) -> Self {
+        let mut stats = Self::default();
+        let mut per_file: HashMap<&str, usize> = HashMap::new();
+        for (path, kind, matches) in results.into_iter() {
+            let file_matches = per_file.entry(path).or_insert(0);
+            if kind == EntryKind::Context {
+                continue;
+            }
+
+            let matches = matches.max(1);
+            *file_matches += matches;
+            stats.matches += matches;
+            if kind == EntryKind::Match {
+                stats.matched_lines += 1;
+            }
+        }
+
+        stats.files = per_file.len();
+
+        let mut extensions: HashMap<String, ExtensionStatistics> = HashMap::new();
+        for (path, matches) in per_file.iter() {
+            let extension = std::path::Path::new(path)
+                .extension()
+                .map_or(String::from("(none)"), |extension| extension.to_string_lossy().into_owned());
+            let entry = extensions.entry(extension.clone()).or_insert_with(|| ExtensionStatistics { extension, ..Default::default() });
+            entry.files += 1;
+            entry.matches += matches;
+        }
+
+        stats.extensions = extensions.into_values().collect();
+        stats.extensions.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.extension.cmp(&b.extension)));
+
+        let mut files: Vec<(&str, usize)> = per_file.into_iter().collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        stats.top_files = files.into_iter().take(TOP_FILES).map(|(path, matches)| (path.to_owned(), matches)).collect();
+
+        return stats;
+    }
+
+    pub fn average_matches_per_file(&self) -> f32 {
+        if self.files == 0 {
+            return 0.0;
+        }
+        return self.matches as f32 / self.files as f32;
+    }
+}
+
+pub struct StatisticsWindow {
+    opened: bool,
+    statistics: ResultStatistics,
+}
+
+impl StatisticsWindow {
+    pub fn new() -> Self {
+        Self { opened: false, statistics: ResultStatistics::default() }
+    }
+
+    pub fn open(&mut self, statistics: ResultStatistics) {
+        self.opened = true;
+        self.statistics = statistics;
+    }
+
+    /// Returns true if the statistics must be computed again from the current results.
+    pub fn draw_statistics(&mut self, ui: &Ui) -> bool {
+        if !self.opened {
+            return false;
+        }
+
+        let display_size = ui.io().display_size;
+        let window_size = [600.0, 500.0];
+        let pos_x = (display_size[0] / 2.0) - (window_size[0] / 2.0);
+        let pos_y = (display_size[1] / 2.0) - (window_size[1] / 2.0);
+
+        let window = ui
+            .window("Statistics")
+            .size(window_size, Condition::Appearing)
+            .position([pos_x, pos_y], Condition::Appearing)
+            .collapsible(false)
+            .opened(&mut self.opened);
+
+        let stats = &self.statistics;
+        let mut refresh = false;
+        window.build(|| {
+            refresh = ui.button("Refresh");
+            ui.separator();
+
+            ui.text(format!("Files: {}", stats.files));
+            ui.text(format!("Matched lines: {}", stats.matched_lines));
+            ui.text(format!("Matches: {}", stats.matches));
+            ui.text(format!("Average matches per file: {:.1}", stats.average_matches_per_file()));
+
+            ui.separator();
+            ui.text("Matches per extension");
+            let max_matches = stats.extensions.first().map_or(1, |extension| extension.matches.max(1));
+            for extension in stats.extensions.iter() {
+                let fraction = extension.matches as f32 / max_matches as f32;
+                let text = format!("{} ({} matches in {} files)", extension.extension, extension.matches, extension.files);
+                ProgressBar::new(fraction).overlay_text(text).build(ui);
+            }
+
+            ui.separator();
+            ui.text("Top files");
+            if let Some(_t) = ui.begin_table_with_flags("##top-files", 2, TableFlags::SIZING_FIXED_FIT) {
+                for (path, matches) in stats.top_files.iter() {
+                    ui.table_next_column();
+                    ui.text(format!("{}", matches));
+                    ui.table_next_column();
+                    ui.text(path);
+                }
+            }
+        });
+
+        return refresh;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_statistics() {
+        let results = [
+            ("src/main.rs", EntryKind::Match, 2),
+            ("src/main.rs", EntryKind::Context, 0),
+            ("src/main.rs", EntryKind::Match, 1),
+            ("src/lib.rs", EntryKind::Match, 1),
+            ("README", EntryKind::FileName, 1),
+            ("notes.txt", EntryKind::Match, 0),
+        ];
+
+        let stats = ResultStatistics::compute(results);
+        assert_eq!((stats.files, stats.matched_lines, stats.matches), (4, 4, 6));
+        assert_eq!(stats.average_matches_per_file(), 1.5);
+
+        let extensions: Vec<(&str, usize, usize)> = stats
+            .extensions
+            .iter()
+            .map(|extension| (extension.extension.as_str(), extension.files, extension.matches))
+            .collect();
+        assert_eq!(extensions, vec![("rs", 2, 4), ("(none)", 1, 1), ("txt", 1, 1)]);
+        assert_eq!(stats.top_files[0], (String::from("src/main.rs"), 3));
+        assert_eq!(stats.top_files.len(), 4);
+
+        assert_eq!(ResultStatistics::compute([]).average_matches_per_file(), 0.0);
+    }
+}
@@ -1,15 +1,27 @@
+use anyhow::{bail, Result};
 use glium::glutin;
 use glium::glutin::event::{Event, WindowEvent};
 use glium::glutin::event_loop::{ControlFlow, EventLoop};
 use glium::glutin::window::WindowBuilder;
+use glium::texture::{RawImage2d, Texture2d};
+use glium::uniforms::SamplerBehavior;
 use glium::{Display, Surface};
-use imgui::{ConfigFlags, Context, FontConfig, FontGlyphRanges, FontSource, Ui};
-use imgui_glium_renderer::Renderer;
+use imgui::{ConfigFlags, Context, FontConfig, FontGlyphRanges, FontId, FontSource, TextureId, Ui};
+use imgui_glium_renderer::{Renderer, Texture};
+use std::{cell::Cell, rc::Rc};
 use imgui_winit_support::{HiDpiMode, WinitPlatform};
 use std::path::Path;
 use std::time::Instant;
 
-use crate::clipboard;
+use crate::{clipboard, sys};
+
+/// Fonts loaded in the atlas. The monospace font is the default one, the proportional font
+/// is the same font unless it was loaded for the results.
+#[derive(Clone, Copy)]
+pub struct Fonts {
+    pub monospace: FontId,
+    pub proportional: FontId,
+}
 
 pub struct System {
     pub event_loop: EventLoop<()>,
@@ -17,9 +29,20 @@ pub struct System {
     pub imgui: Context,
     pub platform: WinitPlatform,
     pub renderer: Renderer,
+    pub fonts: Fonts,
+    /// Whether the window is kept above the others, applied to the window at the end of the frame.
+    pub always_on_top: Rc<Cell<bool>>,
+    /// Hotkey hiding and showing the window.
+    pub global_hotkey: Option<sys::GlobalHotkey>,
+    /// Set when the window is shown again by the global hotkey.
+    pub restored: Rc<Cell<bool>>,
+    /// Set when the window is closed, the application decides whether to quit.
+    pub close_requested: Rc<Cell<bool>>,
 }
 
-pub fn init(title: &str) -> System {
+/// Create the window. The Japanese glyphs are only loaded with `cjk_glyphs`, as they make
+/// the font atlas much larger.
+pub fn init(title: &str, cjk_glyphs: bool, proportional_font: bool) -> System {
     let title = match Path::new(&title).file_name() {
         Some(file_name) => file_name.to_str().unwrap(),
         None => title,
@@ -38,7 +61,7 @@ pub fn init(title: &str) -> System {
     if let Some(backend) = clipboard::init() {
         imgui.set_clipboard_backend(backend);
     } else {
-        eprintln!("Failed to initialize clipboard");
+        log!("Failed to initialize clipboard");
     }
 
     let mut platform = WinitPlatform::init(&mut imgui);
@@ -59,7 +82,22 @@ pub fn init(title: &str) -> System {
         platform.attach_window(imgui.io_mut(), window, dpi_mode);
     }
 
-    imgui.fonts().add_font(&[
+    // Japanese glyphs are merged in every font loaded.
+    let japanese = || cjk_glyphs.then(|| FontSource::TtfData {
+        data: include_bytes!("../resources/mplus-1p-regular.ttf"),
+        size_pixels: 15.0,
+        config: Some(FontConfig {
+            // Oversampling font helps improve text rendering at
+            // expense of larger font atlas texture.
+            oversample_h: 4,
+            oversample_v: 4,
+            // Range of glyphs to rasterize
+            glyph_ranges: FontGlyphRanges::japanese(),
+            ..FontConfig::default()
+        }),
+    });
+
+    let mut sources = vec![
         FontSource::TtfData {
             data: include_bytes!("../resources/Lucon.ttf"),
             size_pixels: 12.0,
@@ -76,20 +114,28 @@ pub fn init(title: &str) -> System {
                 ..FontConfig::default()
             }),
         },
-        FontSource::TtfData {
-            data: include_bytes!("../resources/mplus-1p-regular.ttf"),
-            size_pixels: 15.0,
-            config: Some(FontConfig {
-                // Oversampling font helps improve text rendering at
-                // expense of larger font atlas texture.
-                oversample_h: 4,
-                oversample_v: 4,
-                // Range of glyphs to rasterize
-                glyph_ranges: FontGlyphRanges::japanese(),
-                ..FontConfig::default()
-            }),
-        },
-    ]);
+    ];
+    sources.extend(japanese());
+    let monospace = imgui.fonts().add_font(&sources);
+
+    // Only loaded when the results use it, as the glyphs merged in it double the size of the atlas.
+    let proportional = if proportional_font {
+        let mut sources = vec![
+            FontSource::TtfData {
+                data: include_bytes!("../resources/Roboto-Regular.ttf"),
+                size_pixels: 14.0,
+                config: Some(FontConfig {
+                    oversample_h: 4,
+                    oversample_v: 4,
+                    ..FontConfig::default()
+                }),
+            },
+        ];
+        sources.extend(japanese());
+        imgui.fonts().add_font(&sources)
+    } else {
+        monospace
+    };
 
     let renderer = Renderer::init(&mut imgui, &display).expect("Failed to initialize renderer");
 
@@ -99,10 +145,36 @@ pub fn init(title: &str) -> System {
         imgui,
         platform,
         renderer,
+        fonts: Fonts { monospace, proportional },
+        always_on_top: Rc::new(Cell::new(false)),
+        global_hotkey: None,
+        restored: Rc::new(Cell::new(false)),
+        close_requested: Rc::new(Cell::new(false)),
     }
 }
 
 impl System {
+    /// Decode a RGBA PNG image and upload it as a texture usable with `ui.image_button`.
+    pub fn load_texture(&mut self, png_data: &[u8]) -> Result<TextureId> {
+        let decoder = png::Decoder::new(png_data);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            bail!("Only 8 bits RGBA images are supported");
+        }
+
+        buffer.truncate(info.buffer_size());
+        let image = RawImage2d::from_raw_rgba(buffer, (info.width, info.height));
+        let texture = Texture2d::new(&self.display, image)?;
+        let texture = Texture {
+            texture: Rc::new(texture),
+            sampler: SamplerBehavior::default(),
+        };
+
+        Ok(self.renderer.textures().insert(texture))
+    }
+
     pub fn main_loop<F: FnMut(&mut bool, &mut Ui) + 'static>(self, mut run_ui: F) {
         let System {
             event_loop,
@@ -110,12 +182,21 @@ impl System {
             mut imgui,
             mut platform,
             mut renderer,
+            always_on_top,
+            global_hotkey,
+            restored,
+            close_requested,
             ..
         } = self;
 
         // Allow us to use PageUp and PageDown to navigate in the result window.
         imgui.io_mut().config_flags.set(ConfigFlags::NAV_ENABLE_KEYBOARD, true);
 
+        let mut is_always_on_top = false;
+        // Shown while the window is hidden, such that it can be shown again without the hotkey.
+        let mut tray_icon: Option<sys::TrayIcon> = None;
+        let mut is_visible = true;
+        let mut is_focused = true;
         let mut last_frame = Instant::now();
         event_loop.run(move |event, _, control_flow| {
             match event {
@@ -126,6 +207,36 @@ impl System {
                 }
                 Event::MainEventsCleared => {
                     let gl_window = display.gl_window();
+
+                    // This is checked here, because the window isn't redrawn while it's hidden.
+                    let hotkey_triggered = global_hotkey.as_ref().is_some_and(|hotkey| hotkey.triggered());
+                    let tray_icon_clicked = tray_icon.as_ref().is_some_and(|icon| icon.clicked());
+                    if hotkey_triggered || tray_icon_clicked {
+                        let window = gl_window.window();
+                        if hotkey_triggered && is_visible && is_focused {
+                            match sys::TrayIcon::add("Search") {
+                                Ok(icon) => {
+                                    tray_icon = Some(icon);
+                                    window.set_visible(false);
+                                    is_visible = false;
+                                }
+                                Err(err) => {
+                                    // A hidden window without a tray icon could only be found again
+                                    // with the hotkey, so it's only minimized.
+                                    log!("{}, minimizing the window rather than hiding it", err);
+                                    window.set_minimized(true);
+                                }
+                            }
+                        } else {
+                            tray_icon = None;
+                            window.set_visible(true);
+                            window.set_minimized(false);
+                            window.focus_window();
+                            is_visible = true;
+                            restored.set(true);
+                        }
+                    }
+
                     platform
                         .prepare_frame(imgui.io_mut(), gl_window.window())
                         .expect("Failed to prepare frame");
@@ -141,8 +252,17 @@ impl System {
                     }
 
                     let gl_window = display.gl_window();
+                    if always_on_top.get() != is_always_on_top {
+                        // This is ignored on the platforms that don't support it.
+                        is_always_on_top = always_on_top.get();
+                        gl_window.window().set_always_on_top(is_always_on_top);
+                    }
+
+                    // Cleared with the background of the windows of the current style, such that
+                    // the edges of the window don't flash white with the dark styles.
+                    let [red, green, blue, _] = ui.style_color(imgui::StyleColor::WindowBg);
                     let mut target = display.draw();
-                    target.clear_color_srgb(1.0, 1.0, 1.0, 1.0);
+                    target.clear_color_srgb(red, green, blue, 1.0);
                     platform.prepare_render(ui, gl_window.window());
                     let draw_data = imgui.render();
                     renderer
@@ -153,11 +273,19 @@ impl System {
                 Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
                     ..
-                } => *control_flow = ControlFlow::Exit,
+                } => close_requested.set(true),
                 Event::WindowEvent {
                     event: WindowEvent::Resized(new_size),
                     ..
                 } => imgui.io_mut().display_size = [new_size.width as f32, new_size.height as f32],
+                Event::WindowEvent {
+                    event: WindowEvent::Focused(focused),
+                    ..
+                } => {
+                    is_focused = focused;
+                    let gl_window = display.gl_window();
+                    platform.handle_event(imgui.io_mut(), gl_window.window(), &event);
+                }
                 event => {
                     let gl_window = display.gl_window();
                     platform.handle_event(imgui.io_mut(), gl_window.window(), &event);
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, bail, Result};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Open the file with the application associated to its type.
+pub fn open_file(path: &Path) -> Result<()> {
+    let program = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    let mut child = Command::new(program).arg(path).spawn().map_err(|err| {
+        anyhow!("Failed to open '{}' with '{}', error: {}", path.to_string_lossy(), program, err)
+    })?;
+
+    // The launcher exits quickly, but must be waited for to not leave a zombie process.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Ask the user to pick a folder with the dialog of the desktop. Blocks until the dialog is
+/// closed, returns `None` if it was cancelled.
+pub fn pick_folder() -> Result<Option<PathBuf>> {
+    let dialogs: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("osascript", &["-e", "POSIX path of (choose folder)"])]
+    } else {
+        &[("zenity", &["--file-selection", "--directory"]), ("kdialog", &["--getexistingdirectory"])]
+    };
+
+    for (program, args) in dialogs.iter() {
+        let output = match Command::new(program).args(args.iter()).output() {
+            Ok(output) => output,
+            // Not installed, try the next one.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => bail!("Failed to open the folder dialog '{}', error: {}", program, err),
+        };
+
+        // The dialogs exit with an error when they are cancelled.
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let path = String::from_utf8_lossy(&output.stdout).trim_end_matches(['\n', '\r']).to_owned();
+        return Ok(Some(PathBuf::from(path)).filter(|path| !path.as_os_str().is_empty()));
+    }
+
+    bail!("No folder dialog is available, install zenity or kdialog")
+}
+
+/// Global hotkeys are not supported on this platform, registering one always fails.
+pub struct GlobalHotkey;
+
+impl GlobalHotkey {
+    pub fn register(_hotkey: &crate::hotkeys::HotkeyCombination) -> Result<Self> {
+        Err(anyhow!("Global hotkeys are not supported on this platform"))
+    }
+
+    pub fn triggered(&self) -> bool {
+        false
+    }
+}
+
+/// Tray icons are not supported on this platform, adding one always fails.
+pub struct TrayIcon;
+
+impl TrayIcon {
+    pub fn add(_tooltip: &str) -> Result<Self> {
+        Err(anyhow!("Tray icons are not supported on this platform"))
+    }
+
+    pub fn clicked(&self) -> bool {
+        false
+    }
+}
+
+/// The console is always available on this platform.
+pub fn attach_parent_console() {}
@@ -0,0 +1,20 @@
+cfg_if::cfg_if! {
+    if #[cfg(windows)] {
+        mod windows;
+        pub use self::windows::{console::*, dialog::*, hotkey::*, shell::*, tray::*};
+    } else {
+        mod dummy;
+        pub use self::dummy::*;
+    }
+}
+
+use anyhow::Result;
+use std::{path::PathBuf, sync::mpsc};
+
+/// Show the dialog picking a folder on its own thread, such that the window keeps being drawn
+/// while it's opened. The picked folder is received once the dialog is closed.
+pub fn spawn_pick_folder() -> mpsc::Receiver<Result<Option<PathBuf>>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || tx.send(pick_folder()));
+    return rx;
+}
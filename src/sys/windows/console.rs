@@ -0,0 +1,8 @@
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+/// Attach to the console of the parent process, such that the output is visible when
+/// the application is built for the windows subsystem and started from a terminal.
+pub fn attach_parent_console() {
+    // Fails if the process already has a console, which is fine.
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
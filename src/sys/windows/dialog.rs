@@ -0,0 +1,77 @@
+use anyhow::{anyhow, bail, Result};
+use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf, ptr};
+use winapi::{
+    shared::{
+        minwindef::LPVOID,
+        winerror::{HRESULT_FROM_WIN32, ERROR_CANCELLED, SUCCEEDED},
+        wtypesbase::CLSCTX_INPROC_SERVER,
+    },
+    um::{
+        combaseapi::{CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize},
+        objbase::COINIT_APARTMENTTHREADED,
+        shobjidl::{IFileOpenDialog, FOS_FORCEFILESYSTEM, FOS_PICKFOLDERS},
+        shobjidl_core::{CLSID_FileOpenDialog, IShellItem, SIGDN_FILESYSPATH},
+    },
+    Interface,
+};
+
+/// Ask the user to pick a folder with the dialog of the system. Blocks until the dialog is
+/// closed, returns `None` if it was cancelled.
+pub fn pick_folder() -> Result<Option<PathBuf>> {
+    // The dialog is shown from its own thread, which must initialize COM.
+    let initialized = unsafe { SUCCEEDED(CoInitializeEx(ptr::null_mut(), COINIT_APARTMENTTHREADED)) };
+    let picked = unsafe { show_folder_dialog() };
+    if initialized {
+        unsafe { CoUninitialize() };
+    }
+    return picked;
+}
+
+unsafe fn show_folder_dialog() -> Result<Option<PathBuf>> {
+    let mut dialog: *mut IFileOpenDialog = ptr::null_mut();
+    let hr = CoCreateInstance(
+        &CLSID_FileOpenDialog,
+        ptr::null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &IFileOpenDialog::uuidof(),
+        &mut dialog as *mut *mut IFileOpenDialog as *mut LPVOID,
+    );
+    if !SUCCEEDED(hr) {
+        bail!("Failed to create the folder dialog, error: {:#x}", hr);
+    }
+
+    let mut options = 0;
+    (*dialog).GetOptions(&mut options);
+    (*dialog).SetOptions(options | FOS_PICKFOLDERS | FOS_FORCEFILESYSTEM);
+
+    let hr = (*dialog).Show(ptr::null_mut());
+    let picked = if hr == HRESULT_FROM_WIN32(ERROR_CANCELLED) {
+        Ok(None)
+    } else if !SUCCEEDED(hr) {
+        Err(anyhow!("Failed to show the folder dialog, error: {:#x}", hr))
+    } else {
+        picked_path(&*dialog).map(Some)
+    };
+
+    (*dialog).Release();
+    return picked;
+}
+
+unsafe fn picked_path(dialog: &IFileOpenDialog) -> Result<PathBuf> {
+    let mut item: *mut IShellItem = ptr::null_mut();
+    if !SUCCEEDED(dialog.GetResult(&mut item)) {
+        bail!("Failed to get the picked folder");
+    }
+
+    let mut name = ptr::null_mut();
+    let hr = (*item).GetDisplayName(SIGDN_FILESYSPATH, &mut name);
+    (*item).Release();
+    if !SUCCEEDED(hr) {
+        bail!("The picked folder has no path, error: {:#x}", hr);
+    }
+
+    let len = (0..).take_while(|idx| *name.offset(*idx) != 0).count();
+    let path = OsString::from_wide(std::slice::from_raw_parts(name, len));
+    CoTaskMemFree(name as LPVOID);
+    return Ok(PathBuf::from(path));
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use std::{
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+use winapi::um::{
+    processthreadsapi::GetCurrentThreadId,
+    winuser::{
+        GetMessageW, PostThreadMessageW, RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+        MOD_SHIFT, MOD_WIN, MSG, VK_F1, VK_SPACE, WM_HOTKEY, WM_QUIT,
+    },
+};
+
+use crate::hotkeys::{HotkeyCombination, HotkeyKey};
+
+const HOTKEY_ID: i32 = 1;
+
+/// Hotkey registered for the whole system, which is triggered even if the window isn't focused.
+pub struct GlobalHotkey {
+    triggered: Arc<AtomicBool>,
+    thread_id: u32,
+}
+
+impl GlobalHotkey {
+    pub fn register(hotkey: &HotkeyCombination) -> Result<Self> {
+        let mut modifiers = MOD_NOREPEAT;
+        if hotkey.ctrl {
+            modifiers |= MOD_CONTROL;
+        }
+        if hotkey.alt {
+            modifiers |= MOD_ALT;
+        }
+        if hotkey.shift {
+            modifiers |= MOD_SHIFT;
+        }
+        if hotkey.win {
+            modifiers |= MOD_WIN;
+        }
+
+        let key = match hotkey.key {
+            HotkeyKey::Char(value) => value as i32,
+            HotkeyKey::Function(number) => VK_F1 + (number as i32 - 1),
+            HotkeyKey::Space => VK_SPACE,
+        };
+
+        // The hotkey messages are posted to the thread that registered it, so a thread
+        // is dedicated to receive them, rather than depending on the event loop of the window.
+        let triggered = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let flag = Arc::clone(&triggered);
+        std::thread::spawn(move || unsafe {
+            if RegisterHotKey(ptr::null_mut(), HOTKEY_ID, modifiers as u32, key as u32) == 0 {
+                let _ = tx.send(Err(std::io::Error::last_os_error()));
+                return;
+            }
+
+            let _ = tx.send(Ok(GetCurrentThreadId()));
+
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+                if msg.message == WM_HOTKEY {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            }
+
+            UnregisterHotKey(ptr::null_mut(), HOTKEY_ID);
+        });
+
+        let thread_id = rx
+            .recv()?
+            .map_err(|err| anyhow!("Failed to register the hotkey, error: {}", err))?;
+        Ok(Self { triggered, thread_id })
+    }
+
+    /// Returns whether the hotkey was pressed since the last call.
+    pub fn triggered(&self) -> bool {
+        self.triggered.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for GlobalHotkey {
+    fn drop(&mut self) {
+        unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+    }
+}
@@ -0,0 +1,5 @@
+pub mod console;
+pub mod dialog;
+pub mod hotkey;
+pub mod shell;
+pub mod tray;
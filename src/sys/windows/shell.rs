@@ -0,0 +1,35 @@
+use anyhow::{bail, Result};
+use std::{
+    ffi::OsStr,
+    os::windows::ffi::OsStrExt,
+    path::Path,
+    ptr,
+};
+use winapi::um::{shellapi::ShellExecuteW, winuser::SW_SHOWNORMAL};
+
+fn to_wide(value: &OsStr) -> Vec<u16> {
+    value.encode_wide().chain(Some(0)).collect()
+}
+
+/// Open the file with the application associated to its type.
+pub fn open_file(path: &Path) -> Result<()> {
+    let operation = to_wide(OsStr::new("open"));
+    let file = to_wide(path.as_os_str());
+    let instance = unsafe {
+        ShellExecuteW(
+            ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+
+    // ShellExecute returns a value greater than 32 on success.
+    if instance as usize <= 32 {
+        bail!("Failed to open '{}' with the default application", path.to_string_lossy());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use std::{
+    ffi::OsStr,
+    os::windows::ffi::OsStrExt,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+};
+use winapi::{
+    shared::{
+        minwindef::{LPARAM, LRESULT, UINT, WPARAM},
+        windef::HWND,
+    },
+    um::{
+        libloaderapi::GetModuleHandleW,
+        processthreadsapi::GetCurrentThreadId,
+        shellapi::{Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW},
+        winuser::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, LoadIconW,
+            PostThreadMessageW, RegisterClassW, TranslateMessage, HWND_MESSAGE, IDI_APPLICATION, MSG, WM_APP,
+            WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_QUIT, WNDCLASSW,
+        },
+    },
+};
+
+/// Message sent to the window of the icon when the icon is clicked.
+const WM_TRAY_ICON: UINT = WM_APP + 1;
+
+/// Set by the window procedure, which can't hold any state, when the icon is clicked.
+static CLICKED: AtomicBool = AtomicBool::new(false);
+
+fn to_wide(value: &str) -> Vec<u16> {
+    OsStr::new(value).encode_wide().chain(Some(0)).collect()
+}
+
+unsafe extern "system" fn tray_window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if msg == WM_TRAY_ICON && matches!(lparam as UINT, WM_LBUTTONUP | WM_LBUTTONDBLCLK) {
+        CLICKED.store(true, Ordering::Relaxed);
+        return 0;
+    }
+    return DefWindowProcW(hwnd, msg, wparam, lparam);
+}
+
+/// Icon in the notification area, shown while the window is hidden such that it can be
+/// shown again by clicking it. The icon is removed once dropped.
+pub struct TrayIcon {
+    thread_id: u32,
+}
+
+impl TrayIcon {
+    pub fn add(tooltip: &str) -> Result<Self> {
+        let tooltip = to_wide(tooltip);
+        CLICKED.store(false, Ordering::Relaxed);
+
+        // The messages of the icon are sent to a window of the thread that created it, so a
+        // thread is dedicated to it, like the global hotkey.
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || unsafe {
+            let instance = GetModuleHandleW(ptr::null());
+            let class_name = to_wide("search-tray-icon");
+            let class = WNDCLASSW {
+                style: 0,
+                lpfnWndProc: Some(tray_window_proc),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: instance,
+                hIcon: ptr::null_mut(),
+                hCursor: ptr::null_mut(),
+                hbrBackground: ptr::null_mut(),
+                lpszMenuName: ptr::null(),
+                lpszClassName: class_name.as_ptr(),
+            };
+            // Fails once the class is registered by a previous icon, which can be reused.
+            RegisterClassW(&class);
+
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                class_name.as_ptr(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                ptr::null_mut(),
+                instance,
+                ptr::null_mut(),
+            );
+            if hwnd.is_null() {
+                let _ = tx.send(Err(std::io::Error::last_os_error()));
+                return;
+            }
+
+            let mut data: NOTIFYICONDATAW = std::mem::zeroed();
+            data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+            data.hWnd = hwnd;
+            data.uID = 1;
+            data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+            data.uCallbackMessage = WM_TRAY_ICON;
+            data.hIcon = LoadIconW(ptr::null_mut(), IDI_APPLICATION);
+            let len = tooltip.len().min(data.szTip.len() - 1);
+            data.szTip[..len].copy_from_slice(&tooltip[..len]);
+            if Shell_NotifyIconW(NIM_ADD, &mut data) == 0 {
+                let _ = tx.send(Err(std::io::Error::last_os_error()));
+                DestroyWindow(hwnd);
+                return;
+            }
+
+            let _ = tx.send(Ok(GetCurrentThreadId()));
+
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            Shell_NotifyIconW(NIM_DELETE, &mut data);
+            DestroyWindow(hwnd);
+        });
+
+        let thread_id = rx
+            .recv()?
+            .map_err(|err| anyhow!("Failed to add the tray icon, error: {}", err))?;
+        Ok(Self { thread_id })
+    }
+
+    /// Returns whether the icon was clicked since the last call.
+    pub fn clicked(&self) -> bool {
+        CLICKED.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+    }
+}
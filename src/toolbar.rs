@@ -0,0 +1,59 @@
+use imgui::*;
+
+use crate::support::System;
+
+pub enum ToolbarAction {
+    NewTab,
+    Search,
+    Cancel,
+    Settings,
+    Export,
+}
+
+pub struct Toolbar {
+    buttons: Vec<(TextureId, &'static str, ToolbarAction)>,
+}
+
+impl Toolbar {
+    pub fn load(system: &mut System) -> Self {
+        let icons: [(&[u8], &'static str, ToolbarAction); 5] = [
+            (include_bytes!("../resources/icons/new-tab.png"), "New tab (Ctrl+T)", ToolbarAction::NewTab),
+            (include_bytes!("../resources/icons/search.png"), "Search", ToolbarAction::Search),
+            (include_bytes!("../resources/icons/cancel.png"), "Cancel search (Esc)", ToolbarAction::Cancel),
+            (include_bytes!("../resources/icons/settings.png"), "Settings", ToolbarAction::Settings),
+            (include_bytes!("../resources/icons/export.png"), "Export the results to the clipboard", ToolbarAction::Export),
+        ];
+
+        let mut buttons = Vec::with_capacity(icons.len());
+        for (data, tooltip, action) in icons.into_iter() {
+            match system.load_texture(data) {
+                Ok(texture_id) => buttons.push((texture_id, tooltip, action)),
+                Err(err) => log!("Failed to load the icon for '{}', error: {}", tooltip, err),
+            }
+        }
+
+        Self { buttons }
+    }
+
+    pub fn draw(&self, ui: &Ui) -> Option<&ToolbarAction> {
+        // The icons are white, such that they can be tinted with the color of the text of the style.
+        let tint = ui.style_color(StyleColor::Text);
+
+        let mut clicked = None;
+        for (idx, (texture_id, tooltip, action)) in self.buttons.iter().enumerate() {
+            if idx != 0 {
+                ui.same_line();
+            }
+
+            if ImageButton::new(*texture_id, [16.0, 16.0]).tint_col(tint).build(ui) {
+                clicked = Some(action);
+            }
+
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tooltip);
+            }
+        }
+
+        clicked
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+use crate::sys;
+
+const MAX_RECENT_WORKSPACES: usize = 10;
+const MAX_WORKSPACE_SUMMARIES: usize = 50;
+
+/// Last search done in a workspace, restored in the first tab when starting in it again.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkspaceSummary {
+    /// Canonical path of the workspace.
+    pub workspace: String,
+    pub paths: String,
+    pub globs: String,
+    pub query: String,
+}
+
+/// Make `path` the current directory, which is the default path of new tabs.
+pub fn set_workspace(path: &str) -> Result<PathBuf> {
+    let path = std::fs::canonicalize(path)
+        .map_err(|err| anyhow!("Can't open workspace '{}', error: {}", path, err))?;
+    std::env::set_current_dir(&path)
+        .map_err(|err| anyhow!("Can't open workspace '{}', error: {}", path.to_string_lossy(), err))?;
+    log!("Opened workspace '{}'", path.to_string_lossy());
+    return Ok(path);
+}
+
+/// Move `path` at the front of the recent workspaces, dropping the oldest ones.
+pub fn push_recent_workspace(recent: &mut Vec<String>, path: String) {
+    recent.retain(|value| *value != path);
+    recent.insert(0, path);
+    recent.truncate(MAX_RECENT_WORKSPACES);
+}
+
+/// Canonical path of the current directory, which is the workspace.
+pub fn current_workspace() -> Option<String> {
+    let path = std::env::current_dir().and_then(std::fs::canonicalize).ok()?;
+    return Some(path.to_string_lossy().into_owned());
+}
+
+/// Replace the summary of the workspace of `summary`, most recent first, dropping the oldest ones.
+pub fn push_workspace_summary(summaries: &mut Vec<WorkspaceSummary>, summary: WorkspaceSummary) {
+    summaries.retain(|value| value.workspace != summary.workspace);
+    summaries.insert(0, summary);
+    summaries.truncate(MAX_WORKSPACE_SUMMARIES);
+}
+
+pub fn find_workspace_summary<'a>(summaries: &'a [WorkspaceSummary], workspace: &str) -> Option<&'a WorkspaceSummary> {
+    summaries.iter().find(|summary| summary.workspace == workspace)
+}
+
+pub struct WorkspaceWindow {
+    opened: bool,
+    path: String,
+    error: Option<String>,
+    /// Folder picked with "Browse...", received once the dialog is closed.
+    picked_folder: Option<Receiver<Result<Option<PathBuf>>>>,
+}
+
+impl WorkspaceWindow {
+    pub fn new() -> Self {
+        Self { opened: false, path: String::new(), error: None, picked_folder: None }
+    }
+
+    /// Fill the path with the picked folder, once the dialog is closed.
+    fn receive_picked_folder(&mut self) {
+        let picked = match self.picked_folder.as_ref().map(|picked| picked.try_recv()) {
+            Some(Ok(picked)) => picked,
+            Some(Err(TryRecvError::Disconnected)) => Ok(None),
+            Some(Err(TryRecvError::Empty)) | None => return,
+        };
+
+        self.picked_folder = None;
+        match picked {
+            Ok(Some(path)) => {
+                self.path = path.to_string_lossy().into_owned();
+                self.error = None;
+            }
+            Ok(None) => (),
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    pub fn open(&mut self, path: String, error: Option<String>) {
+        self.opened = true;
+        self.path = path;
+        self.error = error;
+    }
+
+    /// Returns the path of the workspace to open, once the user confirmed it.
+    pub fn draw_workspace(&mut self, ui: &Ui) -> Option<String> {
+        if !self.opened {
+            return None;
+        }
+
+        self.receive_picked_folder();
+        let display_size = ui.io().display_size;
+        let window_size = [600.0, 120.0];
+        let pos_x = (display_size[0] / 2.0) - (window_size[0] / 2.0);
+        let pos_y = (display_size[1] / 2.0) - (window_size[1] / 2.0);
+
+        let window = ui
+            .window("Open Workspace")
+            .size(window_size, Condition::Appearing)
+            .position([pos_x, pos_y], Condition::Appearing)
+            .collapsible(false)
+            .opened(&mut self.opened);
+
+        let mut confirmed = false;
+        window.build(|| {
+            ui.text("Directory:");
+            let browse_width = ui.calc_text_size("Browse...")[0] + 2.0 * unsafe { ui.style() }.frame_padding[0];
+            let _w = ui.push_item_width(-(browse_width + unsafe { ui.style() }.item_spacing[0]));
+            confirmed |= ui
+                .input_text("##workspace-path", &mut self.path)
+                .enter_returns_true(true)
+                .build();
+
+            ui.same_line();
+            // Unless the dialog is already opened.
+            if ui.button("Browse...") && self.picked_folder.is_none() {
+                self.picked_folder = Some(sys::spawn_pick_folder());
+            }
+
+            confirmed |= ui.button("Open");
+
+            if let Some(error) = &self.error {
+                ui.text_colored([1.0, 0.0, 0.0, 1.0], error);
+            }
+        });
+
+        if !confirmed {
+            return None;
+        }
+
+        self.opened = false;
+        return Some(self.path.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_workspaces_are_unique_and_bounded() {
+        let mut recent = Vec::new();
+        for idx in 0..(MAX_RECENT_WORKSPACES + 2) {
+            push_recent_workspace(&mut recent, idx.to_string());
+        }
+        assert_eq!(recent.len(), MAX_RECENT_WORKSPACES);
+        assert_eq!(recent[0], (MAX_RECENT_WORKSPACES + 1).to_string());
+
+        push_recent_workspace(&mut recent, String::from("5"));
+        assert_eq!(recent.len(), MAX_RECENT_WORKSPACES);
+        assert_eq!(recent[0], "5");
+        assert_eq!(recent.iter().filter(|value| *value == "5").count(), 1);
+    }
+
+    #[test]
+    fn one_summary_per_workspace() {
+        let summary = |workspace: &str, query: &str| WorkspaceSummary {
+            workspace: workspace.to_owned(),
+            paths: String::from("."),
+            globs: String::new(),
+            query: query.to_owned(),
+        };
+
+        let mut summaries = Vec::new();
+        push_workspace_summary(&mut summaries, summary("/a", "first"));
+        push_workspace_summary(&mut summaries, summary("/b", "other"));
+        push_workspace_summary(&mut summaries, summary("/a", "second"));
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0], summary("/a", "second"));
+        assert_eq!(find_workspace_summary(&summaries, "/b"), Some(&summary("/b", "other")));
+        assert_eq!(find_workspace_summary(&summaries, "/c"), None);
+
+        for idx in 0..(MAX_WORKSPACE_SUMMARIES + 2) {
+            push_workspace_summary(&mut summaries, summary(&idx.to_string(), "query"));
+        }
+        assert_eq!(summaries.len(), MAX_WORKSPACE_SUMMARIES);
+    }
+}